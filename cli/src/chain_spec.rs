@@ -16,7 +16,10 @@ use sp_runtime::traits::{IdentifyAccount, Verify};
 
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 
-use chainx_dev_runtime::constants::{currency::DOLLARS as DEV_DOLLARS, time::DAYS as DEV_DAYS};
+use chainx_dev_runtime::constants::{
+    currency::DOLLARS as DEV_DOLLARS,
+    time::{DAYS as DEV_DAYS, HOURS as DEV_HOURS, MINUTES as DEV_MINUTES},
+};
 use chainx_primitives::{AccountId, AssetId, Balance, ReferralId, Signature};
 use chainx_runtime::constants::currency::DOLLARS;
 use xp_assets_registrar::Chain;
@@ -412,7 +415,7 @@ fn build_genesis(
             sessions_per_era: 12,
             vesting_account,
             glob_dist_ratio: (12, 88), // (Treasury, X-type Asset and Staking) = (12, 88)
-            mining_ratio: (10, 90),    // (Asset Mining, Staking) = (10, 90)
+            mining_ratio: (10, 90, 0), // (Asset Mining, Staking, DEX Mining) = (10, 90, 0)
             minimum_penalty: 2 * DOLLARS,
             ..Default::default()
         }),
@@ -422,6 +425,7 @@ fn build_genesis(
         }),
         xpallet_dex_spot: Some(chainx_dev::XSpotConfig {
             trading_pairs: vec![(PCX, X_BTC, 9, 2, 100000, true)],
+            kline_intervals: vec![DEV_MINUTES, DEV_HOURS, DEV_DAYS],
         }),
         xpallet_genesis_builder: Some(chainx_dev::XGenesisBuilderConfig {
             params: crate::genesis::genesis_builder_params(),
@@ -70,6 +70,9 @@ pub struct RunCmd {
 
     #[structopt(flatten)]
     pub logger: crate::logger::LoggerParams,
+
+    #[structopt(flatten)]
+    pub relayer: crate::relayer::RelayerParams,
 }
 
 impl Cli {
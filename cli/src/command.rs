@@ -93,10 +93,16 @@ pub fn run() -> sc_cli::Result<()> {
             let chain_spec = &runner.config().chain_spec;
             set_default_ss58_version(chain_spec);
 
+            let relayer = cli.run.relayer.config()?;
             runner.run_node_until_exit(|config| async move {
                 match config.role {
-                    Role::Light => service::new_light(config),
-                    _ => service::new_full(config),
+                    Role::Light => {
+                        if relayer.is_some() {
+                            log::warn!("The bitcoin relayer is unavailable for the light client");
+                        }
+                        service::new_light(config)
+                    }
+                    _ => service::new_full(config, relayer),
                 }
             })
         }
@@ -11,6 +11,7 @@ mod command;
 mod config;
 mod genesis;
 mod logger;
+mod relayer;
 mod service;
 
 pub use sc_cli::Result;
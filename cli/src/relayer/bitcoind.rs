@@ -0,0 +1,328 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! A minimal client of the Bitcoin Core JSON-RPC interface.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use hyper::{
+    client::HttpConnector,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    Body, Client, Method, Request, StatusCode, Uri,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use light_bitcoin::{
+    chain::{BlockHeader as BtcHeader, Transaction},
+    merkle::PartialMerkleTree,
+    primitives::{hash_rev, H256},
+    serialization::{deserialize, Deserializable},
+};
+
+use super::{Error, Result};
+
+/// The size of the serialized bitcoin block header.
+const HEADER_SIZE: usize = 80;
+
+/// The bitcoin data source of the relayer.
+///
+/// All the hashes are in the internal byte order, i.e. the same as the ones stored in
+/// the bitcoin gateway, the conversion from/to the RPC (reversed) order is up to the
+/// implementation.
+#[async_trait::async_trait]
+pub trait BitcoinApi: Send + Sync {
+    /// Returns the height of the best block.
+    async fn block_count(&self) -> Result<u32>;
+
+    /// Returns the hash of the block at `height` in the best chain.
+    async fn block_hash(&self, height: u32) -> Result<H256>;
+
+    /// Returns the block header of `hash`.
+    async fn block_header(&self, hash: H256) -> Result<BtcHeader>;
+
+    /// Returns all the transactions of the block `hash`.
+    async fn block_transactions(&self, hash: H256) -> Result<Vec<Transaction>>;
+
+    /// Returns the transaction `txid`, it requires `-txindex` if the transaction
+    /// isn't in the mempool.
+    async fn raw_transaction(&self, txid: H256) -> Result<Transaction>;
+
+    /// Returns the merkle proof that the transaction `txid` is included in the block `hash`.
+    async fn merkle_proof(&self, txid: H256, hash: H256) -> Result<PartialMerkleTree>;
+}
+
+#[derive(Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RpcBlock {
+    tx: Vec<RpcTransaction>,
+}
+
+#[derive(Deserialize)]
+struct RpcTransaction {
+    hex: String,
+}
+
+/// The Bitcoin Core JSON-RPC client over HTTP.
+pub struct Bitcoind {
+    client: Client<HttpConnector>,
+    url: Uri,
+    /// The value of the `Authorization` header, `None` if no credential is provided.
+    auth: Option<String>,
+    request_id: AtomicU64,
+}
+
+impl Bitcoind {
+    /// Creates a client of the bitcoind listening on `url`, e.g. `http://127.0.0.1:18443`,
+    /// with the optional `user` and `password` of the RPC authentication.
+    pub fn new(url: Uri, user: Option<String>, password: Option<String>) -> Self {
+        let auth = user.map(|user| {
+            let credential = format!("{}:{}", user, password.unwrap_or_default());
+            format!("Basic {}", base64::encode(credential))
+        });
+        Self {
+            client: Client::new(),
+            url,
+            auth,
+            request_id: AtomicU64::new(0),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Vec<Value>) -> Result<T> {
+        let request = RpcRequest {
+            jsonrpc: "1.0",
+            id: self.request_id.fetch_add(1, Ordering::Relaxed),
+            method,
+            params,
+        };
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.clone())
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(auth) = &self.auth {
+            builder = builder.header(AUTHORIZATION, auth.as_str());
+        }
+        let request = builder.body(Body::from(serde_json::to_vec(&request)?))?;
+
+        let response = self.client.request(request).await?;
+        // bitcoind replies the RPC errors with the status code 404 or 500 and the error
+        // in the body, but nothing in the body for an authentication failure.
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(Error::Unauthorized);
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let response: RpcResponse<T> = serde_json::from_slice(&body)?;
+        match (response.result, response.error) {
+            (_, Some(err)) => Err(Error::Rpc(err.code, err.message)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(Error::Rpc(0, format!("`{}` returns null", method))),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BitcoinApi for Bitcoind {
+    async fn block_count(&self) -> Result<u32> {
+        self.call("getblockcount", vec![]).await
+    }
+
+    async fn block_hash(&self, height: u32) -> Result<H256> {
+        let hash: String = self.call("getblockhash", vec![json!(height)]).await?;
+        decode_hash(&hash)
+    }
+
+    async fn block_header(&self, hash: H256) -> Result<BtcHeader> {
+        let params = vec![json!(encode_hash(hash)), json!(false)];
+        let header: String = self.call("getblockheader", params).await?;
+        decode_hex(&header)
+    }
+
+    async fn block_transactions(&self, hash: H256) -> Result<Vec<Transaction>> {
+        let params = vec![json!(encode_hash(hash)), json!(2)];
+        let block: RpcBlock = self.call("getblock", params).await?;
+        block.tx.iter().map(|tx| decode_hex(&tx.hex)).collect()
+    }
+
+    async fn raw_transaction(&self, txid: H256) -> Result<Transaction> {
+        let params = vec![json!(encode_hash(txid)), json!(false)];
+        let tx: String = self.call("getrawtransaction", params).await?;
+        decode_hex(&tx)
+    }
+
+    async fn merkle_proof(&self, txid: H256, hash: H256) -> Result<PartialMerkleTree> {
+        let params = vec![json!([encode_hash(txid)]), json!(encode_hash(hash))];
+        let proof: String = self.call("gettxoutproof", params).await?;
+        // the proof is a serialized merkle block, i.e. the block header followed by
+        // the partial merkle tree.
+        let proof = hex::decode(proof)?;
+        if proof.len() < HEADER_SIZE {
+            return Err(Error::Deserialize(format!(
+                "Invalid merkle proof length: {}",
+                proof.len()
+            )));
+        }
+        deserialize(&proof[HEADER_SIZE..])
+            .map_err(|err| Error::Deserialize(format!("Invalid merkle proof: {:?}", err)))
+    }
+}
+
+/// Encodes the block hash or the transaction id as the hex string in the RPC (reversed) order.
+fn encode_hash(hash: H256) -> String {
+    hex::encode(hash_rev(hash).as_bytes())
+}
+
+/// Decodes the hex string of the block hash or the transaction id in the RPC (reversed) order.
+fn decode_hash(hash: &str) -> Result<H256> {
+    let bytes = hex::decode(hash)?;
+    if bytes.len() != 32 {
+        return Err(Error::Deserialize(format!(
+            "Invalid hash length: {}",
+            bytes.len()
+        )));
+    }
+    Ok(hash_rev(H256::from_slice(&bytes)))
+}
+
+/// Decodes the hex string of the serialized bitcoin data, e.g. block header and transaction.
+fn decode_hex<T: Deserializable>(data: &str) -> Result<T> {
+    let bytes = hex::decode(data)?;
+    deserialize(bytes.as_slice())
+        .map_err(|err| Error::Deserialize(format!("Invalid bitcoin data: {:?}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use light_bitcoin::primitives::h256_rev;
+
+    use super::*;
+    use crate::relayer::mock::{
+        with_bitcoind, GENESIS_HASH, GENESIS_HEADER, MERKLE_PROOF, PASSWORD, TX, TXID,
+    };
+
+    fn not_found() -> std::result::Result<Value, (i64, String)> {
+        Err((-5, "Block not found".into()))
+    }
+
+    #[test]
+    fn test_bitcoind_block() {
+        let handler = |method: &str, params: &[Value]| match (method, params) {
+            ("getblockcount", []) => Ok(json!(0)),
+            ("getblockhash", [height]) if *height == json!(0) => Ok(json!(GENESIS_HASH)),
+            ("getblockheader", [hash, verbose]) if *hash == json!(GENESIS_HASH) => {
+                assert_eq!(*verbose, json!(false));
+                Ok(json!(GENESIS_HEADER))
+            }
+            ("getblock", [hash, verbosity]) if *hash == json!(GENESIS_HASH) => {
+                assert_eq!(*verbosity, json!(2));
+                Ok(json!({ "hash": GENESIS_HASH, "tx": [{ "txid": TXID, "hex": TX }] }))
+            }
+            _ => not_found(),
+        };
+        with_bitcoind(handler, PASSWORD, |bitcoind| async move {
+            let genesis_hash = h256_rev(GENESIS_HASH);
+
+            assert_eq!(bitcoind.block_count().await.unwrap(), 0);
+            // the hashes are in the internal byte order
+            assert_eq!(bitcoind.block_hash(0).await.unwrap(), genesis_hash);
+            let header = bitcoind.block_header(genesis_hash).await.unwrap();
+            assert_eq!(header.hash(), genesis_hash);
+            let txs = bitcoind.block_transactions(genesis_hash).await.unwrap();
+            assert_eq!(
+                txs.iter().map(Transaction::hash).collect::<Vec<_>>(),
+                vec![h256_rev(TXID)]
+            );
+
+            // the reversed hash is not found
+            let result = bitcoind.block_header(hash_rev(genesis_hash)).await;
+            assert!(matches!(result, Err(Error::Rpc(-5, _))));
+        });
+    }
+
+    #[test]
+    fn test_bitcoind_transaction() {
+        let handler = |method: &str, params: &[Value]| match (method, params) {
+            ("getrawtransaction", [txid, verbose]) if *txid == json!(TXID) => {
+                assert_eq!(*verbose, json!(false));
+                Ok(json!(TX))
+            }
+            ("getrawtransaction", _) => {
+                Err((-5, "No such mempool or blockchain transaction".into()))
+            }
+            ("gettxoutproof", [txids, hash]) if *txids == json!([TXID]) => {
+                assert_eq!(*hash, json!(GENESIS_HASH));
+                Ok(json!(format!("{}{}", GENESIS_HEADER, MERKLE_PROOF)))
+            }
+            ("gettxoutproof", [_, hash]) => Ok(json!(hash)),
+            _ => not_found(),
+        };
+        with_bitcoind(handler, PASSWORD, |bitcoind| async move {
+            let txid = h256_rev(TXID);
+            let genesis_hash = h256_rev(GENESIS_HASH);
+
+            let tx = bitcoind.raw_transaction(txid).await.unwrap();
+            assert_eq!(tx.hash(), txid);
+            let result = bitcoind.raw_transaction(genesis_hash).await;
+            assert!(matches!(result, Err(Error::Rpc(-5, _))));
+
+            let proof = bitcoind.merkle_proof(txid, genesis_hash).await.unwrap();
+            let mut matches = Vec::new();
+            let mut indexes = Vec::new();
+            let root = proof.extract_matches(&mut matches, &mut indexes).unwrap();
+            let expected_root = "ad7dc74602363a2889ba790fcc912ebd86e5b19cc0847ca6239fd5734202a81c";
+            assert_eq!(root, H256::from_slice(&hex::decode(expected_root).unwrap()));
+            assert_eq!(matches, vec![txid]);
+            assert_eq!(indexes, vec![1]);
+
+            // the proof shorter than the block header
+            let result = bitcoind.merkle_proof(genesis_hash, genesis_hash).await;
+            assert!(matches!(result, Err(Error::Deserialize(_))));
+        });
+    }
+
+    #[test]
+    fn test_bitcoind_error() {
+        let handler = |method: &str, params: &[Value]| match (method, params) {
+            ("getblockhash", [height]) if *height == json!(0) => Ok(json!("00")),
+            ("getblockhash", _) => Err((-8, "Block height out of range".into())),
+            ("getblockcount", _) => Ok(Value::Null),
+            _ => not_found(),
+        };
+        with_bitcoind(handler, PASSWORD, |bitcoind| async move {
+            let result = bitcoind.block_hash(1).await;
+            assert!(matches!(result, Err(Error::Rpc(-8, _))));
+            // the invalid hash
+            let result = bitcoind.block_hash(0).await;
+            assert!(matches!(result, Err(Error::Deserialize(_))));
+            // the null result
+            let result = bitcoind.block_count().await;
+            assert!(matches!(result, Err(Error::Rpc(0, _))));
+        });
+
+        with_bitcoind(
+            |_: &str, _: &[Value]| not_found(),
+            "wrong password",
+            |bitcoind| async move {
+                let result = bitcoind.block_count().await;
+                assert!(matches!(result, Err(Error::Unauthorized)));
+            },
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! The mocked Bitcoin Core JSON-RPC server for the relayer tests.
+
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    header::AUTHORIZATION,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use serde_json::{json, Value};
+
+use super::Bitcoind;
+
+/// The credential of the JSON-RPC authentication accepted by the mocked bitcoind.
+pub const USER: &str = "user";
+pub const PASSWORD: &str = "password";
+
+/// The genesis block of the bitcoin mainnet.
+pub const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+pub const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+// https://btc.com/62c389f1974b8a44737d76f92da0f5cd7f6f48d065e7af6ba368298361141270.rawhex
+pub const TXID: &str = "62c389f1974b8a44737d76f92da0f5cd7f6f48d065e7af6ba368298361141270";
+pub const TX: &str = "0100000001052ceda6cf9c93012a994f4ffa2a29c9e31ecf96f472b175eb8e602bfa2b2c5100000000fdfd000047304402200e4d732c456f4722d376252be16554edb27fc93c55db97859e16682bc62b014502202b9c4b01ad55daa1f76e6a564b7762cd0a81240c947806ab3f3b056f2e77c1da01483045022100c7cd680992de60da8c33fc3ef7f5ead85b204660822d9fbda2d85f9fadba732a022021fdc49b20a6007ea971a385732a4065d1d7c792ac9dc391034fb78aa9f5034b014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff03e0349500000000001976a91413256ff2dee6e80c275ddb877abc1ffe453a731488ace00f9700000000001976a914ea6e8dd56703ace584eb9dff0224629f8486672988acc88a02000000000017a914cb94110435d0635223eebe25ed2aaabc03781c458700000000";
+/// The transaction paying to the P2SH addresses other than the ones of `TX`.
+pub const UNRELATED_TX: &str = "0100000001abbd850cf083bbfa367081718c7efd911e56ffd849ae48e812c861adf253ef6101000000fd1b010048304502210081d668bf752424c89e208cf9789e7449c080a2cd9fda6a518ac36d81e5d760dd02206353eaad7e587602ab6665a5788a2c831e9d08bf8685dd250370dbd978a54665014ccf542102e2b2720a9e54617ba87fca287c3d7f9124154d30fa8dc9cd260b6b254e1d7aea210219fc860933a1362bc5e0a0bbe1b33a47aedf904765f4a85cd166ba1d767927ee2102b921cb319a14c6887b12cee457453f720e88808a735a578d6c57aba0c74e5af32102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210346aa7ade0b567b34182cacf9444deb44ee829e14705dc87175107dd09d5dbf4021034d3e7f87e69c6c71df6052b44f9ed99a3d811613140ebf09f8fdaf904a2e1de856aeffffffff03d2622000000000001976a914b9944df543bc909b527351311c5a01a78a3271e788acff40330e0000000017a9149079c3650e5a9799afa552cbbcc280e45d52117c8777d778e50000000017a914d246f700f4969106291a75ba85ad863cae68d6678700000000";
+/// The merkle block (without the block header) of `UNRELATED_TX` and `TX`, in which `TX` matches.
+pub const MERKLE_PROOF: &str = "0200000002b35d79d3c6b62f26fbc87632fa5250646110ffc0e0a5625da5d8a919d292367470121461832968a36bafe765d0486f7fcdf5a02df9767d73448a4b97f189c3620105";
+
+/// Runs `test` with the client of a mocked bitcoind on localhost, which serves the JSON-RPC
+/// calls by `handler` and rejects the clients not authenticated by `PASSWORD`.
+///
+/// `handler` returns the result of the call `(method, params)`, or the error `(code, message)`.
+pub fn with_bitcoind<H, F, Fut>(handler: H, password: &str, test: F)
+where
+    H: Fn(&str, &[Value]) -> Result<Value, (i64, String)> + Send + Sync + 'static,
+    F: FnOnce(Bitcoind) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut runtime = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async move {
+        let handler = Arc::new(handler);
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| handle(handler.clone(), request)))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+
+        test(Bitcoind::new(url, Some(USER.into()), Some(password.into()))).await
+    })
+}
+
+async fn handle<H>(handler: Arc<H>, request: Request<Body>) -> Result<Response<Body>, Infallible>
+where
+    H: Fn(&str, &[Value]) -> Result<Value, (i64, String)>,
+{
+    let auth = format!("Basic {}", base64::encode(format!("{}:{}", USER, PASSWORD)));
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        == Some(auth.as_str());
+    if !authorized {
+        // bitcoind replies nothing but the status code for an authentication failure.
+        let response = Response::builder().status(StatusCode::UNAUTHORIZED);
+        return Ok(response.body(Body::empty()).unwrap());
+    }
+
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+    let request: Value = serde_json::from_slice(&body).unwrap();
+    let method = request["method"].as_str().unwrap_or_default();
+    let params = request["params"].as_array().cloned().unwrap_or_default();
+    let (status, response) = match handler(method, &params) {
+        Ok(result) => (
+            StatusCode::OK,
+            json!({ "result": result, "error": null, "id": request["id"] }),
+        ),
+        Err((code, message)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            json!({
+                "result": null,
+                "error": { "code": code, "message": message },
+                "id": request["id"],
+            }),
+        ),
+    };
+    Ok(Response::builder()
+        .status(status)
+        .body(Body::from(response.to_string()))
+        .unwrap())
+}
@@ -0,0 +1,922 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! The optional bitcoin relayer built into the full node.
+//!
+//! The bitcoin gateway only learns about the bitcoin chain through `push_header` and
+//! `push_transaction`. Once enabled by `--btc-relayer-url`, the relayer periodically:
+//!
+//! 1. pushes the bitcoin headers missing in the gateway, from the fork point of `BestIndex`
+//!    and the main chain of bitcoind forward.
+//! 2. scans the newly confirmed blocks and pushes the transactions related to the current
+//!    trustee session, i.e. paying to the hot/cold address or being the pending withdrawal,
+//!    together with the merkle proof and the previous transaction.
+//!
+//! The relay transactions are signed by the account in `--btc-relayer-signer-file` and submitted
+//! to the local transaction pool, the signer account has to afford the transaction fee of
+//! `push_header`.
+
+mod bitcoind;
+#[cfg(test)]
+mod mock;
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use codec::{Decode, Encode};
+use log::{debug, info, warn};
+use structopt::StructOpt;
+
+use sc_network::NetworkService;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{sr25519, Pair};
+use sp_runtime::{
+    generic::{BlockId, Era},
+    traits::{Block as BlockT, StaticLookup},
+};
+use sp_transaction_pool::{TransactionPool, TransactionSource};
+
+use light_bitcoin::{
+    chain::{BlockHeader as BtcHeader, Transaction},
+    keys::{Address, Network},
+    merkle::PartialMerkleTree,
+    primitives::{hash_rev, H256},
+    serialization::serialize,
+};
+
+use chainx_primitives::{AccountId, Balance, Block, Index};
+use chainx_runtime::{
+    impls::ChargeExtraFee, BaseFilter, Call, Chain, Indices, Runtime, SignedExtra, SignedPayload,
+    UncheckedExtrinsic,
+};
+use frame_system_rpc_runtime_api::AccountNonceApi;
+use xp_gateway_bitcoin::{extract_output_addr, Destination, WitnessAddress};
+use xpallet_gateway_bitcoin::{BtcHeaderIndex, BtcRelayedTxInfo};
+use xpallet_gateway_bitcoin_rpc_runtime_api::XGatewayBitcoinApi;
+use xpallet_gateway_common::trustees::bitcoin::BtcTrusteeSessionInfo;
+use xpallet_gateway_common_rpc_runtime_api::XGatewayCommonApi;
+
+pub use self::bitcoind::{BitcoinApi, Bitcoind};
+
+const LOG_TARGET: &str = "btc-relayer";
+
+/// The maximum number of headers pushed in one round.
+const MAX_HEADERS_PER_ROUND: u32 = 100;
+/// The maximum number of confirmed blocks scanned in one round.
+const MAX_BLOCKS_PER_ROUND: u32 = 10;
+/// The maximum depth of the bitcoin fork the relayer could recover from.
+const MAX_FORK_DEPTH: u32 = 144;
+/// The number of rounds to wait for the pending relay transactions before
+/// assuming they are dropped by the transaction pool.
+const MAX_PENDING_ROUNDS: u32 = 10;
+
+#[derive(Debug, StructOpt)]
+pub struct RelayerParams {
+    /// Enable the bitcoin relayer with the JSON-RPC endpoint of Bitcoin Core.
+    ///
+    /// e.g. `http://127.0.0.1:18443` for a local regtest bitcoind, the `-txindex` of
+    /// bitcoind is required for fetching the previous transaction of the deposit.
+    #[structopt(long, value_name = "URL")]
+    pub btc_relayer_url: Option<String>,
+
+    /// The user of the Bitcoin Core JSON-RPC authentication.
+    #[structopt(long, value_name = "USER")]
+    pub btc_relayer_user: Option<String>,
+
+    /// The file containing the password of the Bitcoin Core JSON-RPC authentication.
+    ///
+    /// The secrets are read from the files, which are not visible in the process list
+    /// like the command line arguments.
+    #[structopt(long, value_name = "PATH", parse(from_os_str))]
+    pub btc_relayer_password_file: Option<PathBuf>,
+
+    /// The file containing the secret key URI of the account signing the relay transactions,
+    /// e.g. `//Alice`.
+    #[structopt(long, value_name = "PATH", parse(from_os_str))]
+    pub btc_relayer_signer_file: Option<PathBuf>,
+
+    /// The interval (in seconds) between two relay rounds.
+    #[structopt(long, value_name = "SECONDS", default_value = "30")]
+    pub btc_relayer_interval: u64,
+
+    /// Scan the transactions from this bitcoin block height.
+    ///
+    /// By default the relayer only scans the blocks confirmed after it starts.
+    #[structopt(long, value_name = "HEIGHT")]
+    pub btc_relayer_scan_from: Option<u32>,
+}
+
+impl RelayerParams {
+    /// Returns the relayer configuration, `None` if the relayer is disabled.
+    pub fn config(&self) -> sc_cli::Result<Option<RelayerConfig>> {
+        let url = match &self.btc_relayer_url {
+            Some(url) => url
+                .parse()
+                .map_err(|err| sc_cli::Error::Input(format!("Invalid relayer url: {}", err)))?,
+            None => return Ok(None),
+        };
+        let signer = self.btc_relayer_signer_file.as_deref().ok_or_else(|| {
+            sc_cli::Error::Input("`--btc-relayer-signer-file` is required by the relayer".into())
+        })?;
+        let signer = sr25519::Pair::from_string(&read_secret(signer)?, None)
+            .map_err(|err| sc_cli::Error::Input(format!("Invalid relayer signer: {:?}", err)))?;
+        let password = self
+            .btc_relayer_password_file
+            .as_deref()
+            .map(read_secret)
+            .transpose()?;
+
+        Ok(Some(RelayerConfig {
+            bitcoind: Bitcoind::new(url, self.btc_relayer_user.clone(), password),
+            signer,
+            interval: Duration::from_secs(self.btc_relayer_interval),
+            scan_from: self.btc_relayer_scan_from,
+        }))
+    }
+}
+
+/// Reads the secret from the file `path`, ignoring the trailing newline.
+fn read_secret(path: &Path) -> sc_cli::Result<String> {
+    let secret = std::fs::read_to_string(path).map_err(|err| {
+        sc_cli::Error::Input(format!(
+            "Failed to read the relayer secret from {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    Ok(secret.trim_end().to_string())
+}
+
+/// The configuration of the bitcoin relayer.
+pub struct RelayerConfig {
+    pub bitcoind: Bitcoind,
+    pub signer: sr25519::Pair,
+    pub interval: Duration,
+    pub scan_from: Option<u32>,
+}
+
+/// The error of the bitcoin relayer.
+#[derive(Debug)]
+pub enum Error {
+    /// The HTTP request to bitcoind is invalid.
+    Request(hyper::http::Error),
+    /// Failed to send the HTTP request to bitcoind.
+    Transport(hyper::Error),
+    /// bitcoind rejects the JSON-RPC credential.
+    Unauthorized,
+    /// bitcoind returns the JSON-RPC error.
+    Rpc(i64, String),
+    Json(serde_json::Error),
+    Hex(hex::FromHexError),
+    Deserialize(String),
+    /// The runtime API call fails.
+    Runtime(String),
+    /// The transaction pool rejects the relay transaction.
+    Pool(String),
+    /// No block of the bitcoind main chain within `MAX_FORK_DEPTH` has been relayed.
+    ForkTooDeep(BtcHeaderIndex),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Request(err) => write!(f, "Invalid bitcoind request: {}", err),
+            Error::Transport(err) => write!(f, "Bitcoind request failed: {}", err),
+            Error::Unauthorized => write!(f, "Bitcoind authentication failed"),
+            Error::Rpc(code, message) => write!(f, "Bitcoind error ({}): {}", code, message),
+            Error::Json(err) => write!(f, "Invalid bitcoind response: {}", err),
+            Error::Hex(err) => write!(f, "Invalid hex: {}", err),
+            Error::Deserialize(err) => write!(f, "{}", err),
+            Error::Runtime(err) => write!(f, "Runtime API error: {}", err),
+            Error::Pool(err) => write!(f, "Transaction pool error: {}", err),
+            Error::ForkTooDeep(best) => write!(
+                f,
+                "No common block with bitcoind since the best index (height: {}, hash: {:?})",
+                best.height,
+                hash_rev(best.hash)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<hyper::http::Error> for Error {
+    fn from(err: hyper::http::Error) -> Self {
+        Error::Request(err)
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Self {
+        Error::Transport(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<hex::FromHexError> for Error {
+    fn from(err: hex::FromHexError) -> Self {
+        Error::Hex(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+fn runtime_error<E: fmt::Debug>(err: E) -> Error {
+    Error::Runtime(format!("{:?}", err))
+}
+
+/// The bitcoin relayer service.
+pub struct Relayer<C, P, B> {
+    client: Arc<C>,
+    pool: Arc<P>,
+    network: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
+    bitcoind: B,
+    signer: sr25519::Pair,
+    interval: Duration,
+    /// The height of the next confirmed bitcoin block to scan.
+    next_scan_height: Option<u32>,
+    /// The nonce of the next relay transaction.
+    next_nonce: Index,
+    /// The number of rounds waiting for the pending relay transactions.
+    pending_rounds: u32,
+}
+
+impl<C, P> Relayer<C, P, Bitcoind> {
+    /// Creates the relayer from the configuration.
+    pub fn new(
+        config: RelayerConfig,
+        client: Arc<C>,
+        pool: Arc<P>,
+        network: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
+    ) -> Self {
+        Self {
+            client,
+            pool,
+            network,
+            bitcoind: config.bitcoind,
+            signer: config.signer,
+            interval: config.interval,
+            next_scan_height: config.scan_from,
+            next_nonce: 0,
+            pending_rounds: 0,
+        }
+    }
+}
+
+impl<C, P, B> Relayer<C, P, B>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: sp_api::Core<Block>
+        + AccountNonceApi<Block, AccountId, Index>
+        + XGatewayCommonApi<Block, AccountId, Balance>
+        + XGatewayBitcoinApi<Block, AccountId>,
+    P: TransactionPool<Block = Block> + 'static,
+    B: BitcoinApi,
+{
+    /// Runs the relayer until the node exits.
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "Bitcoin relayer started, signer: {}",
+            self.signer.public()
+        );
+        loop {
+            futures_timer::Delay::new(self.interval).await;
+            // the state of the gateway is stale during the major sync.
+            if self.network.is_major_syncing() {
+                continue;
+            }
+            if let Err(err) = self.relay().await {
+                warn!(target: LOG_TARGET, "Relay failed: {}", err);
+            }
+        }
+    }
+
+    // NOTE: the runtime api instance can't be held across `.await`, since the relayer
+    // must be `Send` to be spawned.
+    async fn relay(&mut self) -> Result<()> {
+        let at = BlockId::hash(self.client.info().best_hash);
+
+        let who: AccountId = self.signer.public().into();
+        let nonce = self
+            .client
+            .runtime_api()
+            .account_nonce(&at, who)
+            .map_err(runtime_error)?;
+        if nonce < self.next_nonce && self.pending_rounds < MAX_PENDING_ROUNDS {
+            // wait for the relay transactions submitted in the previous rounds,
+            // for the new ones may depend on them.
+            self.pending_rounds += 1;
+            debug!(
+                target: LOG_TARGET,
+                "Waiting for the pending relay transactions, nonce: {}, next nonce: {}",
+                nonce,
+                self.next_nonce
+            );
+            return Ok(());
+        }
+        self.next_nonce = nonce;
+        self.pending_rounds = 0;
+
+        let best = self
+            .client
+            .runtime_api()
+            .best_index(&at)
+            .map_err(runtime_error)?;
+        let client = &self.client;
+        let headers = missing_headers(
+            &self.bitcoind,
+            best,
+            |hash| {
+                client
+                    .runtime_api()
+                    .header(&at, hash)
+                    .map(|header| header.is_some())
+                    .map_err(runtime_error)
+            },
+            MAX_HEADERS_PER_ROUND,
+        )
+        .await?;
+        for header in headers {
+            debug!(
+                target: LOG_TARGET,
+                "Push header: {:?}",
+                hash_rev(header.hash())
+            );
+            let call = xpallet_gateway_bitcoin::Call::push_header(serialize(&header).into());
+            self.submit(&at, Call::XGatewayBitcoin(call)).await?;
+        }
+
+        self.relay_transactions(&at).await
+    }
+
+    /// Pushes the related transactions in the newly confirmed blocks.
+    async fn relay_transactions(&mut self, at: &BlockId<Block>) -> Result<()> {
+        let confirmed = match self
+            .client
+            .runtime_api()
+            .confirmed_index(at)
+            .map_err(runtime_error)?
+        {
+            Some(confirmed) => confirmed,
+            None => return Ok(()),
+        };
+        let from = *self.next_scan_height.get_or_insert(confirmed.height);
+        let to = confirmed
+            .height
+            .min(from.saturating_add(MAX_BLOCKS_PER_ROUND - 1));
+        if from > to {
+            return Ok(());
+        }
+
+        let (network, trustee_addrs, proposal_txid) = {
+            let api = self.client.runtime_api();
+            let network = api.params(at).map_err(runtime_error)?.network;
+            let trustee_addrs = match api
+                .trustee_session_info(at, Chain::Bitcoin)
+                .map_err(runtime_error)?
+            {
+                Some(info) => trustee_addrs(info, network)?,
+                None => Vec::new(),
+            };
+            let proposal_txid = api
+                .withdrawal_proposal(at)
+                .map_err(runtime_error)?
+                .map(|details| details.proposal.tx.hash());
+            (network, trustee_addrs, proposal_txid)
+        };
+
+        for height in from..=to {
+            let block_hash = match self
+                .client
+                .runtime_api()
+                .header_at(at, height)
+                .map_err(runtime_error)?
+            {
+                Some(header) => header.hash,
+                None => break,
+            };
+            let client = &self.client;
+            let txs = related_transactions(
+                &self.bitcoind,
+                block_hash,
+                network,
+                &trustee_addrs,
+                proposal_txid,
+                |txid| {
+                    client
+                        .runtime_api()
+                        .tx_state(at, txid)
+                        .map(|tx_state| tx_state.is_some())
+                        .map_err(runtime_error)
+                },
+            )
+            .await?;
+            for (tx, merkle_proof, prev_tx) in txs {
+                info!(
+                    target: LOG_TARGET,
+                    "Push transaction: {:?}, block height: {}",
+                    hash_rev(tx.hash()),
+                    height
+                );
+                let call = xpallet_gateway_bitcoin::Call::push_transaction(
+                    serialize(&tx).into(),
+                    BtcRelayedTxInfo {
+                        block_hash,
+                        merkle_proof,
+                    },
+                    prev_tx.map(|prev_tx| serialize(&prev_tx).into()),
+                );
+                self.submit(at, Call::XGatewayBitcoin(call)).await?;
+            }
+            self.next_scan_height = Some(height + 1);
+        }
+        Ok(())
+    }
+
+    /// Signs the `call` with the next nonce and submits it to the transaction pool.
+    async fn submit(&mut self, at: &BlockId<Block>, call: Call) -> Result<()> {
+        let version = self
+            .client
+            .runtime_api()
+            .version(at)
+            .map_err(runtime_error)?;
+        let genesis_hash = self.client.info().genesis_hash;
+        let extra: SignedExtra = (
+            frame_system::CheckSpecVersion::<Runtime>::new(),
+            frame_system::CheckTxVersion::<Runtime>::new(),
+            frame_system::CheckGenesis::<Runtime>::new(),
+            frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+            frame_system::CheckNonce::<Runtime>::from(self.next_nonce),
+            frame_system::CheckWeight::<Runtime>::new(),
+            pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+            BaseFilter,
+            ChargeExtraFee,
+        );
+        let payload = SignedPayload::from_raw(
+            call,
+            extra,
+            (
+                version.spec_version,
+                version.transaction_version,
+                genesis_hash,
+                genesis_hash,
+                (),
+                (),
+                (),
+                (),
+                (),
+            ),
+        );
+        let signature = payload.using_encoded(|payload| self.signer.sign(payload));
+        let (call, extra, _) = payload.deconstruct();
+        let who: AccountId = self.signer.public().into();
+        let xt =
+            UncheckedExtrinsic::new_signed(call, Indices::unlookup(who), signature.into(), extra);
+        let xt = Decode::decode(&mut xt.encode().as_slice())
+            .map_err(|err| Error::Deserialize(format!("Invalid extrinsic: {:?}", err)))?;
+
+        self.pool
+            .submit_one(at, TransactionSource::Local, xt)
+            .await
+            .map_err(|err| Error::Pool(format!("{:?}", err)))?;
+        self.next_nonce += 1;
+        Ok(())
+    }
+}
+
+/// Returns the headers of the bitcoind main chain that haven't been relayed, starting from
+/// the latest relayed one of the main chain, at most `max_count` headers.
+///
+/// `is_relayed` returns whether the header has been pushed into the gateway, no matter
+/// whether it's in the main chain of the gateway.
+pub async fn missing_headers<B, F>(
+    bitcoind: &B,
+    best: BtcHeaderIndex,
+    is_relayed: F,
+    max_count: u32,
+) -> Result<Vec<BtcHeader>>
+where
+    B: BitcoinApi,
+    F: Fn(H256) -> Result<bool>,
+{
+    let tip = bitcoind.block_count().await?;
+
+    // find the fork point of the gateway and bitcoind.
+    let mut height = best.height.min(tip);
+    loop {
+        if is_relayed(bitcoind.block_hash(height).await?)? {
+            break;
+        }
+        if height == 0 || best.height - height >= MAX_FORK_DEPTH {
+            return Err(Error::ForkTooDeep(best));
+        }
+        height -= 1;
+    }
+
+    let mut headers = Vec::new();
+    for height in height + 1..=tip.min(height.saturating_add(max_count)) {
+        let hash = bitcoind.block_hash(height).await?;
+        headers.push(bitcoind.block_header(hash).await?);
+    }
+    Ok(headers)
+}
+
+/// Returns the transactions of the block `block_hash` to relay, together with the merkle
+/// proof and the previous transaction (if available) of each.
+///
+/// `is_processed` returns whether the transaction has been processed by the gateway.
+pub async fn related_transactions<B, F>(
+    bitcoind: &B,
+    block_hash: H256,
+    network: Network,
+    trustee_addrs: &[Destination],
+    proposal_txid: Option<H256>,
+    is_processed: F,
+) -> Result<Vec<(Transaction, PartialMerkleTree, Option<Transaction>)>>
+where
+    B: BitcoinApi,
+    F: Fn(H256) -> Result<bool>,
+{
+    let mut related = Vec::new();
+    for tx in bitcoind.block_transactions(block_hash).await? {
+        if !is_related(&tx, network, trustee_addrs, proposal_txid) {
+            continue;
+        }
+        let txid = tx.hash();
+        if is_processed(txid)? {
+            continue;
+        }
+        let merkle_proof = bitcoind.merkle_proof(txid, block_hash).await?;
+        let prev_tx = match bitcoind
+            .raw_transaction(tx.inputs[0].previous_output.txid)
+            .await
+        {
+            Ok(prev_tx) => Some(prev_tx),
+            Err(err) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to fetch the previous transaction of {:?}: {}",
+                    hash_rev(txid),
+                    err
+                );
+                None
+            }
+        };
+        related.push((tx, merkle_proof, prev_tx));
+    }
+    Ok(related)
+}
+
+/// Parses the hot and cold addresses of the trustee session.
+fn trustee_addrs(
+    info: chainx_runtime::GenericTrusteeSessionInfo<AccountId>,
+    network: Network,
+) -> Result<Vec<Destination>> {
+    use std::convert::TryFrom;
+
+    let info = BtcTrusteeSessionInfo::<AccountId>::try_from(info)
+        .map_err(|_| Error::Deserialize("Invalid bitcoin trustee session info".into()))?;
+    [info.hot_address.addr, info.cold_address.addr]
+        .iter()
+        .map(|addr| {
+            parse_addr(addr)
+                .filter(|addr| addr.network() == network)
+                .ok_or_else(|| {
+                    Error::Deserialize(format!(
+                        "Invalid trustee address: {}",
+                        String::from_utf8_lossy(addr)
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Parses the base58 legacy address or the bech32/bech32m native SegWit address.
+fn parse_addr(addr: &[u8]) -> Option<Destination> {
+    if let Some(addr) = WitnessAddress::from_bech32(addr) {
+        return Some(addr.into());
+    }
+    std::str::from_utf8(addr)
+        .ok()?
+        .parse::<Address>()
+        .ok()
+        .map(Into::into)
+}
+
+/// Returns true if the transaction pays to the trustee addresses or it's the transaction
+/// of the withdrawal proposal.
+fn is_related(
+    tx: &Transaction,
+    network: Network,
+    trustee_addrs: &[Destination],
+    proposal_txid: Option<H256>,
+) -> bool {
+    if tx.is_coinbase() {
+        return false;
+    }
+    if proposal_txid == Some(tx.hash()) {
+        return true;
+    }
+    tx.outputs.iter().any(|output| {
+        extract_output_addr(output, network).map_or(false, |addr| {
+            trustee_addrs
+                .iter()
+                .any(|trustee| trustee.has_same_hash(&addr))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use light_bitcoin::primitives::{h256_rev, Compact};
+    use serde_json::{json, Value};
+
+    use super::mock::{
+        with_bitcoind, GENESIS_HEADER, MERKLE_PROOF, PASSWORD, TX, TXID, UNRELATED_TX,
+    };
+    use super::*;
+
+    /// The mocked bitcoind which only serves the block headers of the main chain.
+    struct MockBitcoind {
+        main_chain: BTreeMap<u32, BtcHeader>,
+    }
+
+    impl MockBitcoind {
+        /// Generates a chain of `count` headers, the nonce makes the forks different.
+        fn new(count: u32, fork_height: u32, nonce: u32) -> Self {
+            let mut main_chain = BTreeMap::new();
+            let mut previous_header_hash = H256::default();
+            for height in 0..count {
+                let header = BtcHeader {
+                    version: 1,
+                    previous_header_hash,
+                    merkle_root_hash: H256::default(),
+                    time: height,
+                    bits: Compact::new(0x207fffff),
+                    nonce: if height > fork_height { nonce } else { 0 },
+                };
+                previous_header_hash = header.hash();
+                main_chain.insert(height, header);
+            }
+            Self { main_chain }
+        }
+
+        fn index(&self, height: u32) -> BtcHeaderIndex {
+            BtcHeaderIndex {
+                hash: self.main_chain[&height].hash(),
+                height,
+            }
+        }
+
+        fn is_relayed(&self, max_height: u32) -> impl Fn(H256) -> Result<bool> + '_ {
+            move |hash| {
+                Ok(self
+                    .main_chain
+                    .range(..=max_height)
+                    .any(|(_, header)| header.hash() == hash))
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl BitcoinApi for MockBitcoind {
+        async fn block_count(&self) -> Result<u32> {
+            Ok(self.main_chain.len() as u32 - 1)
+        }
+
+        async fn block_hash(&self, height: u32) -> Result<H256> {
+            self.main_chain
+                .get(&height)
+                .map(BtcHeader::hash)
+                .ok_or_else(|| Error::Rpc(-8, "Block height out of range".into()))
+        }
+
+        async fn block_header(&self, hash: H256) -> Result<BtcHeader> {
+            self.main_chain
+                .values()
+                .find(|header| header.hash() == hash)
+                .copied()
+                .ok_or_else(|| Error::Rpc(-5, "Block not found".into()))
+        }
+
+        async fn block_transactions(&self, _hash: H256) -> Result<Vec<Transaction>> {
+            Ok(Vec::new())
+        }
+
+        async fn raw_transaction(&self, _txid: H256) -> Result<Transaction> {
+            Err(Error::Rpc(
+                -5,
+                "No such mempool or blockchain transaction".into(),
+            ))
+        }
+
+        async fn merkle_proof(&self, _txid: H256, _hash: H256) -> Result<PartialMerkleTree> {
+            Err(Error::Rpc(-5, "Transaction not yet in block".into()))
+        }
+    }
+
+    #[test]
+    fn test_missing_headers() {
+        let bitcoind = MockBitcoind::new(20, 20, 0);
+
+        let headers = futures::executor::block_on(missing_headers(
+            &bitcoind,
+            bitcoind.index(10),
+            bitcoind.is_relayed(10),
+            5,
+        ))
+        .unwrap();
+        let expected = (11..=15)
+            .map(|h| bitcoind.main_chain[&h])
+            .collect::<Vec<_>>();
+        assert_eq!(headers, expected);
+
+        // all relayed
+        let headers = futures::executor::block_on(missing_headers(
+            &bitcoind,
+            bitcoind.index(19),
+            bitcoind.is_relayed(19),
+            5,
+        ))
+        .unwrap();
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn test_missing_headers_after_fork() {
+        // the gateway has relayed the blocks 0..=10 of a fork from the height 5,
+        // while the main chain of bitcoind has switched to another fork.
+        let relayed = MockBitcoind::new(11, 5, 1);
+        let bitcoind = MockBitcoind::new(20, 5, 2);
+
+        let is_relayed = |hash| {
+            Ok(relayed
+                .main_chain
+                .values()
+                .any(|header| header.hash() == hash))
+        };
+        let headers = futures::executor::block_on(missing_headers(
+            &bitcoind,
+            relayed.index(10),
+            is_relayed,
+            100,
+        ))
+        .unwrap();
+        let expected = (6..20).map(|h| bitcoind.main_chain[&h]).collect::<Vec<_>>();
+        assert_eq!(headers, expected);
+
+        // no common block
+        let relayed = MockBitcoind::new(11, 0, 1);
+        let bitcoind = MockBitcoind::new(20, 0, 2);
+        let is_relayed = |hash| {
+            Ok(relayed
+                .main_chain
+                .values()
+                .skip(1)
+                .any(|header| header.hash() == hash))
+        };
+        let result = futures::executor::block_on(missing_headers(
+            &bitcoind,
+            relayed.index(10),
+            is_relayed,
+            100,
+        ));
+        assert!(matches!(result, Err(Error::ForkTooDeep(_))));
+    }
+
+    #[test]
+    fn test_read_secret() {
+        let path = std::env::temp_dir().join("chainx-btc-relayer-signer");
+        std::fs::write(&path, "//Alice\n").unwrap();
+        assert_eq!(read_secret(&path).unwrap(), "//Alice");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(read_secret(&path).is_err());
+    }
+
+    /// The P2SH address paid by the 3rd output of `TX`.
+    const TRUSTEE_ADDR: &[u8] = b"3LFSUKkP26hun42J1Dy6RATsbgmBJb27NF";
+
+    #[test]
+    fn test_is_related() {
+        let tx: Transaction = TX.parse().unwrap();
+        let unrelated_tx: Transaction = UNRELATED_TX.parse().unwrap();
+        let trustee_addrs = vec![parse_addr(TRUSTEE_ADDR).unwrap()];
+
+        assert!(is_related(&tx, Network::Mainnet, &trustee_addrs, None));
+        assert!(!is_related(
+            &unrelated_tx,
+            Network::Mainnet,
+            &trustee_addrs,
+            None
+        ));
+        // the tx of the withdrawal proposal
+        let proposal_txid = Some(unrelated_tx.hash());
+        assert!(is_related(
+            &unrelated_tx,
+            Network::Mainnet,
+            &trustee_addrs,
+            proposal_txid
+        ));
+
+        // the coinbase tx is never relayed
+        let mut coinbase = tx;
+        coinbase.inputs.truncate(1);
+        coinbase.inputs[0].previous_output.txid = H256::default();
+        coinbase.inputs[0].previous_output.index = u32::max_value();
+        assert!(!is_related(
+            &coinbase,
+            Network::Mainnet,
+            &trustee_addrs,
+            None
+        ));
+    }
+
+    #[test]
+    fn test_related_transactions() {
+        let block_hash = H256::repeat_byte(1);
+        let tx: Transaction = TX.parse().unwrap();
+        // the hashes in the RPC (reversed) order
+        let rpc_block_hash = json!(hex::encode(hash_rev(block_hash).as_bytes()));
+        let rpc_prev_txid = json!(hex::encode(
+            hash_rev(tx.inputs[0].previous_output.txid).as_bytes()
+        ));
+        let handler = move |method: &str, params: &[Value]| match (method, params) {
+            ("getblock", [hash, _]) if *hash == rpc_block_hash => {
+                Ok(json!({ "tx": [{ "hex": UNRELATED_TX }, { "hex": TX }] }))
+            }
+            ("gettxoutproof", [txids, hash])
+                if *txids == json!([TXID]) && *hash == rpc_block_hash =>
+            {
+                Ok(json!(format!("{}{}", GENESIS_HEADER, MERKLE_PROOF)))
+            }
+            ("getrawtransaction", [txid, _]) if *txid == rpc_prev_txid => {
+                Err((-5, "No such mempool or blockchain transaction".into()))
+            }
+            _ => Err((-32601, "Method not found".into())),
+        };
+        with_bitcoind(handler, PASSWORD, |bitcoind| async move {
+            let trustee_addrs = vec![parse_addr(TRUSTEE_ADDR).unwrap()];
+
+            let related = related_transactions(
+                &bitcoind,
+                block_hash,
+                Network::Mainnet,
+                &trustee_addrs,
+                None,
+                |_| Ok(false),
+            )
+            .await
+            .unwrap();
+            assert_eq!(related.len(), 1);
+            let (related_tx, merkle_proof, prev_tx) = &related[0];
+            assert_eq!(*related_tx, tx);
+            let mut matches = Vec::new();
+            let mut indexes = Vec::new();
+            merkle_proof
+                .extract_matches(&mut matches, &mut indexes)
+                .unwrap();
+            assert_eq!(matches, vec![tx.hash()]);
+            // the previous tx is optional
+            assert!(prev_tx.is_none());
+
+            // the processed tx is skipped, without fetching the merkle proof
+            let related = related_transactions(
+                &bitcoind,
+                block_hash,
+                Network::Mainnet,
+                &trustee_addrs,
+                None,
+                |txid| Ok(txid == h256_rev(TXID)),
+            )
+            .await
+            .unwrap();
+            assert!(related.is_empty());
+
+            // the errors of bitcoind are returned
+            let result = related_transactions(
+                &bitcoind,
+                H256::default(),
+                Network::Mainnet,
+                &trustee_addrs,
+                None,
+                |_| Ok(false),
+            )
+            .await;
+            assert!(matches!(result, Err(Error::Rpc(-32601, _))));
+        });
+    }
+}
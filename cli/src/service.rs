@@ -18,6 +18,8 @@ use chainx_executor::Executor;
 use chainx_primitives::Block;
 use chainx_runtime::{self, RuntimeApi};
 
+use crate::relayer::{Relayer, RelayerConfig};
+
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullGrandpaBlockImport =
@@ -157,8 +159,11 @@ pub struct NewFullBase {
     pub transaction_pool: Arc<sc_transaction_pool::FullPool<Block, FullClient>>,
 }
 
-/// Creates a full service from the configuration.
-pub fn new_full_base(config: Configuration) -> Result<NewFullBase, ServiceError> {
+/// Creates a full service from the configuration, with the optional bitcoin relayer.
+pub fn new_full_base(
+    config: Configuration,
+    relayer: Option<RelayerConfig>,
+) -> Result<NewFullBase, ServiceError> {
     let sc_service::PartialComponents {
         client,
         backend,
@@ -221,6 +226,18 @@ pub fn new_full_base(config: Configuration) -> Result<NewFullBase, ServiceError>
         system_rpc_tx,
     })?;
 
+    if let Some(relayer) = relayer {
+        let relayer = Relayer::new(
+            relayer,
+            client.clone(),
+            transaction_pool.clone(),
+            network.clone(),
+        );
+        task_manager
+            .spawn_handle()
+            .spawn("btc-relayer", relayer.run());
+    }
+
     let (block_import, grandpa_link, babe_link) = import_setup;
 
     if role.is_authority() {
@@ -338,8 +355,11 @@ pub fn new_full_base(config: Configuration) -> Result<NewFullBase, ServiceError>
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
-    new_full_base(config).map(|NewFullBase { task_manager, .. }| task_manager)
+pub fn new_full(
+    config: Configuration,
+    relayer: Option<RelayerConfig>,
+) -> Result<TaskManager, ServiceError> {
+    new_full_base(config, relayer).map(|NewFullBase { task_manager, .. }| task_manager)
 }
 
 /// Builds a new service for a light client.
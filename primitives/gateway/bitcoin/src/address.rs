@@ -0,0 +1,259 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use sp_std::prelude::Vec;
+
+use light_bitcoin::{
+    keys::{Address, AddressHash, Network},
+    primitives::H256,
+};
+
+use crate::bech32::{self, Variant};
+
+/// The opcode `OP_1`, which pushes the witness version 1.
+const OP_1: u8 = 0x51;
+
+/// The witness program of a native SegWit output.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum WitnessProgram {
+    /// Pay to witness public key hash (P2WPKH), witness version 0.
+    KeyHash(AddressHash),
+    /// Pay to witness script hash (P2WSH), witness version 0.
+    ScriptHash(H256),
+    /// Pay to taproot (P2TR) with the x-only output key, witness version 1.
+    Taproot(H256),
+}
+
+impl WitnessProgram {
+    /// Parses the witness program from the output script `OP_0 <20 or 32 bytes>` or
+    /// `OP_1 <32 bytes>`.
+    pub fn from_script(script: &[u8]) -> Option<Self> {
+        match script {
+            [0x00, 0x14, program @ ..] if program.len() == 20 => {
+                Some(WitnessProgram::KeyHash(AddressHash::from_slice(program)))
+            }
+            [0x00, 0x20, program @ ..] if program.len() == 32 => {
+                Some(WitnessProgram::ScriptHash(H256::from_slice(program)))
+            }
+            [OP_1, 0x20, program @ ..] if program.len() == 32 => {
+                Some(WitnessProgram::Taproot(H256::from_slice(program)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the witness version of the witness program.
+    pub fn version(&self) -> u8 {
+        match self {
+            WitnessProgram::KeyHash(_) | WitnessProgram::ScriptHash(_) => 0,
+            WitnessProgram::Taproot(_) => 1,
+        }
+    }
+
+    /// Returns the bytes of the witness program.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            WitnessProgram::KeyHash(hash) => hash.as_bytes(),
+            WitnessProgram::ScriptHash(hash) => hash.as_bytes(),
+            WitnessProgram::Taproot(key) => key.as_bytes(),
+        }
+    }
+
+    /// Returns the output script `OP_n <program>` paying to the witness program.
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        let program = self.as_bytes();
+        let mut script = Vec::with_capacity(program.len() + 2);
+        script.push(match self.version() {
+            0 => 0x00,
+            version => OP_1 + version - 1,
+        });
+        script.push(program.len() as u8);
+        script.extend_from_slice(program);
+        script
+    }
+}
+
+/// A native SegWit address, i.e., the witness program with the network.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct WitnessAddress {
+    /// The bitcoin network type (mainnet/testnet).
+    pub network: Network,
+    /// The witness program.
+    pub program: WitnessProgram,
+}
+
+impl WitnessAddress {
+    /// Returns the human-readable part of the bech32 address of `network`.
+    fn hrp(network: Network) -> &'static [u8] {
+        match network {
+            Network::Mainnet => b"bc",
+            Network::Testnet => b"tb",
+        }
+    }
+
+    /// Parses the native SegWit address from a bech32 string, e.g., `bc1q...`, or
+    /// a bech32m string, e.g., `bc1p...`.
+    pub fn from_bech32(s: &[u8]) -> Option<Self> {
+        let (hrp, data, variant) = bech32::decode(s)?;
+        let network = if hrp == Self::hrp(Network::Mainnet) {
+            Network::Mainnet
+        } else if hrp == Self::hrp(Network::Testnet) {
+            Network::Testnet
+        } else {
+            return None;
+        };
+
+        // Only the witness version 0 (bech32) and 1 (bech32m) are supported.
+        let (version, data) = data.split_first()?;
+        let program = bech32::convert_bits(data, 5, 8, false)?;
+        let program = match (*version, variant, program.len()) {
+            (0, Variant::Bech32, 20) => WitnessProgram::KeyHash(AddressHash::from_slice(&program)),
+            (0, Variant::Bech32, 32) => WitnessProgram::ScriptHash(H256::from_slice(&program)),
+            (1, Variant::Bech32m, 32) => WitnessProgram::Taproot(H256::from_slice(&program)),
+            _ => return None,
+        };
+
+        Some(Self { network, program })
+    }
+
+    /// Encodes the address as a lowercase bech32 string, or bech32m string for the
+    /// witness version 1.
+    pub fn to_bech32(&self) -> Vec<u8> {
+        let version = self.program.version();
+        let variant = if version == 0 {
+            Variant::Bech32
+        } else {
+            Variant::Bech32m
+        };
+        let mut data = Vec::with_capacity(1 + (self.program.as_bytes().len() * 8 + 4) / 5);
+        data.push(version);
+        data.extend(
+            bech32::convert_bits(self.program.as_bytes(), 8, 5, true)
+                .expect("the bytes are always 8-bit groups; qed"),
+        );
+        bech32::encode(Self::hrp(self.network), &data, variant)
+    }
+}
+
+/// The destination of a bitcoin output, which is either a legacy address
+/// (P2PKH/P2SH) or a native SegWit address (P2WPKH/P2WSH/P2TR).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Destination {
+    /// The legacy address encoded in base58.
+    Legacy(Address),
+    /// The native SegWit address encoded in bech32 or bech32m.
+    Witness(WitnessAddress),
+}
+
+impl Destination {
+    /// Returns the bitcoin network type of the address.
+    pub fn network(&self) -> Network {
+        match self {
+            Destination::Legacy(addr) => addr.network,
+            Destination::Witness(addr) => addr.network,
+        }
+    }
+
+    /// Returns true if both of the addresses pay to the same hash, the network and the
+    /// kind of the legacy address are ignored.
+    pub fn has_same_hash(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Destination::Legacy(a), Destination::Legacy(b)) => a.hash == b.hash,
+            (Destination::Witness(a), Destination::Witness(b)) => a.program == b.program,
+            _ => false,
+        }
+    }
+
+    /// Returns the legacy address if it is.
+    pub fn legacy(&self) -> Option<Address> {
+        match self {
+            Destination::Legacy(addr) => Some(*addr),
+            Destination::Witness(_) => None,
+        }
+    }
+}
+
+impl From<Address> for Destination {
+    fn from(addr: Address) -> Self {
+        Destination::Legacy(addr)
+    }
+}
+
+impl From<WitnessAddress> for Destination {
+    fn from(addr: WitnessAddress) -> Self {
+        Destination::Witness(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_witness_address() {
+        // P2WPKH
+        let addr =
+            WitnessAddress::from_bech32(b"BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!(addr.network, Network::Mainnet);
+        assert_eq!(
+            addr.program.script_pubkey(),
+            hex::decode("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+        );
+        assert_eq!(
+            addr.to_bech32(),
+            b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_vec()
+        );
+
+        // P2WSH
+        let s = b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7";
+        let addr = WitnessAddress::from_bech32(s).unwrap();
+        assert_eq!(addr.network, Network::Testnet);
+        let script =
+            hex::decode("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262")
+                .unwrap();
+        assert_eq!(addr.program.script_pubkey(), script);
+        assert_eq!(WitnessProgram::from_script(&script), Some(addr.program));
+        assert_eq!(addr.to_bech32(), s.to_vec());
+
+        let invalid: &[&[u8]] = &[
+            // Invalid human-readable part.
+            b"tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kadnx9p",
+            // The witness version 1 encoded in bech32 instead of bech32m.
+            b"bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx",
+            // Invalid program length.
+            b"BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+            // Mixed case.
+            b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sL5k7",
+            // Non-zero padding.
+            b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3pjxtptv",
+        ];
+        for s in invalid {
+            assert_eq!(WitnessAddress::from_bech32(s), None);
+        }
+    }
+
+    #[test]
+    fn test_taproot_address() {
+        let s = b"bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0";
+        let addr = WitnessAddress::from_bech32(s).unwrap();
+        assert_eq!(addr.network, Network::Mainnet);
+        assert_eq!(addr.program.version(), 1);
+        let script =
+            hex::decode("512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        assert_eq!(addr.program.script_pubkey(), script);
+        assert_eq!(WitnessProgram::from_script(&script), Some(addr.program));
+        assert_eq!(addr.to_bech32(), s.to_vec());
+
+        let invalid: &[&[u8]] = &[
+            // The witness version 0 encoded in bech32m instead of bech32.
+            b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+            // The witness version 1 encoded in bech32 instead of bech32m.
+            b"tb1pw508d6qejxtdg4y5r3zarqfsj6c3",
+            // Unsupported witness version.
+            b"bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+        ];
+        for s in invalid {
+            assert_eq!(WitnessAddress::from_bech32(s), None);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! Bech32 (BIP-173) and Bech32m (BIP-350) encoding of the native SegWit addresses.
+
+use sp_std::prelude::Vec;
+
+/// The alphabet of the data part.
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The generator of the BCH checksum.
+const GENERATOR: [u32; 5] = [
+    0x3b6a_57b2,
+    0x2650_8e6d,
+    0x1ea1_19fa,
+    0x3d42_33dd,
+    0x2a14_62b3,
+];
+
+/// The constant the checksum of a valid bech32 string evaluates to.
+const BECH32_CONST: u32 = 1;
+
+/// The constant the checksum of a valid bech32m string evaluates to.
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+/// The checksum variant of the encoding.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    /// Bech32 (BIP-173), used by the witness version 0.
+    Bech32,
+    /// Bech32m (BIP-350), used by the witness version 1 and above.
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => BECH32_CONST,
+            Variant::Bech32m => BECH32M_CONST,
+        }
+    }
+
+    fn from_constant(constant: u32) -> Option<Self> {
+        match constant {
+            BECH32_CONST => Some(Variant::Bech32),
+            BECH32M_CONST => Some(Variant::Bech32m),
+            _ => None,
+        }
+    }
+}
+
+/// The separator between the human-readable part and the data part.
+const SEPARATOR: u8 = b'1';
+
+/// The maximum length of a bech32 string.
+const MAX_LEN: usize = 90;
+
+/// The length of the checksum, in 5-bit groups.
+const CHECKSUM_LEN: usize = 6;
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ u32::from(*value);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &[u8]) -> Vec<u8> {
+    let mut expanded = Vec::with_capacity(hrp.len() * 2 + 1);
+    expanded.extend(hrp.iter().map(|c| c >> 5));
+    expanded.push(0);
+    expanded.extend(hrp.iter().map(|c| c & 0x1f));
+    expanded
+}
+
+/// Encodes the human-readable part `hrp` and the 5-bit groups `data` as a lowercase
+/// bech32 or bech32m string.
+pub fn encode(hrp: &[u8], data: &[u8], variant: Variant) -> Vec<u8> {
+    let hrp = hrp.to_ascii_lowercase();
+
+    let mut values = hrp_expand(&hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0; CHECKSUM_LEN]);
+    let checksum = polymod(&values) ^ variant.constant();
+
+    let mut encoded = hrp;
+    encoded.push(SEPARATOR);
+    encoded.extend(data.iter().map(|d| CHARSET[*d as usize]));
+    encoded
+        .extend((0..CHECKSUM_LEN).map(|i| CHARSET[((checksum >> (5 * (5 - i))) & 0x1f) as usize]));
+    encoded
+}
+
+/// Decodes a bech32 or bech32m string into the lowercase human-readable part, the 5-bit
+/// groups of the data part and the checksum variant, the checksum is verified and stripped.
+///
+/// Returns `None` if the string is neither a valid bech32 nor a valid bech32m string.
+pub fn decode(s: &[u8]) -> Option<(Vec<u8>, Vec<u8>, Variant)> {
+    if s.len() > MAX_LEN || s.iter().any(|c| *c < 33 || *c > 126) {
+        return None;
+    }
+    // Mixed case is not allowed.
+    if s.iter().any(u8::is_ascii_lowercase) && s.iter().any(u8::is_ascii_uppercase) {
+        return None;
+    }
+    let s = s.to_ascii_lowercase();
+
+    let pos = s.iter().rposition(|c| *c == SEPARATOR)?;
+    if pos < 1 || pos + CHECKSUM_LEN + 1 > s.len() {
+        return None;
+    }
+    let (hrp, data) = (&s[..pos], &s[pos + 1..]);
+
+    let data = data
+        .iter()
+        .map(|c| CHARSET.iter().position(|x| x == c).map(|d| d as u8))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    let variant = Variant::from_constant(polymod(&values))?;
+
+    Some((
+        hrp.to_vec(),
+        data[..data.len() - CHECKSUM_LEN].to_vec(),
+        variant,
+    ))
+}
+
+/// Regroups the `from`-bit groups of `data` into `to`-bit groups.
+///
+/// The incomplete group is padded with zero bits if `pad` is true, otherwise it must
+/// be less than `from` bits and all zero.
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let max: u32 = (1 << to) - 1;
+    let mut converted = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for value in data {
+        let value = u32::from(*value);
+        if value >> from != 0 {
+            return None;
+        }
+        acc = (acc << from) | value;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            converted.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            converted.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+        return None;
+    }
+    Some(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bech32_checksum() {
+        let valid: &[&[u8]] = &[
+            b"A12UEL5L",
+            b"a12uel5l",
+            b"abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            b"split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+            b"?1ezyfcl",
+        ];
+        for s in valid {
+            let (hrp, data, variant) = decode(s).unwrap();
+            assert_eq!(variant, Variant::Bech32);
+            assert_eq!(encode(&hrp, &data, variant), s.to_ascii_lowercase());
+        }
+
+        let invalid: &[&[u8]] = &[
+            // Invalid character.
+            b"x1b4n0q5v",
+            // Empty human-readable part.
+            b"1pzry9x0s0muk",
+            // Too short checksum.
+            b"li1dgmt3",
+            // Mixed case.
+            b"A12uEL5L",
+            // Wrong checksum.
+            b"a12uel5m",
+        ];
+        for s in invalid {
+            assert_eq!(decode(s), None);
+        }
+    }
+
+    #[test]
+    fn test_bech32m_checksum() {
+        let valid: &[&[u8]] = &[
+            b"A1LQFN3A",
+            b"a1lqfn3a",
+            b"abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            b"?1v759aa",
+        ];
+        for s in valid {
+            let (hrp, data, variant) = decode(s).unwrap();
+            assert_eq!(variant, Variant::Bech32m);
+            assert_eq!(encode(&hrp, &data, variant), s.to_ascii_lowercase());
+        }
+
+        // The bech32 checksum is not a valid bech32m checksum.
+        let (hrp, data, _) = decode(b"a12uel5l").unwrap();
+        assert_ne!(encode(&hrp, &data, Variant::Bech32m), b"a12uel5l".to_vec());
+    }
+
+    #[test]
+    fn test_convert_bits() {
+        let bytes = [0xff, 0x00, 0x5a];
+        let groups = convert_bits(&bytes, 8, 5, true).unwrap();
+        assert_eq!(groups, vec![31, 28, 0, 5, 20]);
+        assert_eq!(convert_bits(&groups, 5, 8, false).unwrap(), bytes.to_vec());
+        // Non-zero padding.
+        assert_eq!(convert_bits(&[31, 28, 0, 5, 21], 5, 8, false), None);
+    }
+}
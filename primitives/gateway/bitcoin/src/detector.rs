@@ -5,14 +5,10 @@ use sp_std::fmt::Debug;
 use chainx_primitives::ReferralId;
 use xp_logging::{debug, warn};
 
-use light_bitcoin::{
-    chain::Transaction,
-    keys::{Address, Network},
-    primitives::hash_rev,
-    script::Script,
-};
+use light_bitcoin::{chain::Transaction, keys::Network, primitives::hash_rev, script::Script};
 
 use crate::{
+    address::Destination,
     types::{BtcDepositInfo, BtcTxMetaType},
     utils::{
         extract_addr_from_transaction, extract_opreturn_data, extract_output_addr, is_trustee_addr,
@@ -27,9 +23,9 @@ pub struct BtcTxTypeDetector {
     // The minimum deposit value of the `Deposit` transaction.
     min_deposit: u64,
     // (current hot trustee address, current cold trustee address)
-    current_trustee_pair: (Address, Address),
+    current_trustee_pair: (Destination, Destination),
     // (last hot trustee address, last cold trustee address)
-    last_trustee_pair: Option<(Address, Address)>,
+    last_trustee_pair: Option<(Destination, Destination)>,
 }
 
 impl BtcTxTypeDetector {
@@ -37,8 +33,8 @@ impl BtcTxTypeDetector {
     pub fn new(
         network: Network,
         min_deposit: u64,
-        current_trustee_pair: (Address, Address),
-        last_trustee_pair: Option<(Address, Address)>,
+        current_trustee_pair: (Destination, Destination),
+        last_trustee_pair: Option<(Destination, Destination)>,
     ) -> Self {
         Self {
             network,
@@ -81,11 +77,11 @@ impl BtcTxTypeDetector {
 
         // detect X-BTC `Withdrawal`/`HotAndCold`/`TrusteeTransition` transaction
         if let Some(input_addr) = input_addr {
-            let all_outputs_is_trustee = tx
-                .outputs
-                .iter()
-                .map(|output| extract_output_addr(output, self.network).unwrap_or_default())
-                .all(|addr| is_trustee_addr(addr, self.current_trustee_pair));
+            let all_outputs_is_trustee = tx.outputs.iter().all(|output| {
+                extract_output_addr(output, self.network).map_or(false, |addr| {
+                    is_trustee_addr(addr, self.current_trustee_pair)
+                })
+            });
 
             if is_trustee_addr(input_addr, self.current_trustee_pair) {
                 return if all_outputs_is_trustee {
@@ -152,7 +148,7 @@ impl BtcTxTypeDetector {
     pub fn detect_deposit_transaction_type<AccountId, Extractor>(
         &self,
         tx: &Transaction,
-        input_addr: Option<Address>,
+        input_addr: Option<Destination>,
         extract_account: Extractor,
     ) -> BtcTxMetaType<AccountId>
     where
@@ -216,7 +212,7 @@ impl BtcTxTypeDetector {
             // extract destination address from the script of output.
             if let Some(dest_addr) = extract_output_addr(output, self.network) {
                 // check if the script address of the output is the hot trustee address
-                if dest_addr.hash == hot_addr.hash && output.value > 0 {
+                if dest_addr.has_same_hash(&hot_addr) && output.value > 0 {
                     deposit_value += output.value;
                 }
             }
@@ -234,7 +230,9 @@ mod tests {
     use sp_core::crypto::{set_default_ss58_version, Ss58AddressFormat};
     use sp_runtime::AccountId32;
 
-    use super::{Address, BtcTxTypeDetector, Network, Transaction};
+    use light_bitcoin::keys::Address;
+
+    use super::{BtcTxTypeDetector, Network, Transaction};
     use crate::extractor::{AccountExtractor, OpReturnExtractor};
 
     fn account(addr: &str) -> AccountId32 {
@@ -381,8 +379,8 @@ mod tests {
             Network::Mainnet,
             0,
             (
-                DEPOSIT_HOT_ADDR.parse::<Address>().unwrap(),
-                DEPOSIT_COLD_ADDR.parse::<Address>().unwrap(),
+                DEPOSIT_HOT_ADDR.parse::<Address>().unwrap().into(),
+                DEPOSIT_COLD_ADDR.parse::<Address>().unwrap().into(),
             ),
             None,
         );
@@ -5,11 +5,14 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
 
+mod address;
+mod bech32;
 mod detector;
 mod extractor;
 mod types;
 mod utils;
 
+pub use self::address::{Destination, WitnessAddress, WitnessProgram};
 pub use self::detector::BtcTxTypeDetector;
 pub use self::extractor::{AccountExtractor, OpReturnExtractor};
 pub use self::types::{BtcDepositInfo, BtcTxMetaType, BtcTxType};
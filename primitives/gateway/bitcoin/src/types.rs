@@ -8,7 +8,7 @@ use sp_runtime::RuntimeDebug;
 
 use chainx_primitives::ReferralId;
 
-use light_bitcoin::keys::Address;
+use crate::address::Destination;
 
 /// The bitcoin transaction type.
 #[doc(hidden)]
@@ -60,5 +60,5 @@ pub struct BtcDepositInfo<AccountId> {
     /// The parsed op_return data.
     pub op_return: Option<(AccountId, Option<ReferralId>)>,
     /// The input address of deposit transaction.
-    pub input_addr: Option<Address>,
+    pub input_addr: Option<Destination>,
 }
@@ -10,20 +10,28 @@ use light_bitcoin::{
     script::{Opcode, Script, ScriptType},
 };
 
+use crate::address::{Destination, WitnessAddress, WitnessProgram};
+
 /// Extract address from a transaction output specified by outpoint_index.
 pub fn extract_addr_from_transaction(
     tx: &Transaction,
     outpoint_index: usize,
     network: Network,
-) -> Option<Address> {
+) -> Option<Destination> {
     tx.outputs
         .get(outpoint_index)
         .and_then(|output| extract_output_addr(output, network))
 }
 
 /// Extract address from a transaction output script.
-/// only support `p2pk`, `p2pkh` and `p2sh` output script
-pub fn extract_output_addr(output: &TransactionOutput, network: Network) -> Option<Address> {
+/// only support `p2pk`, `p2pkh`, `p2sh`, `p2wpkh`, `p2wsh` and `p2tr` output script
+pub fn extract_output_addr(output: &TransactionOutput, network: Network) -> Option<Destination> {
+    // native SegWit script, i.e., `OP_0 <20-byte or 32-byte witness program>`
+    // or `OP_1 <32-byte witness program>`
+    if let Some(program) = WitnessProgram::from_script(&output.script_pubkey) {
+        return Some(Destination::Witness(WitnessAddress { network, program }));
+    }
+
     let script = Script::new(output.script_pubkey.clone());
 
     // only support `p2pk`, `p2pkh` and `p2sh` script
@@ -41,11 +49,11 @@ pub fn extract_output_addr(output: &TransactionOutput, network: Network) -> Opti
             // find address in this transaction
             if script_addresses.len() == 1 {
                 let address = &script_addresses[0];
-                Some(Address {
+                Some(Destination::Legacy(Address {
                     network,
                     kind: address.kind,
                     hash: address.hash,
-                })
+                }))
             } else {
                 warn!(
                     "[extract_output_addr] Can't extract address of btc script, type:{:?}, address:{:?}, script:{}",
@@ -59,9 +67,9 @@ pub fn extract_output_addr(output: &TransactionOutput, network: Network) -> Opti
 }
 
 /// Check if the `addr` is hot trustee address or cold trustee address.
-pub fn is_trustee_addr(addr: Address, trustee_pair: (Address, Address)) -> bool {
+pub fn is_trustee_addr(addr: Destination, trustee_pair: (Destination, Destination)) -> bool {
     let (hot_addr, cold_addr) = trustee_pair;
-    addr.hash == hot_addr.hash || addr.hash == cold_addr.hash
+    addr.has_same_hash(&hot_addr) || addr.has_same_hash(&cold_addr)
 }
 
 /// Extract the opreturn data from btc null data script.
@@ -153,3 +161,59 @@ fn test_extract_opreturn_data() {
         b"5QZYGVVUPsp7cbqGUcHsRJUZrnmTuEyh6SLH6jdpfsFxgpRK@Laocius".to_vec()
     );
 }
+
+#[test]
+fn test_extract_output_addr() {
+    use light_bitcoin::keys::Type;
+
+    let output = |script: &str| TransactionOutput {
+        value: 1,
+        script_pubkey: hex::decode(script).unwrap().into(),
+    };
+
+    // p2sh
+    let addr = extract_output_addr(
+        &output("a914cb94110435d0635223eebe25ed2aaabc03781c4587"),
+        Network::Mainnet,
+    )
+    .and_then(|addr| addr.legacy())
+    .unwrap();
+    assert_eq!(addr.kind, Type::P2SH);
+    assert_eq!(addr, "3LFSUKkP26hun42J1Dy6RATsbgmBJb27NF".parse().unwrap());
+
+    // p2wpkh
+    let addr = extract_output_addr(
+        &output("0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+        Network::Mainnet,
+    );
+    assert_eq!(
+        addr,
+        WitnessAddress::from_bech32(b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").map(Into::into)
+    );
+
+    // p2wsh
+    let addr = extract_output_addr(
+        &output("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"),
+        Network::Testnet,
+    );
+    assert_eq!(
+        addr,
+        WitnessAddress::from_bech32(
+            b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7"
+        )
+        .map(Into::into)
+    );
+
+    // p2tr
+    let addr = extract_output_addr(
+        &output("5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"),
+        Network::Mainnet,
+    );
+    assert_eq!(
+        addr,
+        WitnessAddress::from_bech32(
+            b"bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        )
+        .map(Into::into)
+    );
+}
@@ -41,3 +41,14 @@ impl<Balance> AssetMining<Balance> for () {
 
     fn reward(_: AssetId, _: Balance) {}
 }
+
+/// Trait to reward the DEX Mining participants in Staking.
+pub trait DexMining<Balance> {
+    /// Issues reward to the reward pot of DEX Mining.
+    fn reward(_reward_value: Balance);
+}
+
+/// The reward for DEX Mining is not issued at all.
+impl<Balance> DexMining<Balance> for () {
+    fn reward(_: Balance) {}
+}
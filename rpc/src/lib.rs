@@ -3,6 +3,7 @@
 use std::fmt;
 use std::sync::Arc;
 
+use sc_client_api::BlockchainEvents;
 use sc_consensus_babe::Epoch;
 use sc_consensus_babe_rpc::BabeRpcHandler;
 use sc_finality_grandpa::{
@@ -86,6 +87,7 @@ pub fn create_full<C, P, SC, B>(
 where
     C: ProvideRuntimeApi<Block>,
     C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+    C: BlockchainEvents<Block>,
     C: Send + Sync + 'static,
     C::Api: BlockBuilder<Block>,
     C::Api: BabeApi<Block>,
@@ -95,6 +97,7 @@ where
     C::Api:
         xpallet_dex_spot_rpc_runtime_api::XSpotApi<Block, AccountId, Balance, BlockNumber, Balance>,
     C::Api: xpallet_gateway_common_rpc_runtime_api::XGatewayCommonApi<Block, AccountId, Balance>,
+    C::Api: xpallet_gateway_bitcoin_rpc_runtime_api::XGatewayBitcoinApi<Block, AccountId>,
     C::Api: xpallet_gateway_records_rpc_runtime_api::XGatewayRecordsApi<
         Block,
         AccountId,
@@ -126,6 +129,7 @@ where
     use substrate_frame_rpc_system::{FullSystem, SystemApi};
     use xpallet_assets_rpc::{Assets, XAssetsApi};
     use xpallet_dex_spot_rpc::{XSpot, XSpotApi};
+    use xpallet_gateway_bitcoin_rpc::{XGatewayBitcoin, XGatewayBitcoinApi};
     use xpallet_gateway_common_rpc::{XGatewayCommon, XGatewayCommonApi};
     use xpallet_gateway_records_rpc::{XGatewayRecords, XGatewayRecordsApi};
     use xpallet_mining_asset_rpc::{XMiningAsset, XMiningAssetApi};
@@ -177,7 +181,7 @@ where
             shared_authority_set,
             shared_voter_state,
             justification_stream,
-            subscription_executor,
+            subscription_executor.clone(),
             finality_provider,
         ),
     ));
@@ -187,13 +191,20 @@ where
     )));
     io.extend_with(XAssetsApi::to_delegate(Assets::new(client.clone())));
     io.extend_with(XStakingApi::to_delegate(XStaking::new(client.clone())));
-    io.extend_with(XSpotApi::to_delegate(XSpot::new(client.clone())));
+    io.extend_with(XSpotApi::to_delegate(XSpot::new(
+        client.clone(),
+        subscription_executor,
+        deny_unsafe,
+    )));
     io.extend_with(XMiningAssetApi::to_delegate(XMiningAsset::new(
         client.clone(),
     )));
     io.extend_with(XGatewayRecordsApi::to_delegate(XGatewayRecords::new(
         client.clone(),
     )));
+    io.extend_with(XGatewayBitcoinApi::to_delegate(XGatewayBitcoin::new(
+        client.clone(),
+    )));
     io.extend_with(XGatewayCommonApi::to_delegate(XGatewayCommon::new(client)));
     io
 }
@@ -45,7 +45,14 @@ use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
 use pallet_session::historical as pallet_session_historical;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 
-use xpallet_dex_spot::{Depth, FullPairInfo, RpcOrder, TradingPairId};
+use xpallet_dex_spot::{
+    Depth, FullPairInfo, IntegrityViolation, Kline, OrderExecutedInfo, RpcOrder,
+    TradingHistoryIndex, TradingPairId,
+};
+use xpallet_gateway_bitcoin::{
+    BtcAddress, BtcDepositCache, BtcGatewayParams, BtcHeaderDetails, BtcHeaderIndex, BtcTxState,
+    BtcWithdrawalProposalDetails,
+};
 use xpallet_mining_asset::{MinerLedger, MiningAssetInfo, MiningDividendInfo};
 use xpallet_mining_staking::{NominatorInfo, NominatorLedger, ValidatorInfo};
 use xpallet_support::traits::MultisigAddressFor;
@@ -107,7 +114,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_version: 1,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
 };
 
 /// The version information used to identify this runtime when compiled natively.
@@ -865,6 +872,7 @@ impl xpallet_mining_staking::Trait for Runtime {
     type SessionInterface = Self;
     type TreasuryAccount = SimpleTreasuryAccount;
     type AssetMining = XMiningAsset;
+    type DexMining = XSpot;
     type DetermineRewardPotAccount =
         xpallet_mining_staking::SimpleValidatorRewardPotAccountDeterminer<Runtime>;
     type WeightInfo = xpallet_mining_staking::weights::SubstrateWeight<Runtime>;
@@ -1227,7 +1235,7 @@ impl_runtime_apis! {
     }
 
     impl xpallet_dex_spot_rpc_runtime_api::XSpotApi<Block, AccountId, Balance, BlockNumber, Balance> for Runtime {
-        fn trading_pairs() -> Vec<FullPairInfo<Balance, BlockNumber>> {
+        fn trading_pairs() -> Vec<FullPairInfo<Balance, Balance, BlockNumber>> {
             XSpot::trading_pairs()
         }
 
@@ -1238,6 +1246,18 @@ impl_runtime_apis! {
         fn depth(pair_id: TradingPairId, depth_size: u32) -> Option<Depth<Balance, Balance>> {
             XSpot::depth(pair_id, depth_size)
         }
+
+        fn trade_history(pair_id: TradingPairId, from_index: TradingHistoryIndex, limit: u32) -> Vec<OrderExecutedInfo<AccountId, Balance, BlockNumber, Balance>> {
+            XSpot::trade_history(pair_id, from_index, limit)
+        }
+
+        fn klines(pair_id: TradingPairId, interval: BlockNumber, from: BlockNumber, to: BlockNumber) -> Vec<Kline<Balance, Balance, BlockNumber>> {
+            XSpot::klines(pair_id, interval, from, to)
+        }
+
+        fn integrity_check() -> Vec<IntegrityViolation<AccountId, Balance, Balance>> {
+            XSpot::integrity_check()
+        }
     }
 
     impl xpallet_mining_asset_rpc_runtime_api::XMiningAssetApi<Block, AccountId, Balance, MiningWeight, BlockNumber> for Runtime {
@@ -1300,6 +1320,40 @@ impl_runtime_apis! {
         }
     }
 
+    impl xpallet_gateway_bitcoin_rpc_runtime_api::XGatewayBitcoinApi<Block, AccountId> for Runtime {
+        fn best_index() -> BtcHeaderIndex {
+            XGatewayBitcoin::best_index()
+        }
+
+        fn confirmed_index() -> Option<BtcHeaderIndex> {
+            XGatewayBitcoin::confirmed_index()
+        }
+
+        fn header(hash: BtcHash) -> Option<BtcHeaderDetails> {
+            XGatewayBitcoin::btc_header_details(hash)
+        }
+
+        fn header_at(height: u32) -> Option<BtcHeaderDetails> {
+            XGatewayBitcoin::btc_main_chain_header_details(height)
+        }
+
+        fn tx_state(txid: BtcHash) -> Option<BtcTxState> {
+            XGatewayBitcoin::tx_state(txid)
+        }
+
+        fn pending_deposits(addr: BtcAddress) -> Result<Vec<BtcDepositCache>, DispatchError> {
+            XGatewayBitcoin::btc_pending_deposits(addr)
+        }
+
+        fn withdrawal_proposal() -> Option<BtcWithdrawalProposalDetails<AccountId>> {
+            XGatewayBitcoin::btc_withdrawal_proposal_details()
+        }
+
+        fn params() -> BtcGatewayParams {
+            XGatewayBitcoin::btc_gateway_params()
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn dispatch_benchmark(
@@ -53,7 +53,10 @@ NEW_TYPES = [
     "Token", "TradingHistoryIndex", "TradingPairId", "TradingPairInfo",
     "TradingPairProfile", "UnbondedIndex", "ValidatorLedger",
     "ValidatorProfile", "MiningAssetInfo", "LockedType", "NominatorInfo",
-    "Unbonded", "FeeDetails", "InclusionFee", "MiningDividendInfo"
+    "Unbonded", "FeeDetails", "InclusionFee", "MiningDividendInfo",
+    "TimeInForce", "TradingFee", "SelfTradePrevention", "Kline",
+    "OrderSizeLimit", "LiquidityPool", "MakerMiningRule", "MakerMiningLedger",
+    "ConditionalOrder", "ConditionalOrderKind", "TriggerDirection"
 ]
 
 # Change the working directory to project root directory.
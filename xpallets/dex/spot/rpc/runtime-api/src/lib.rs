@@ -10,7 +10,8 @@ use sp_std::prelude::*;
 use codec::Codec;
 
 pub use xpallet_dex_spot::{
-    Depth, FullPairInfo, Handicap, OrderProperty, RpcOrder, TradingPairId, TradingPairInfo,
+    Depth, FullPairInfo, Handicap, IntegrityViolation, Kline, OrderExecutedInfo, OrderProperty,
+    OrderSizeLimit, RpcOrder, TradingHistoryIndex, TradingPairId, TradingPairInfo,
 };
 
 sp_api::decl_runtime_apis! {
@@ -23,12 +24,21 @@ sp_api::decl_runtime_apis! {
         Price: Codec,
     {
         /// Get the overall info of all trading pairs.
-        fn trading_pairs() -> Vec<FullPairInfo<Price, BlockNumber>>;
+        fn trading_pairs() -> Vec<FullPairInfo<Price, Balance, BlockNumber>>;
 
         /// Get the orders of an account.
         fn orders(who: AccountId, page_index: u32, page_size: u32) -> Vec<RpcOrder<TradingPairId, AccountId, Balance, Price, BlockNumber>>;
 
         /// Get the depth of a trading pair.
         fn depth(pair_id: TradingPairId, depth_size: u32) -> Option<Depth<Price, Balance>>;
+
+        /// Get the recent executions of a trading pair.
+        fn trade_history(pair_id: TradingPairId, from_index: TradingHistoryIndex, limit: u32) -> Vec<OrderExecutedInfo<AccountId, Balance, BlockNumber, Price>>;
+
+        /// Get the klines of a trading pair given the interval.
+        fn klines(pair_id: TradingPairId, interval: BlockNumber, from: BlockNumber, to: BlockNumber) -> Vec<Kline<Price, Balance, BlockNumber>>;
+
+        /// Get the violated invariants of the order book and the reserved balances.
+        fn integrity_check() -> Vec<IntegrityViolation<AccountId, Balance, Price>>;
     }
 }
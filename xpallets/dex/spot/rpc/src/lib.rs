@@ -9,9 +9,15 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use codec::Codec;
+use futures::{future, stream, StreamExt, TryStreamExt};
+use jsonrpc_core::futures::{Future as Future01, Sink as Sink01};
 use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use log::warn;
 use serde::{Deserialize, Serialize};
 
+use sc_client_api::BlockchainEvents;
+use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
@@ -19,7 +25,8 @@ use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 use xp_rpc::{runtime_error_into_rpc_err, Result, RpcBalance, RpcPrice};
 
 use xpallet_dex_spot_rpc_runtime_api::{
-    Depth, FullPairInfo, Handicap, OrderProperty, RpcOrder, TradingPairId, TradingPairInfo,
+    Depth, FullPairInfo, Handicap, IntegrityViolation, Kline, OrderExecutedInfo, OrderProperty,
+    OrderSizeLimit, RpcOrder, TradingHistoryIndex, TradingPairId, TradingPairInfo,
     XSpotApi as XSpotRuntimeApi,
 };
 
@@ -30,12 +37,15 @@ where
     Balance: Display + FromStr,
     Price: Display + FromStr,
 {
+    /// RPC metadata
+    type Metadata;
+
     /// Get the overall info of all trading pairs.
     #[rpc(name = "xspot_getTradingPairs")]
     fn trading_pairs(
         &self,
         at: Option<BlockHash>,
-    ) -> Result<Vec<FullPairInfo<RpcPrice<Price>, BlockNumber>>>;
+    ) -> Result<Vec<FullPairInfo<RpcPrice<Price>, RpcBalance<Balance>, BlockNumber>>>;
 
     /// Get the orders of an account.
     #[rpc(name = "xspot_getOrdersByAccount")]
@@ -67,19 +77,123 @@ where
         depth_size: u32,
         at: Option<BlockHash>,
     ) -> Result<Option<Depth<RpcPrice<Price>, RpcBalance<Balance>>>>;
+
+    /// Get the recent executions of a trading pair.
+    #[rpc(name = "xspot_getTradeHistory")]
+    fn trade_history(
+        &self,
+        pair_id: TradingPairId,
+        from_index: TradingHistoryIndex,
+        limit: u32,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<OrderExecutedInfo<AccountId, RpcBalance<Balance>, BlockNumber, RpcPrice<Price>>>>;
+
+    /// Get the klines of a trading pair given the interval.
+    #[rpc(name = "xspot_getKlines")]
+    fn klines(
+        &self,
+        pair_id: TradingPairId,
+        interval: BlockNumber,
+        from: BlockNumber,
+        to: BlockNumber,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<Kline<RpcPrice<Price>, RpcBalance<Balance>, BlockNumber>>>;
+
+    /// Get the violated invariants of the order book and the reserved balances.
+    ///
+    /// This is an unsafe RPC since it iterates all the orders and reserved balances.
+    #[rpc(name = "xspot_checkIntegrity")]
+    fn integrity_check(
+        &self,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<IntegrityViolation<AccountId, RpcBalance<Balance>, RpcPrice<Price>>>>;
+
+    /// Subscribe to the depth of a trading pair.
+    ///
+    /// A new snapshot is pushed whenever the depth changes in a new best block.
+    #[pubsub(subscription = "xspot_depth", subscribe, name = "xspot_subscribeDepth")]
+    fn subscribe_depth(
+        &self,
+        metadata: Self::Metadata,
+        subscriber: Subscriber<Option<Depth<RpcPrice<Price>, RpcBalance<Balance>>>>,
+        pair_id: TradingPairId,
+        depth_size: u32,
+    );
+
+    /// Unsubscribe from the depth of a trading pair.
+    #[pubsub(
+        subscription = "xspot_depth",
+        unsubscribe,
+        name = "xspot_unsubscribeDepth"
+    )]
+    fn unsubscribe_depth(
+        &self,
+        metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+
+    /// Subscribe to the orders of an account.
+    ///
+    /// A new snapshot is pushed whenever the orders change in a new best block.
+    #[pubsub(
+        subscription = "xspot_orders",
+        subscribe,
+        name = "xspot_subscribeOrders"
+    )]
+    fn subscribe_orders(
+        &self,
+        metadata: Self::Metadata,
+        subscriber: Subscriber<
+            Page<
+                Vec<
+                    RpcOrder<
+                        TradingPairId,
+                        AccountId,
+                        RpcBalance<Balance>,
+                        RpcPrice<Price>,
+                        BlockNumber,
+                    >,
+                >,
+            >,
+        >,
+        who: AccountId,
+        page_index: u32,
+        page_size: u32,
+    );
+
+    /// Unsubscribe from the orders of an account.
+    #[pubsub(
+        subscription = "xspot_orders",
+        unsubscribe,
+        name = "xspot_unsubscribeOrders"
+    )]
+    fn unsubscribe_orders(
+        &self,
+        metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
 }
 
 /// A struct that implements the [`XSpotApi`].
 pub struct XSpot<C, B> {
     client: Arc<C>,
+    manager: SubscriptionManager,
+    deny_unsafe: DenyUnsafe,
     _marker: std::marker::PhantomData<B>,
 }
 
 impl<C, B> XSpot<C, B> {
-    /// Create new `Contracts` with the given reference to the client.
-    pub fn new(client: Arc<C>) -> Self {
+    /// Create new `XSpot` with the given reference to the client,
+    /// the executor driving the subscriptions and the unsafe RPC policy.
+    pub fn new(
+        client: Arc<C>,
+        executor: SubscriptionTaskExecutor,
+        deny_unsafe: DenyUnsafe,
+    ) -> Self {
         Self {
             client,
+            manager: SubscriptionManager::new(Arc::new(executor)),
+            deny_unsafe,
             _marker: Default::default(),
         }
     }
@@ -89,17 +203,24 @@ impl<C, Block, AccountId, Balance, BlockNumber, Price>
     XSpotApi<<Block as BlockT>::Hash, AccountId, Balance, BlockNumber, Price> for XSpot<C, Block>
 where
     Block: BlockT,
-    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C: Send
+        + Sync
+        + 'static
+        + ProvideRuntimeApi<Block>
+        + HeaderBackend<Block>
+        + BlockchainEvents<Block>,
     C::Api: XSpotRuntimeApi<Block, AccountId, Balance, BlockNumber, Price>,
-    AccountId: Codec,
-    Balance: Codec + Display + FromStr,
-    BlockNumber: Codec,
-    Price: Codec + Display + FromStr,
+    AccountId: Codec + Clone + PartialEq + Serialize + Send + Sync + 'static,
+    Balance: Codec + Display + FromStr + Clone + PartialEq + Send + Sync + 'static,
+    BlockNumber: Codec + Clone + PartialEq + Serialize + Send + Sync + 'static,
+    Price: Codec + Display + FromStr + Clone + PartialEq + Send + Sync + 'static,
 {
+    type Metadata = sc_rpc::Metadata;
+
     fn trading_pairs(
         &self,
         at: Option<<Block as BlockT>::Hash>,
-    ) -> Result<Vec<FullPairInfo<RpcPrice<Price>, BlockNumber>>> {
+    ) -> Result<Vec<FullPairInfo<RpcPrice<Price>, RpcBalance<Balance>, BlockNumber>>> {
         let api = self.client.runtime_api();
         let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
         Ok(api
@@ -107,21 +228,27 @@ where
             .map(|trading_pairs| {
                 trading_pairs
                     .into_iter()
-                    .map(
-                        |trading_pairs| FullPairInfo::<RpcPrice<Price>, BlockNumber> {
-                            profile: trading_pairs.profile,
-                            handicap: Handicap {
-                                highest_bid: trading_pairs.handicap.highest_bid.into(),
-                                lowest_ask: trading_pairs.handicap.lowest_ask.into(),
-                            },
-                            pair_info: TradingPairInfo {
-                                latest_price: trading_pairs.pair_info.latest_price.into(),
-                                last_updated: trading_pairs.pair_info.last_updated,
-                            },
-                            max_valid_bid: trading_pairs.max_valid_bid.into(),
-                            min_valid_ask: trading_pairs.min_valid_ask.into(),
+                    .map(|trading_pairs| FullPairInfo::<
+                        RpcPrice<Price>,
+                        RpcBalance<Balance>,
+                        BlockNumber,
+                    > {
+                        profile: trading_pairs.profile,
+                        handicap: Handicap {
+                            highest_bid: trading_pairs.handicap.highest_bid.map(Into::into),
+                            lowest_ask: trading_pairs.handicap.lowest_ask.map(Into::into),
+                        },
+                        pair_info: TradingPairInfo {
+                            latest_price: trading_pairs.pair_info.latest_price.into(),
+                            last_updated: trading_pairs.pair_info.last_updated,
+                        },
+                        order_size_limit: OrderSizeLimit {
+                            min_amount: trading_pairs.order_size_limit.min_amount.into(),
+                            lot_size: trading_pairs.order_size_limit.lot_size.into(),
                         },
-                    )
+                        max_valid_bid: trading_pairs.max_valid_bid.into(),
+                        min_valid_ask: trading_pairs.min_valid_ask.into(),
+                    })
                     .collect::<Vec<_>>()
             })
             .map_err(runtime_error_into_rpc_err)?)
@@ -146,70 +273,322 @@ where
             >,
         >,
     > {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        query_orders(&*self.client, &at, who, page_index, page_size)
+    }
+
+    fn depth(
+        &self,
+        pair_id: TradingPairId,
+        depth_size: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<Depth<RpcPrice<Price>, RpcBalance<Balance>>>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        query_depth(&*self.client, &at, pair_id, depth_size)
+    }
+
+    fn trade_history(
+        &self,
+        pair_id: TradingPairId,
+        from_index: TradingHistoryIndex,
+        limit: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<OrderExecutedInfo<AccountId, RpcBalance<Balance>, BlockNumber, RpcPrice<Price>>>>
+    {
         let api = self.client.runtime_api();
         let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
-        let data = api
-            .orders(&at, who, page_index, page_size)
-            .map(|orders| {
-                orders
+        Ok(api
+            .trade_history(&at, pair_id, from_index, limit)
+            .map(|history| {
+                history
                     .into_iter()
-                    .map(|order| RpcOrder {
-                        props: OrderProperty {
-                            id: order.props.id,
-                            side: order.props.side,
-                            price: order.props.price.into(),
-                            amount: order.props.amount.into(),
-                            pair_id: order.props.pair_id,
-                            submitter: order.props.submitter,
-                            order_type: order.props.order_type,
-                            created_at: order.props.created_at,
-                        },
-                        status: order.status,
-                        remaining: order.remaining.into(),
-                        executed_indices: order.executed_indices,
-                        already_filled: order.already_filled.into(),
-                        reserved_balance: order.reserved_balance.into(),
-                        last_update_at: order.last_update_at,
+                    .map(|info| OrderExecutedInfo {
+                        trading_history_idx: info.trading_history_idx,
+                        pair_id: info.pair_id,
+                        price: info.price.into(),
+                        maker: info.maker,
+                        taker: info.taker,
+                        maker_order_id: info.maker_order_id,
+                        taker_order_id: info.taker_order_id,
+                        turnover: info.turnover.into(),
+                        maker_fee: info.maker_fee.into(),
+                        taker_fee: info.taker_fee.into(),
+                        executed_at: info.executed_at,
                     })
                     .collect::<Vec<_>>()
             })
-            .map_err(runtime_error_into_rpc_err)?;
-        Ok(Page {
-            page_index,
-            page_size,
-            data,
-        })
+            .map_err(runtime_error_into_rpc_err)?)
     }
 
-    fn depth(
+    fn klines(
         &self,
         pair_id: TradingPairId,
-        depth_size: u32,
+        interval: BlockNumber,
+        from: BlockNumber,
+        to: BlockNumber,
         at: Option<<Block as BlockT>::Hash>,
-    ) -> Result<Option<Depth<RpcPrice<Price>, RpcBalance<Balance>>>> {
+    ) -> Result<Vec<Kline<RpcPrice<Price>, RpcBalance<Balance>, BlockNumber>>> {
         let api = self.client.runtime_api();
         let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
-        match api.depth(&at, pair_id, depth_size) {
-            Ok(Some(depth)) => {
-                let asks = depth
-                    .asks
+        Ok(api
+            .klines(&at, pair_id, interval, from, to)
+            .map(|klines| {
+                klines
                     .into_iter()
-                    .map(|(price, quantity)| (price.into(), quantity.into()))
-                    .collect::<Vec<_>>();
-                let bids = depth
-                    .bids
+                    .map(|kline| Kline {
+                        start_at: kline.start_at,
+                        open: kline.open.into(),
+                        high: kline.high.into(),
+                        low: kline.low.into(),
+                        close: kline.close.into(),
+                        volume: kline.volume.into(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map_err(runtime_error_into_rpc_err)?)
+    }
+
+    fn integrity_check(
+        &self,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<IntegrityViolation<AccountId, RpcBalance<Balance>, RpcPrice<Price>>>> {
+        self.deny_unsafe.check_if_safe()?;
+
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        Ok(api
+            .integrity_check(&at)
+            .map(|violations| {
+                violations
                     .into_iter()
-                    .map(|(price, quantity)| (price.into(), quantity.into()))
-                    .collect::<Vec<_>>();
-                Ok(Some(Depth { asks, bids }))
+                    .map(|violation| match violation {
+                        IntegrityViolation::InconsistentPriceLevel(pair_id, price) => {
+                            IntegrityViolation::InconsistentPriceLevel(pair_id, price.into())
+                        }
+                        IntegrityViolation::BrokenQuotations(pair_id, price) => {
+                            IntegrityViolation::BrokenQuotations(pair_id, price.into())
+                        }
+                        IntegrityViolation::DanglingQuotation(pair_id, price, who, order_id) => {
+                            IntegrityViolation::DanglingQuotation(
+                                pair_id,
+                                price.into(),
+                                who,
+                                order_id,
+                            )
+                        }
+                        IntegrityViolation::MissingQuotation(who, order_id) => {
+                            IntegrityViolation::MissingQuotation(who, order_id)
+                        }
+                        IntegrityViolation::ReservedMismatch(who, asset_id, expected, actual) => {
+                            IntegrityViolation::ReservedMismatch(
+                                who,
+                                asset_id,
+                                expected.into(),
+                                actual.into(),
+                            )
+                        }
+                        IntegrityViolation::HandicapMismatch(pair_id, side, expected, actual) => {
+                            IntegrityViolation::HandicapMismatch(
+                                pair_id,
+                                side,
+                                expected.map(Into::into),
+                                actual.map(Into::into),
+                            )
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map_err(runtime_error_into_rpc_err)?)
+    }
+
+    fn subscribe_depth(
+        &self,
+        _metadata: Self::Metadata,
+        subscriber: Subscriber<Option<Depth<RpcPrice<Price>, RpcBalance<Balance>>>>,
+        pair_id: TradingPairId,
+        depth_size: u32,
+    ) {
+        let client = self.client.clone();
+        subscribe_best_blocks(&self.manager, &self.client, subscriber, move |at| {
+            query_depth(&*client, at, pair_id, depth_size)
+        });
+    }
+
+    fn unsubscribe_depth(
+        &self,
+        _metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.manager.cancel(id))
+    }
+
+    fn subscribe_orders(
+        &self,
+        _metadata: Self::Metadata,
+        subscriber: Subscriber<
+            Page<
+                Vec<
+                    RpcOrder<
+                        TradingPairId,
+                        AccountId,
+                        RpcBalance<Balance>,
+                        RpcPrice<Price>,
+                        BlockNumber,
+                    >,
+                >,
+            >,
+        >,
+        who: AccountId,
+        page_index: u32,
+        page_size: u32,
+    ) {
+        let client = self.client.clone();
+        subscribe_best_blocks(&self.manager, &self.client, subscriber, move |at| {
+            query_orders(&*client, at, who.clone(), page_index, page_size)
+        });
+    }
+
+    fn unsubscribe_orders(
+        &self,
+        _metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.manager.cancel(id))
+    }
+}
+
+/// Pushes the result of `query` at the current best block and at each new best block
+/// to the subscriber, the result is only pushed when it differs from the last one.
+fn subscribe_best_blocks<C, Block, T, F>(
+    manager: &SubscriptionManager,
+    client: &Arc<C>,
+    subscriber: Subscriber<T>,
+    query: F,
+) where
+    Block: BlockT,
+    C: HeaderBackend<Block> + BlockchainEvents<Block>,
+    T: Serialize + Clone + PartialEq + Send + 'static,
+    F: Fn(&BlockId<Block>) -> Result<T> + Send + 'static,
+{
+    let initial = query(&BlockId::hash(client.info().best_hash));
+    let mut last = None;
+    let stream = stream::once(future::ready(initial))
+        .chain(
+            client
+                .import_notification_stream()
+                .filter(|notification| future::ready(notification.is_new_best))
+                .map(move |notification| query(&BlockId::hash(notification.hash))),
+        )
+        .filter(move |result| {
+            let changed = last.as_ref() != Some(result);
+            if changed {
+                last = Some(result.clone());
             }
-            Ok(None) => Ok(None),
-            Err(err) => Err(runtime_error_into_rpc_err(err)),
+            future::ready(changed)
+        })
+        .map(Ok::<_, ()>)
+        .compat();
+
+    manager.add(subscriber, |sink| {
+        sink.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+            .send_all(stream)
+            .map(|_| ())
+    });
+}
+
+fn query_orders<C, Block, AccountId, Balance, BlockNumber, Price>(
+    client: &C,
+    at: &BlockId<Block>,
+    who: AccountId,
+    page_index: u32,
+    page_size: u32,
+) -> Result<
+    Page<
+        Vec<RpcOrder<TradingPairId, AccountId, RpcBalance<Balance>, RpcPrice<Price>, BlockNumber>>,
+    >,
+>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+    C::Api: XSpotRuntimeApi<Block, AccountId, Balance, BlockNumber, Price>,
+    AccountId: Codec,
+    Balance: Codec + Display + FromStr,
+    BlockNumber: Codec,
+    Price: Codec + Display + FromStr,
+{
+    let api = client.runtime_api();
+    let data = api
+        .orders(at, who, page_index, page_size)
+        .map(|orders| {
+            orders
+                .into_iter()
+                .map(|order| RpcOrder {
+                    props: OrderProperty {
+                        id: order.props.id,
+                        side: order.props.side,
+                        price: order.props.price.into(),
+                        amount: order.props.amount.into(),
+                        pair_id: order.props.pair_id,
+                        submitter: order.props.submitter,
+                        order_type: order.props.order_type,
+                        time_in_force: order.props.time_in_force,
+                        self_trade_prevention: order.props.self_trade_prevention,
+                        created_at: order.props.created_at,
+                        expire_at: order.props.expire_at,
+                    },
+                    status: order.status,
+                    remaining: order.remaining.into(),
+                    executed_indices: order.executed_indices,
+                    already_filled: order.already_filled.into(),
+                    reserved_balance: order.reserved_balance.into(),
+                    last_update_at: order.last_update_at,
+                })
+                .collect::<Vec<_>>()
+        })
+        .map_err(runtime_error_into_rpc_err)?;
+    Ok(Page {
+        page_index,
+        page_size,
+        data,
+    })
+}
+
+fn query_depth<C, Block, AccountId, Balance, BlockNumber, Price>(
+    client: &C,
+    at: &BlockId<Block>,
+    pair_id: TradingPairId,
+    depth_size: u32,
+) -> Result<Option<Depth<RpcPrice<Price>, RpcBalance<Balance>>>>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+    C::Api: XSpotRuntimeApi<Block, AccountId, Balance, BlockNumber, Price>,
+    AccountId: Codec,
+    Balance: Codec + Display + FromStr,
+    BlockNumber: Codec,
+    Price: Codec + Display + FromStr,
+{
+    let api = client.runtime_api();
+    match api.depth(at, pair_id, depth_size) {
+        Ok(Some(depth)) => {
+            let asks = depth
+                .asks
+                .into_iter()
+                .map(|(price, quantity)| (price.into(), quantity.into()))
+                .collect::<Vec<_>>();
+            let bids = depth
+                .bids
+                .into_iter()
+                .map(|(price, quantity)| (price.into(), quantity.into()))
+                .collect::<Vec<_>>();
+            Ok(Some(Depth { asks, bids }))
         }
+        Ok(None) => Ok(None),
+        Err(err) => Err(runtime_error_into_rpc_err(err)),
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Page<T> {
     pub page_index: u32,
@@ -2,12 +2,15 @@
 
 pub use frame_benchmarking::{account, benchmarks};
 use frame_system::RawOrigin;
+use xp_mining_staking::DexMining;
 use xp_protocol::X_BTC;
+use xpallet_assets::{AssetInfo, Chain};
 
 use super::*;
 
 const EOS: AssetId = 8888;
 const ETH: AssetId = 9999;
+const LP_ASSET: AssetId = 7777;
 
 const SEED: u32 = 0;
 const PAIR_ID: u32 = 0;
@@ -38,6 +41,100 @@ fn b_put_order<T: Trait>(
         Side::Buy,
         pcx_value.into(),
         price.into(),
+        TimeInForce::GoodTillCancel,
+        None,
+        None,
+    )
+    .map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Puts `m` sell orders of 1000 at 1_000_200, each of a different maker.
+fn b_put_maker_orders<T: Trait>(m: u32) -> DispatchResult {
+    for i in 0..m {
+        let maker: T::AccountId = account("maker", i, SEED);
+        b_prepare_put_order::<T>(&maker, 2000, 0)?;
+        Module::<T>::put_order(
+            RawOrigin::Signed(maker).into(),
+            PAIR_ID,
+            OrderType::Limit,
+            Side::Sell,
+            1000.into(),
+            1_000_200.into(),
+            TimeInForce::GoodTillCancel,
+            None,
+            None,
+        )
+        .map_err(|e| e.error)?;
+    }
+    Ok(())
+}
+
+/// Indexes `MAX_PRICE_LEVELS_PER_SIDE - 1` empty price levels below the ones of the
+/// orders put in the benchmarks, so that indexing one more rewrites the largest levels.
+fn b_fill_price_levels<T: Trait>(side: Side) {
+    let levels = (1..MAX_PRICE_LEVELS_PER_SIDE as u32)
+        .map(|i| (i * 100).into())
+        .collect::<Vec<T::Price>>();
+    PriceLevelsOf::<T>::insert(PAIR_ID, side, levels);
+}
+
+/// Returns true if all the `m` maker orders put by `b_put_maker_orders` have been filled.
+fn b_maker_orders_filled<T: Trait>(m: u32) -> bool {
+    (0..m).all(|i| {
+        let maker: T::AccountId = account("maker", i, SEED);
+        OrderInfoOf::<T>::get(maker, 0).is_none()
+    })
+}
+
+fn b_set_maker_mining_rule<T: Trait>() -> DispatchResult {
+    Module::<T>::set_maker_mining_rule(
+        RawOrigin::Root.into(),
+        PAIR_ID,
+        Some(MakerMiningRule {
+            power: 1,
+            band: Permill::from_percent(1),
+        }),
+    )
+}
+
+fn b_register_lp_asset<T: Trait>() -> DispatchResult {
+    let lp_asset = AssetInfo::new::<T>(
+        b"PCX-BTC-LP".to_vec(),
+        b"PCX/X-BTC LP".to_vec(),
+        Chain::ChainX,
+        8,
+        b"The shares of PCX/X-BTC liquidity pool".to_vec(),
+    )?;
+    xpallet_assets_registrar::Module::<T>::register(
+        RawOrigin::Root.into(),
+        LP_ASSET,
+        lp_asset,
+        true,
+        false,
+    )?;
+    Ok(())
+}
+
+fn b_create_liquidity_pool<T: Trait>() -> DispatchResult {
+    b_register_lp_asset::<T>()?;
+    Module::<T>::create_liquidity_pool(
+        RawOrigin::Root.into(),
+        PAIR_ID,
+        LP_ASSET,
+        Permill::from_parts(3_000),
+    )?;
+    Ok(())
+}
+
+fn b_add_liquidity<T: Trait>(user: T::AccountId) -> DispatchResult {
+    b_prepare_put_order::<T>(&user, 2_000_000, 100_000)?;
+    Module::<T>::add_liquidity(
+        RawOrigin::Signed(user).into(),
+        PAIR_ID,
+        1_000_000.into(),
+        100_000.into(),
+        0.into(),
     )?;
     Ok(())
 }
@@ -48,15 +145,23 @@ benchmarks! {
         let u in 0 .. 1000 => ();
     }
 
-    // TODO: put_order with matching.
     put_order {
+        let m in 0 .. MAX_MAKERS_PER_ORDER;
+
         let user: T::AccountId = account("user", u, SEED);
 
-        b_prepare_put_order::<T>(&user, 1000, 100)?;
+        b_put_maker_orders::<T>(m)?;
+        b_fill_price_levels::<T>(Side::Buy);
+        b_prepare_put_order::<T>(&user, 1000, 100 + m)?;
 
-    }: put_order(RawOrigin::Signed(user.clone()), PAIR_ID, OrderType::Limit, Side::Buy, 1000.into(), 1_000_200.into())
+    }: put_order(RawOrigin::Signed(user.clone()), PAIR_ID, OrderType::Limit, Side::Buy, (1000 * (m + 2)).into(), 1_000_200.into(), TimeInForce::GoodTillCancel, None, None)
     verify {
-        assert!(OrderInfoOf::<T>::get(user, 0).is_some());
+        assert!(b_maker_orders_filled::<T>(m));
+        // The unfilled remainder rests at the last price level that can be indexed,
+        // unless it's canceled due to the maker cap.
+        if m < MAX_MAKERS_PER_ORDER {
+            assert_eq!(PriceLevelsOf::<T>::get(PAIR_ID, Side::Buy).len(), MAX_PRICE_LEVELS_PER_SIDE);
+        }
     }
 
     cancel_order {
@@ -81,16 +186,276 @@ benchmarks! {
         assert!(OrderInfoOf::<T>::get(user, 0).is_none());
     }
 
-    set_handicap {
-    }: _(RawOrigin::Root, PAIR_ID, Handicap::new(100.into(), 110.into()))
+    put_orders {
+        let n in 1 .. MAX_ORDERS_PER_BATCH as u32;
+        let m in 0 .. MAX_MAKERS_PER_ORDER;
+
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_put_maker_orders::<T>(m)?;
+        b_prepare_put_order::<T>(&user, 1000, n + m + 1)?;
+        let first = Module::<T>::order_count_of(&user);
+
+        // The first order is matched against all the makers, the rest rest in the order book.
+        let orders = (0..n)
+            .map(|i| OrderRequest {
+                pair_id: PAIR_ID,
+                order_type: OrderType::Limit,
+                side: Side::Buy,
+                amount: (if i == 0 { 1000 * (m + 1) } else { 1000 }).into(),
+                price: 1_000_200.into(),
+                time_in_force: TimeInForce::GoodTillCancel,
+                expire_at: None,
+                self_trade_prevention: None,
+            })
+            .collect::<Vec<_>>();
+    }: _(RawOrigin::Signed(user.clone()), orders)
     verify {
-        assert_eq!(HandicapOf::<T>::get(PAIR_ID), Handicap { highest_bid: 100.into(), lowest_ask: 110.into() });
+        assert!(b_maker_orders_filled::<T>(m));
+        assert_eq!(Module::<T>::order_count_of(&user), first + n as OrderId);
+    }
+
+    cancel_orders {
+        let n in 1 .. MAX_ORDERS_PER_BATCH as u32;
+
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_prepare_put_order::<T>(&user, 1000, n)?;
+        let first = Module::<T>::order_count_of(&user);
+        for _ in 0..n {
+            Module::<T>::put_order(
+                RawOrigin::Signed(user.clone()).into(),
+                PAIR_ID,
+                OrderType::Limit,
+                Side::Buy,
+                1000.into(),
+                1_000_200.into(),
+                TimeInForce::GoodTillCancel,
+                None,
+                None,
+            )
+            .map_err(|e| e.error)?;
+        }
+
+        let orders = (first..first + n as OrderId).map(|order_id| (PAIR_ID, order_id)).collect::<Vec<_>>();
+    }: _(RawOrigin::Signed(user.clone()), orders)
+    verify {
+        assert!(OrderInfoOf::<T>::get(user, first).is_none());
+    }
+
+    replace_order {
+        let m in 0 .. MAX_MAKERS_PER_ORDER;
+
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_put_order::<T>(user.clone(), 1000, 100, 1_000_100)?;
+        b_put_maker_orders::<T>(m)?;
+
+    }: _(RawOrigin::Signed(user.clone()), PAIR_ID, 0, (1000 * (m + 1)).into(), 1_000_200.into())
+    verify {
+        assert!(OrderInfoOf::<T>::get(&user, 0).is_none());
+        assert!(b_maker_orders_filled::<T>(m));
+    }
+
+    put_conditional_order {
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_prepare_put_order::<T>(&user, 1000, 100)?;
+
+    }: _(RawOrigin::Signed(user.clone()), PAIR_ID, ConditionalOrderKind::StopLimit, Side::Buy, 1000.into(), 1_000_200.into(), 1_000_100.into())
+    verify {
+        assert!(ConditionalOrderInfoOf::<T>::get(user, 0).is_some());
+    }
+
+    cancel_conditional_order {
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_prepare_put_order::<T>(&user, 1000, 100)?;
+        Module::<T>::put_conditional_order(
+            RawOrigin::Signed(user.clone()).into(),
+            PAIR_ID,
+            ConditionalOrderKind::StopLimit,
+            Side::Buy,
+            1000.into(),
+            1_000_200.into(),
+            1_000_100.into(),
+        )?;
+
+    }: _(RawOrigin::Signed(user.clone()), 0)
+    verify {
+        assert!(ConditionalOrderInfoOf::<T>::get(user, 0).is_none());
+    }
+
+    cancel_all_orders {
+        let n in 1 .. MAX_ORDERS_PER_BATCH as u32;
+
+        let user: T::AccountId = account("user", u, SEED);
+
+        for _ in 0..n {
+            b_put_order::<T>(user.clone(), 1000, 1, 1_000_200)?;
+        }
+    }: _(RawOrigin::Signed(user.clone()), Some(PAIR_ID))
+    verify {
+        assert_eq!(OrderInfoOf::<T>::iter_prefix_values(&user).count(), 0);
+    }
+
+    force_cancel_all_orders {
+        let n in 1 .. MAX_ORDERS_PER_BATCH as u32;
+
+        let user: T::AccountId = account("user", u, SEED);
+
+        for _ in 0..n {
+            b_put_order::<T>(user.clone(), 1000, 1, 1_000_200)?;
+        }
+        let tick_decimals = Module::<T>::trading_pair(PAIR_ID)?.tick_decimals;
+        Module::<T>::update_trading_pair(RawOrigin::Root.into(), PAIR_ID, tick_decimals, false)?;
+    }: _(RawOrigin::Root, None, Some(PAIR_ID))
+    verify {
+        assert_eq!(OrderInfoOf::<T>::iter_prefix_values(&user).count(), 0);
     }
 
     set_price_fluctuation {
-    }: _(RawOrigin::Root, PAIR_ID, 1000)
+    }: _(RawOrigin::Root, PAIR_ID, 2000)
+    verify {
+        assert_eq!(PriceFluctuationOf::get(PAIR_ID), 2000);
+    }
+
+    set_trading_fee {
+    }: _(RawOrigin::Root, PAIR_ID, Permill::from_parts(1_000), Permill::from_parts(2_000))
+    verify {
+        assert_eq!(
+            TradingFeeOf::get(PAIR_ID),
+            TradingFee::new(Permill::from_parts(1_000), Permill::from_parts(2_000))
+        );
+    }
+
+    set_fee_destination {
+        let receiver: T::AccountId = account("receiver", 0, SEED);
+        let receiver_lookup: <T::Lookup as StaticLookup>::Source = T::Lookup::unlookup(receiver.clone());
+    }: _(RawOrigin::Root, Some(receiver_lookup))
+    verify {
+        assert_eq!(FeeDestination::<T>::get(), Some(receiver));
+    }
+
+    set_order_size_limit {
+        let min_amount: BalanceOf<T> = 1_000_000u32.into();
+        let lot_size: BalanceOf<T> = 100_000u32.into();
+    }: _(RawOrigin::Root, PAIR_ID, min_amount, lot_size)
+    verify {
+        assert_eq!(
+            OrderSizeLimitOf::<T>::get(PAIR_ID),
+            OrderSizeLimit::new(min_amount, lot_size)
+        );
+    }
+
+    set_self_trade_prevention {
+    }: _(RawOrigin::Root, PAIR_ID, SelfTradePrevention::CancelBoth)
+    verify {
+        assert_eq!(SelfTradePreventionOf::get(PAIR_ID), SelfTradePrevention::CancelBoth);
+    }
+
+    set_kline_intervals {
+        let intervals: Vec<T::BlockNumber> = (1..=MAX_KLINE_INTERVALS as u32).map(Into::into).collect();
+    }: _(RawOrigin::Root, intervals.clone())
+    verify {
+        assert_eq!(KlineIntervals::<T>::get(), intervals);
+    }
+
+    create_liquidity_pool {
+        b_register_lp_asset::<T>()?;
+    }: _(RawOrigin::Root, PAIR_ID, LP_ASSET, Permill::from_parts(3_000))
+    verify {
+        assert_eq!(LiquidityPoolOf::<T>::get(PAIR_ID).unwrap().lp_asset_id, LP_ASSET);
+    }
+
+    add_liquidity {
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_create_liquidity_pool::<T>()?;
+        b_prepare_put_order::<T>(&user, 2_000_000, 100_000)?;
+    }: _(RawOrigin::Signed(user.clone()), PAIR_ID, 1_000_000.into(), 100_000.into(), 0.into())
+    verify {
+        let pool = LiquidityPoolOf::<T>::get(PAIR_ID).unwrap();
+        assert_eq!(pool.base_reserve, 1_000_000.into());
+        assert_eq!(pool.quote_reserve, 100_000.into());
+    }
+
+    remove_liquidity {
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_create_liquidity_pool::<T>()?;
+        b_add_liquidity::<T>(user.clone())?;
+        let shares = LiquidityPoolOf::<T>::get(PAIR_ID).unwrap().total_shares;
+    }: _(RawOrigin::Signed(user.clone()), PAIR_ID, shares, 0.into(), 0.into())
     verify {
-        assert_eq!(PriceFluctuationOf::get(PAIR_ID), 1000);
+        assert!(LiquidityPoolOf::<T>::get(PAIR_ID).unwrap().total_shares.is_zero());
+    }
+
+    swap {
+        let provider: T::AccountId = account("provider", u, SEED);
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_create_liquidity_pool::<T>()?;
+        b_add_liquidity::<T>(provider)?;
+        b_prepare_put_order::<T>(&user, 2_000_000, 100_000)?;
+    }: _(RawOrigin::Signed(user.clone()), PAIR_ID, Side::Sell, 1_000.into(), 0.into())
+    verify {
+        let pool = LiquidityPoolOf::<T>::get(PAIR_ID).unwrap();
+        assert_eq!(pool.base_reserve, 1_001_000.into());
+    }
+
+    swap_exact_in {
+        let m in 1 .. MAX_MAKERS_PER_ORDER;
+
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_put_maker_orders::<T>(m)?;
+        b_prepare_put_order::<T>(&user, 0, 100)?;
+    }: _(RawOrigin::Signed(user.clone()), vec![X_BTC, T::NativeAssetId::get()], m.into(), 1.into())
+    verify {
+        assert!(!<T as xpallet_assets::Trait>::Currency::free_balance(&user).is_zero());
+    }
+
+    claim_maker_reward {
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_set_maker_mining_rule::<T>()?;
+        b_put_order::<T>(user.clone(), 1000, 100, 1_000_200)?;
+        frame_system::Module::<T>::set_block_number(frame_system::Module::<T>::block_number() + 10u32.into());
+        <Module<T> as DexMining<BalanceOf<T>>>::reward(1_000.into());
+    }: _(RawOrigin::Signed(user.clone()))
+    verify {
+        assert!(<T as xpallet_assets::Trait>::Currency::free_balance(&Module::<T>::dex_reward_pot()).is_zero());
+    }
+
+    set_maker_mining_rule {
+        let user: T::AccountId = account("user", u, SEED);
+
+        b_put_order::<T>(user.clone(), 1000, 100, 1_000_200)?;
+        let rule = MakerMiningRule {
+            power: 1,
+            band: Permill::from_percent(1),
+        };
+    }: _(RawOrigin::Root, PAIR_ID, Some(rule))
+    verify {
+        assert_eq!(Module::<T>::mining_refresh_queue_tail(), 1);
+    }
+
+    schedule_delisting {
+        let at = frame_system::Module::<T>::block_number() + 10u32.into();
+    }: _(RawOrigin::Root, PAIR_ID, at)
+    verify {
+        assert_eq!(DelistingAt::<T>::get(PAIR_ID), Some(at));
+    }
+
+    relist_trading_pair {
+        let now = frame_system::Module::<T>::block_number();
+        Module::<T>::schedule_delisting(RawOrigin::Root.into(), PAIR_ID, now + 1u32.into())?;
+        Module::<T>::delist_trading_pairs(now + 1u32.into());
+    }: _(RawOrigin::Root, PAIR_ID, 2, 1_000_000.into())
+    verify {
+        assert!(Module::<T>::trading_pair_of(PAIR_ID).unwrap().tradable);
+        assert!(DelistedAt::<T>::get(PAIR_ID).is_none());
     }
 
     add_trading_pair {
@@ -123,7 +488,7 @@ benchmarks! {
 mod tests {
     use super::*;
     use crate::mock::{ExtBuilder, Test, XSpot};
-    use crate::tests::{t_generic_issue, t_issue_pcx, t_set_handicap};
+    use crate::tests::{t_generic_issue, t_issue_pcx};
     use frame_support::assert_ok;
 
     #[test]
@@ -133,8 +498,6 @@ mod tests {
             let who = 1;
             let trading_pair = XSpot::trading_pair_of(pair_id).unwrap();
 
-            t_set_handicap(pair_id, 1_000_000, 1_100_000);
-
             // Reserve asset.
             t_generic_issue(trading_pair.quote(), who, 10);
 
@@ -144,8 +507,28 @@ mod tests {
             assert_ok!(test_benchmark_put_order::<Test>());
             assert_ok!(test_benchmark_cancel_order::<Test>());
             assert_ok!(test_benchmark_force_cancel_order::<Test>());
-            assert_ok!(test_benchmark_set_handicap::<Test>());
+            assert_ok!(test_benchmark_put_orders::<Test>());
+            assert_ok!(test_benchmark_cancel_orders::<Test>());
+            assert_ok!(test_benchmark_replace_order::<Test>());
+            assert_ok!(test_benchmark_put_conditional_order::<Test>());
+            assert_ok!(test_benchmark_cancel_conditional_order::<Test>());
+            assert_ok!(test_benchmark_cancel_all_orders::<Test>());
+            assert_ok!(test_benchmark_force_cancel_all_orders::<Test>());
             assert_ok!(test_benchmark_set_price_fluctuation::<Test>());
+            assert_ok!(test_benchmark_set_trading_fee::<Test>());
+            assert_ok!(test_benchmark_set_fee_destination::<Test>());
+            assert_ok!(test_benchmark_set_order_size_limit::<Test>());
+            assert_ok!(test_benchmark_set_self_trade_prevention::<Test>());
+            assert_ok!(test_benchmark_set_kline_intervals::<Test>());
+            assert_ok!(test_benchmark_create_liquidity_pool::<Test>());
+            assert_ok!(test_benchmark_add_liquidity::<Test>());
+            assert_ok!(test_benchmark_remove_liquidity::<Test>());
+            assert_ok!(test_benchmark_swap::<Test>());
+            assert_ok!(test_benchmark_swap_exact_in::<Test>());
+            assert_ok!(test_benchmark_claim_maker_reward::<Test>());
+            assert_ok!(test_benchmark_set_maker_mining_rule::<Test>());
+            assert_ok!(test_benchmark_schedule_delisting::<Test>());
+            assert_ok!(test_benchmark_relist_trading_pair::<Test>());
             assert_ok!(test_benchmark_add_trading_pair::<Test>());
             assert_ok!(test_benchmark_update_trading_pair::<Test>());
         });
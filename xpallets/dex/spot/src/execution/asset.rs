@@ -3,10 +3,15 @@
 //! This module handles all the asset related operations in Spot.
 
 use super::*;
+use frame_support::traits::ExistenceRequirement;
+use xp_logging::warn;
 use xpallet_assets::AssetType::{self, ReservedDexSpot, Usable};
 
 impl<T: Trait> Module<T> {
     /// Delivery the assets to maker and taker respectively when executing the order.
+    ///
+    /// Returns the amounts paid by maker and taker, as well as the fees charged
+    /// from the assets they received.
     pub(super) fn delivery_asset_to_each_other(
         maker_order_side: Side,
         pair: &TradingPairProfile,
@@ -14,7 +19,7 @@ impl<T: Trait> Module<T> {
         price: T::Price,
         maker_order: &mut OrderInfo<T>,
         taker_order: &mut OrderInfo<T>,
-    ) -> Result<(BalanceOf<T>, BalanceOf<T>), DispatchError> {
+    ) -> Result<(BalanceOf<T>, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>), DispatchError> {
         let maker = &maker_order.submitter();
         let taker = &taker_order.submitter();
 
@@ -24,6 +29,8 @@ impl<T: Trait> Module<T> {
         let turnover_in_quote =
             Self::convert_base_to_quote(turnover, price, pair).unwrap_or_else(|_| Zero::zero());
 
+        let fee = Self::trading_fee_of(pair.id);
+
         match maker_order_side {
             Side::Sell => {
                 // maker(seller): unreserve the base currency and move to the taker.
@@ -31,10 +38,27 @@ impl<T: Trait> Module<T> {
                 let maker_turnover_amount = turnover;
                 let taker_turnover_amount = turnover_in_quote;
 
-                Self::apply_delivery(base, maker_turnover_amount, maker, taker)?;
-                Self::apply_delivery(quote, taker_turnover_amount, taker, maker)?;
+                let taker_fee = Self::apply_delivery_with_fee(
+                    base,
+                    maker_turnover_amount,
+                    fee.taker * maker_turnover_amount,
+                    maker,
+                    taker,
+                )?;
+                let maker_fee = Self::apply_delivery_with_fee(
+                    quote,
+                    taker_turnover_amount,
+                    fee.maker * taker_turnover_amount,
+                    taker,
+                    maker,
+                )?;
 
-                Ok((maker_turnover_amount, taker_turnover_amount))
+                Ok((
+                    maker_turnover_amount,
+                    taker_turnover_amount,
+                    maker_fee,
+                    taker_fee,
+                ))
             }
             Side::Buy => {
                 // maker(buyer): unreserve the quote currency and move to the taker.
@@ -42,14 +66,69 @@ impl<T: Trait> Module<T> {
                 let maker_turnover_amount = turnover_in_quote;
                 let taker_turnover_amount = turnover;
 
-                Self::apply_delivery(base, taker_turnover_amount, taker, maker)?;
-                Self::apply_delivery(quote, maker_turnover_amount, maker, taker)?;
+                let maker_fee = Self::apply_delivery_with_fee(
+                    base,
+                    taker_turnover_amount,
+                    fee.maker * taker_turnover_amount,
+                    taker,
+                    maker,
+                )?;
+                let taker_fee = Self::apply_delivery_with_fee(
+                    quote,
+                    maker_turnover_amount,
+                    fee.taker * maker_turnover_amount,
+                    maker,
+                    taker,
+                )?;
 
-                Ok((maker_turnover_amount, taker_turnover_amount))
+                Ok((
+                    maker_turnover_amount,
+                    taker_turnover_amount,
+                    maker_fee,
+                    taker_fee,
+                ))
             }
         }
     }
 
+    /// Returns the account receiving the trading fees.
+    fn fee_receiver() -> T::AccountId {
+        Self::fee_destination()
+            .unwrap_or_else(<T as xpallet_assets::Trait>::TreasuryAccount::treasury_account)
+    }
+
+    /// Move `value` of the locked balance in Spot of account `from`, of which
+    /// `fee` goes to the fee receiver and the rest goes to account `to`.
+    ///
+    /// Returns the fee actually charged. The fee is waived if it can't be delivered,
+    /// e.g., the native token can't be moved to a fee receiver that doesn't exist,
+    /// which must not fail the execution of the orders.
+    fn apply_delivery_with_fee(
+        asset_id: AssetId,
+        value: BalanceOf<T>,
+        fee: BalanceOf<T>,
+        from: &T::AccountId,
+        to: &T::AccountId,
+    ) -> Result<BalanceOf<T>, DispatchError> {
+        let fee = if fee.is_zero() {
+            fee
+        } else {
+            let fee_receiver = Self::fee_receiver();
+            match Self::apply_delivery(asset_id, fee, from, &fee_receiver) {
+                Ok(()) => fee,
+                Err(err) => {
+                    warn!(
+                        "[apply_delivery_with_fee] Waive the fee since it can't be delivered, fee_receiver:{:?}, asset_id:{}, fee:{:?}, err:{:?}",
+                        fee_receiver, asset_id, fee, err
+                    );
+                    Zero::zero()
+                }
+            }
+        };
+        Self::apply_delivery(asset_id, value - fee, from, to)?;
+        Ok(fee)
+    }
+
     /// Returns true if the `asset_id` is native token.
     #[inline]
     fn is_native_asset(asset_id: AssetId) -> bool {
@@ -105,13 +184,57 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
+    /// Returns the balance of `who` which is free to spend.
+    pub(super) fn usable_balance_of(who: &T::AccountId, asset_id: AssetId) -> BalanceOf<T> {
+        if Self::is_native_asset(asset_id) {
+            <T as xpallet_assets::Trait>::Currency::free_balance(who)
+        } else {
+            <xpallet_assets::Module<T>>::usable_balance(who, &asset_id)
+        }
+    }
+
+    /// Move the usable asset of `who` to the liquidity pool account and lock it in Spot.
+    pub(super) fn deposit_into_pool(
+        who: &T::AccountId,
+        pool_account: &T::AccountId,
+        asset_id: AssetId,
+        value: BalanceOf<T>,
+    ) -> DispatchResult {
+        if Self::is_native_asset(asset_id) {
+            <T as xpallet_assets::Trait>::Currency::transfer(
+                who,
+                pool_account,
+                value,
+                ExistenceRequirement::KeepAlive,
+            )?;
+            Self::put_order_reserve(pool_account, asset_id, value)
+        } else {
+            ensure!(
+                <xpallet_assets::Module<T>>::usable_balance(who, &asset_id) >= value,
+                Error::<T>::InsufficientBalance
+            );
+            Self::move_foreign_asset(asset_id, who, Usable, pool_account, ReservedDexSpot, value)
+        }
+    }
+
+    /// Move the locked asset of the liquidity pool account to the usable of `who`.
+    #[inline]
+    pub(super) fn withdraw_from_pool(
+        pool_account: &T::AccountId,
+        who: &T::AccountId,
+        asset_id: AssetId,
+        value: BalanceOf<T>,
+    ) -> DispatchResult {
+        Self::apply_delivery(asset_id, value, pool_account, who)
+    }
+
     /// Transfer some locked native token balance of `from` to another account.
     fn transfer_native_asset(
         from: &T::AccountId,
         to: &T::AccountId,
         value: BalanceOf<T>,
     ) -> DispatchResult {
-        // The account `to` definitely exists so this should always succeed.
+        // This fails with `DeadAccount` if the account `to` doesn't exist.
         // This is equivalent to unreserve(from, value) + transfer(from, to, value)
         <T as xpallet_assets::Trait>::Currency::repatriate_reserved(
             from,
@@ -124,12 +247,25 @@ impl<T: Trait> Module<T> {
     }
 
     /// Move one's foreign asset from the state of `ReservedDexSpot` to `Usable`.
+    ///
+    /// The asset could have been deregistered while the orders are still open, which
+    /// is refused by `move_balance`, so the reserved is released by setting the
+    /// balances directly in that case.
     fn unreserve_foreign_asset(
         who: &T::AccountId,
         asset_id: AssetId,
         value: BalanceOf<T>,
     ) -> DispatchResult {
-        Self::move_foreign_asset(asset_id, who, ReservedDexSpot, who, Usable, value)
+        if xpallet_assets_registrar::Module::<T>::is_valid(&asset_id) {
+            return Self::move_foreign_asset(asset_id, who, ReservedDexSpot, who, Usable, value);
+        }
+
+        let mut balances = <xpallet_assets::Module<T>>::asset_balance(who, asset_id);
+        let reserved = balances.entry(ReservedDexSpot).or_default();
+        ensure!(*reserved >= value, Error::<T>::InsufficientBalance);
+        *reserved -= value;
+        *balances.entry(Usable).or_default() += value;
+        <xpallet_assets::Module<T>>::set_balance_impl(who, &asset_id, balances)
     }
 
     /// Wrap the move_balance function in xassets module.
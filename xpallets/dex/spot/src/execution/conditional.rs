@@ -0,0 +1,346 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of the conditional orders and the trigger book.
+
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Reserves the asset of the limit order and inserts the conditional order into the trigger book.
+    pub(crate) fn apply_put_conditional_order(
+        who: T::AccountId,
+        pair: &TradingPairProfile,
+        kind: ConditionalOrderKind,
+        side: Side,
+        amount: BalanceOf<T>,
+        price: T::Price,
+        trigger_price: T::Price,
+    ) -> DispatchResult {
+        let direction = TriggerDirection::of(kind, side);
+        if let Some(info) = Self::trading_pair_info_of(pair.id) {
+            ensure!(
+                !direction.is_triggered(info.latest_price, trigger_price),
+                Error::<T>::InvalidTriggerPrice
+            );
+        }
+        // The price might have crossed the trigger price since the last collection,
+        // in which case the order would be collected as triggered.
+        if let Some(triggering_price) = Self::triggering_price_of(pair.id, direction) {
+            ensure!(
+                !direction.is_triggered(triggering_price, trigger_price),
+                Error::<T>::InvalidTriggerPrice
+            );
+        }
+        ensure!(
+            Self::triggers_of((pair.id, direction), trigger_price).len()
+                < MAX_CONDITIONAL_ORDERS_PER_PRICE,
+            Error::<T>::TooManyConditionalOrders
+        );
+
+        let (reserve_asset, reserve_amount) = match side {
+            Side::Buy => (
+                pair.quote(),
+                Self::convert_base_to_quote(amount, price, pair)?,
+            ),
+            Side::Sell => (pair.base(), amount),
+        };
+        Self::put_order_reserve(&who, reserve_asset, reserve_amount)?;
+
+        let order_id = Self::conditional_order_count_of(&who);
+        let order = ConditionalOrder {
+            id: order_id,
+            pair_id: pair.id,
+            submitter: who.clone(),
+            kind,
+            side,
+            amount,
+            price,
+            trigger_price,
+            reserved: reserve_amount,
+            created_at: <frame_system::Module<T>>::block_number(),
+        };
+
+        debug!(
+            "[apply_put_conditional_order] New conditional order:{:?}",
+            order
+        );
+        <ConditionalOrderInfoOf<T>>::insert(&who, order_id, &order);
+        <ConditionalOrderCountOf<T>>::insert(&who, order_id + 1);
+
+        Self::insert_trigger(pair.id, direction, trigger_price, (who, order_id));
+
+        Self::deposit_event(Event::<T>::NewConditionalOrder(order));
+
+        Ok(())
+    }
+
+    /// Removes the conditional order and refunds the reserved asset.
+    pub(crate) fn apply_cancel_conditional_order(
+        who: &T::AccountId,
+        order_id: ConditionalOrderId,
+    ) -> DispatchResult {
+        let order = Self::conditional_order_info_of(who, order_id)
+            .ok_or(Error::<T>::InvalidConditionalOrderId)?;
+
+        // The triggered order is no longer in the trigger book, it will be skipped
+        // on activation since the order details have been removed.
+        Self::remove_trigger(
+            order.pair_id,
+            order.direction(),
+            order.trigger_price,
+            (who.clone(), order_id),
+        );
+        Self::refund_conditional_order(&order)?;
+        <ConditionalOrderInfoOf<T>>::remove(who, order_id);
+
+        Self::deposit_event(Event::<T>::ConditionalOrderCanceled(order));
+
+        Ok(())
+    }
+
+    fn refund_conditional_order(order: &ConditionalOrderInfo<T>) -> DispatchResult {
+        let pair = Self::trading_pair(order.pair_id)?;
+        let refund_asset = match order.side {
+            Side::Buy => pair.quote(),
+            Side::Sell => pair.base(),
+        };
+        Self::generic_unreserve(&order.submitter, refund_asset, order.reserved)
+    }
+
+    fn insert_trigger(
+        pair_id: TradingPairId,
+        direction: TriggerDirection,
+        trigger_price: T::Price,
+        key: (T::AccountId, ConditionalOrderId),
+    ) {
+        <TriggersOf<T>>::mutate((pair_id, direction), trigger_price, |triggers| {
+            triggers.push(key)
+        });
+        <TriggerPricesOf<T>>::mutate(pair_id, direction, |prices| {
+            if let Err(pos) = prices.binary_search(&trigger_price) {
+                prices.insert(pos, trigger_price);
+            }
+        });
+    }
+
+    pub(super) fn remove_trigger(
+        pair_id: TradingPairId,
+        direction: TriggerDirection,
+        trigger_price: T::Price,
+        key: (T::AccountId, ConditionalOrderId),
+    ) {
+        let mut triggers = <TriggersOf<T>>::get((pair_id, direction), trigger_price);
+        if let Some(idx) = triggers.iter().position(|x| *x == key) {
+            triggers.remove(idx);
+            if triggers.is_empty() {
+                <TriggersOf<T>>::remove((pair_id, direction), trigger_price);
+                <TriggerPricesOf<T>>::mutate(pair_id, direction, |prices| {
+                    if let Ok(pos) = prices.binary_search(&trigger_price) {
+                        prices.remove(pos);
+                    }
+                });
+            } else {
+                <TriggersOf<T>>::insert((pair_id, direction), trigger_price, triggers);
+            }
+        }
+    }
+
+    /// Returns the most extreme execution price of `direction` since the last collection,
+    /// i.e., the highest for `Rise` and the lowest for `Fall`.
+    fn triggering_price_of(
+        pair_id: TradingPairId,
+        direction: TriggerDirection,
+    ) -> Option<T::Price> {
+        Self::triggering_range_of(pair_id).map(|(lowest, highest)| match direction {
+            TriggerDirection::Rise => highest,
+            TriggerDirection::Fall => lowest,
+        })
+    }
+
+    /// Extends the triggering range of the trading pair with the latest execution price.
+    ///
+    /// This happens after an order is executed every time, the triggered orders are
+    /// collected at the beginning of the next block, so that the cost of matching
+    /// does not depend on how many trigger prices are crossed.
+    pub(crate) fn update_triggering_range(pair_id: TradingPairId, latest: T::Price) {
+        <TriggeringRangeOf<T>>::mutate(pair_id, |range| {
+            *range = Some(match *range {
+                Some((lowest, highest)) => (cmp::min(lowest, latest), cmp::max(highest, latest)),
+                None => (latest, latest),
+            });
+        });
+    }
+
+    /// Returns all the triggered conditional orders waiting for being activated in order.
+    pub fn triggered_orders() -> Vec<(T::AccountId, ConditionalOrderId)> {
+        (Self::triggered_queue_head()..Self::triggered_queue_tail())
+            .flat_map(Self::triggered_queue)
+            .collect()
+    }
+
+    /// Moves the conditional orders triggered within the triggering range of each trading
+    /// pair from the trigger book to `TriggeredQueue`.
+    ///
+    /// At most `MAX_COLLECTIONS_PER_BLOCK` trigger prices are collected in one block, the
+    /// triggering range is kept until all the triggered ones have been collected.
+    pub(crate) fn collect_triggered_orders() -> Weight {
+        let ranges = <TriggeringRangeOf<T>>::iter().collect::<Vec<_>>();
+        let mut tail = Self::triggered_queue_tail();
+        let mut collected = 0u32;
+
+        for &(pair_id, _) in ranges.iter() {
+            let mut exhausted = true;
+            for &direction in [TriggerDirection::Rise, TriggerDirection::Fall].iter() {
+                let latest = match Self::triggering_price_of(pair_id, direction) {
+                    Some(latest) => latest,
+                    None => continue,
+                };
+                // The trigger prices are in ascending order, the ones crossed by the price
+                // rising or falling are at the beginning or the end respectively.
+                let mut prices = Self::trigger_prices_of(pair_id, direction);
+                let count = match direction {
+                    TriggerDirection::Rise => prices
+                        .iter()
+                        .take_while(|&&price| direction.is_triggered(latest, price))
+                        .count(),
+                    TriggerDirection::Fall => prices
+                        .iter()
+                        .rev()
+                        .take_while(|&&price| direction.is_triggered(latest, price))
+                        .count(),
+                };
+                let budget = (MAX_COLLECTIONS_PER_BLOCK - collected) as usize;
+                if count > budget {
+                    exhausted = false;
+                }
+                let count = cmp::min(count, budget);
+                if count == 0 {
+                    continue;
+                }
+
+                let len = prices.len();
+                let triggered = match direction {
+                    TriggerDirection::Rise => prices.drain(..count).collect::<Vec<_>>(),
+                    TriggerDirection::Fall => prices.drain(len - count..).rev().collect(),
+                };
+                for trigger_price in triggered {
+                    let orders = <TriggersOf<T>>::take((pair_id, direction), trigger_price);
+                    <TriggeredQueue<T>>::insert(tail, orders);
+                    tail += 1;
+                }
+                <TriggerPricesOf<T>>::insert(pair_id, direction, prices);
+                collected += count as u32;
+            }
+
+            if exhausted {
+                <TriggeringRangeOf<T>>::remove(pair_id);
+            }
+        }
+
+        if collected > 0 {
+            TriggeredQueueTail::put(tail);
+        }
+
+        let pairs = ranges.len() as Weight;
+        T::DbWeight::get()
+            .reads_writes(2 + pairs.saturating_mul(2), 1 + pairs.saturating_mul(3))
+            .saturating_add(
+                T::DbWeight::get()
+                    .reads_writes(1, 2)
+                    .saturating_mul(collected as Weight),
+            )
+    }
+
+    /// Puts the triggered conditional orders as limit orders.
+    ///
+    /// At most `MAX_ACTIVATIONS_PER_BLOCK` orders are activated in one block, the rest
+    /// are left to the next block. The activated orders might trigger more conditional
+    /// orders when they get matched, which are collected in the next block.
+    pub(crate) fn activate_triggered_orders() -> Weight {
+        let mut head = Self::triggered_queue_head();
+        let tail = Self::triggered_queue_tail();
+        let mut activated: Weight = 0;
+        let mut weight: Weight = 0;
+
+        while head < tail && activated < MAX_ACTIVATIONS_PER_BLOCK as Weight {
+            let mut orders = <TriggeredQueue<T>>::take(head);
+            let rest = orders.split_off(cmp::min(
+                orders.len(),
+                (MAX_ACTIVATIONS_PER_BLOCK as Weight - activated) as usize,
+            ));
+
+            for (who, order_id) in orders {
+                activated += 1;
+
+                // The conditional order might have been canceled already.
+                if let Some(order) = <ConditionalOrderInfoOf<T>>::take(&who, order_id) {
+                    let makers = match Self::activate_conditional_order(&order) {
+                        Ok(makers) => makers,
+                        Err(err) => {
+                            error!(
+                                "[activate_triggered_orders] Fail to activate the conditional order, who:{:?}, order_id:{}, err:{:?}",
+                                who, order_id, err
+                            );
+                            MAX_MAKERS_PER_ORDER
+                        }
+                    };
+                    weight = weight.saturating_add(<T as Trait>::WeightInfo::put_order(makers));
+                }
+            }
+
+            if rest.is_empty() {
+                head += 1;
+            } else {
+                <TriggeredQueue<T>>::insert(head, rest);
+            }
+        }
+        TriggeredQueueHead::put(head);
+
+        T::DbWeight::get()
+            .reads_writes(2, 1)
+            .saturating_add(weight)
+            .saturating_add(
+                T::DbWeight::get()
+                    .reads_writes(2, 2)
+                    .saturating_mul(activated),
+            )
+    }
+
+    /// Puts the conditional order as a limit order with the reserved asset.
+    ///
+    /// The reserved asset is refunded if the trading pair is untradable or
+    /// going to be delisted, or the limit order fails to be put.
+    ///
+    /// Returns the number of the maker orders touched by the limit order.
+    fn activate_conditional_order(order: &ConditionalOrderInfo<T>) -> Result<u32, DispatchError> {
+        Self::deposit_event(Event::<T>::ConditionalOrderTriggered(order.clone()));
+
+        let pair = Self::trading_pair(order.pair_id)?;
+        if !pair.tradable || Self::is_delisting(order.pair_id) {
+            Self::refund_conditional_order(order)?;
+            return Ok(0);
+        }
+
+        let mut makers = 0;
+        let put_result = with_transaction_result(|| {
+            Self::apply_put_order(
+                order.submitter.clone(),
+                order.pair_id,
+                OrderType::Limit,
+                order.side,
+                order.amount,
+                order.price,
+                order.reserved,
+                TimeInForce::GoodTillCancel,
+                None,
+                Self::self_trade_prevention_of(order.pair_id),
+                &mut makers,
+            )
+        });
+
+        if put_result.is_err() {
+            Self::refund_conditional_order(order)?;
+        }
+
+        put_result.map(|_| makers)
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of delisting and re-listing the trading pairs.
+//!
+//! Once the delisting of a trading pair is scheduled, no new orders are accepted,
+//! but the open orders can still be canceled as usual. At the delisting block,
+//! all the open orders and conditional orders are canceled and refunded, then
+//! the order book of the trading pair is cleared.
+
+use super::*;
+
+impl<T: Trait> Module<T> {
+    /// Returns true if the delisting of the trading pair has been scheduled.
+    pub(crate) fn is_delisting(pair_id: TradingPairId) -> bool {
+        <DelistingAt<T>>::contains_key(pair_id)
+    }
+
+    /// Schedules the delisting of the trading pair at block `at`, the previous
+    /// schedule of the trading pair is replaced if any.
+    pub(crate) fn apply_schedule_delisting(
+        pair_id: TradingPairId,
+        at: T::BlockNumber,
+    ) -> DispatchResult {
+        Self::trading_pair(pair_id)?;
+        ensure!(
+            Self::delisted_at(pair_id).is_none(),
+            Error::<T>::TradingPairDelisted
+        );
+        ensure!(
+            at > <frame_system::Module<T>>::block_number(),
+            Error::<T>::InvalidDelistingBlock
+        );
+
+        if let Some(prev) = <DelistingAt<T>>::get(pair_id) {
+            <DelistingPairsAt<T>>::mutate(prev, |pairs| pairs.retain(|&id| id != pair_id));
+        }
+        <DelistingPairsAt<T>>::append(at, pair_id);
+        <DelistingAt<T>>::insert(pair_id, at);
+
+        Self::deposit_event(Event::<T>::TradingPairDelistingScheduled(pair_id, at));
+
+        Ok(())
+    }
+
+    /// Delists the trading pairs scheduled at block `now`.
+    ///
+    /// At most `MAX_DELISTING_CANCELS_PER_BLOCK` orders are canceled in one block,
+    /// the trading pairs still having open orders are postponed to the next block.
+    pub(crate) fn delist_trading_pairs(now: T::BlockNumber) -> Weight {
+        let pairs = <DelistingPairsAt<T>>::take(now);
+        if pairs.is_empty() {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let mut canceled = 0;
+        let mut postponed = Vec::new();
+        for pair_id in pairs {
+            canceled += Self::cancel_orders_on_delisting(
+                pair_id,
+                MAX_DELISTING_CANCELS_PER_BLOCK - canceled,
+            );
+            if Self::has_open_orders(pair_id) {
+                postponed.push(pair_id);
+            } else {
+                Self::clear_delisted_trading_pair(pair_id, now);
+            }
+        }
+
+        let postponed_count = postponed.len() as Weight;
+        if !postponed.is_empty() {
+            let next = now + One::one();
+            for &pair_id in postponed.iter() {
+                <DelistingAt<T>>::insert(pair_id, next);
+            }
+            <DelistingPairsAt<T>>::mutate(next, |pairs| pairs.extend(postponed));
+        }
+
+        T::DbWeight::get()
+            .reads_writes(2, 2)
+            .saturating_add(T::DbWeight::get().writes(postponed_count))
+            .saturating_add(
+                <T as Trait>::WeightInfo::cancel_order().saturating_mul(canceled as Weight),
+            )
+    }
+
+    /// Returns true if there are any orders or conditional orders on the trading pair.
+    fn has_open_orders(pair_id: TradingPairId) -> bool {
+        [Side::Buy, Side::Sell]
+            .iter()
+            .any(|side| !Self::price_levels_of(pair_id, *side).is_empty())
+            || [TriggerDirection::Rise, TriggerDirection::Fall]
+                .iter()
+                .any(|direction| !Self::trigger_prices_of(pair_id, *direction).is_empty())
+    }
+
+    /// Cancels at most `limit` orders and conditional orders on the trading pair,
+    /// returns how many of them have been processed.
+    ///
+    /// The ones failed to be refunded are still removed from the order book and
+    /// the trigger book, so that they won't be processed again.
+    fn cancel_orders_on_delisting(pair_id: TradingPairId, limit: usize) -> usize {
+        let orders = [Side::Buy, Side::Sell]
+            .iter()
+            .flat_map(|&side| {
+                Self::price_levels_of(pair_id, side)
+                    .into_iter()
+                    .map(move |price| (side, price))
+            })
+            .flat_map(|(side, price)| {
+                Self::iter_quotations(pair_id, price).map(move |order_key| (side, price, order_key))
+            })
+            .take(limit)
+            .collect::<Vec<_>>();
+
+        for (side, price, (who, order_id)) in orders.iter() {
+            if let Err(err) = Self::apply_cancel_order(who, pair_id, *order_id) {
+                error!(
+                    "[cancel_orders_on_delisting] Fail to cancel the order, who:{:?}, order_id:{}, err:{:?}",
+                    who, order_id, err
+                );
+                Self::remove_quotation(pair_id, *price, *side, (who.clone(), *order_id));
+            }
+        }
+
+        let conditional_orders = [TriggerDirection::Rise, TriggerDirection::Fall]
+            .iter()
+            .flat_map(|&direction| {
+                Self::trigger_prices_of(pair_id, direction)
+                    .into_iter()
+                    .map(move |trigger_price| (direction, trigger_price))
+            })
+            .flat_map(|(direction, trigger_price)| {
+                Self::triggers_of((pair_id, direction), trigger_price)
+                    .into_iter()
+                    .map(move |order_key| (direction, trigger_price, order_key))
+            })
+            .take(limit - orders.len())
+            .collect::<Vec<_>>();
+
+        for (direction, trigger_price, (who, order_id)) in conditional_orders.iter() {
+            if let Err(err) = Self::apply_cancel_conditional_order(who, *order_id) {
+                error!(
+                    "[cancel_orders_on_delisting] Fail to cancel the conditional order, who:{:?}, order_id:{}, err:{:?}",
+                    who, order_id, err
+                );
+                Self::remove_trigger(
+                    pair_id,
+                    *direction,
+                    *trigger_price,
+                    (who.clone(), *order_id),
+                );
+            }
+        }
+
+        orders.len() + conditional_orders.len()
+    }
+
+    /// Clears the order book of the trading pair which has no open orders now.
+    ///
+    /// The profile, the latest price and the trading history are kept as the archive.
+    fn clear_delisted_trading_pair(pair_id: TradingPairId, now: T::BlockNumber) {
+        for side in [Side::Buy, Side::Sell].iter() {
+            <PriceLevelsOf<T>>::remove(pair_id, side);
+        }
+        for direction in [TriggerDirection::Rise, TriggerDirection::Fall].iter() {
+            <TriggerPricesOf<T>>::remove(pair_id, direction);
+        }
+        <HandicapOf<T>>::remove(pair_id);
+
+        TradingPairOf::mutate(pair_id, |pair| {
+            if let Some(pair) = pair {
+                pair.tradable = false;
+            }
+        });
+        <DelistingAt<T>>::remove(pair_id);
+        <DelistedAt<T>>::insert(pair_id, now);
+
+        info!("[delist_trading_pairs] pair_id: {:?} is delisted", pair_id);
+
+        Self::deposit_event(Event::<T>::TradingPairDelisted(pair_id));
+    }
+
+    /// Lists the delisted trading pair again with a fresh `TradingPairInfo`.
+    pub(crate) fn apply_relist_trading_pair(
+        pair_id: TradingPairId,
+        tick_decimals: u32,
+        latest_price: T::Price,
+    ) -> DispatchResult {
+        let mut pair = Self::trading_pair(pair_id)?;
+        ensure!(
+            Self::delisted_at(pair_id).is_some(),
+            Error::<T>::TradingPairNotDelisted
+        );
+        xpallet_assets_registrar::Module::<T>::ensure_asset_is_valid(&pair.base())?;
+        xpallet_assets_registrar::Module::<T>::ensure_asset_is_valid(&pair.quote())?;
+
+        pair.tick_decimals = tick_decimals;
+        pair.tradable = true;
+
+        TradingPairOf::insert(pair_id, &pair);
+        <TradingPairInfoOf<T>>::insert(
+            pair_id,
+            TradingPairInfo {
+                latest_price,
+                last_updated: <frame_system::Module<T>>::block_number(),
+            },
+        );
+        <DelistedAt<T>>::remove(pair_id);
+
+        Self::deposit_event(Event::<T>::TradingPairRelisted(pair));
+
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
 mod asset;
+mod conditional;
+mod delisting;
 mod order;
+mod pool;
+mod route;
 mod state;
 
 use xp_logging::debug;
@@ -12,7 +16,7 @@ use crate::types::*;
 impl<T: Trait> Module<T> {
     fn check_bid_price(
         quote: T::Price,
-        lowest_ask: T::Price,
+        lowest_ask: Option<T::Price>,
         fluctuation: T::Price,
     ) -> Result<(), Error<T>> {
         debug!(
@@ -21,9 +25,10 @@ impl<T: Trait> Module<T> {
         );
 
         // There is no offer yet, this is the first one.
-        if lowest_ask.is_zero() {
-            return Ok(());
-        }
+        let lowest_ask = match lowest_ask {
+            Some(lowest_ask) => lowest_ask,
+            None => return Ok(()),
+        };
 
         if quote > lowest_ask && quote - lowest_ask > fluctuation {
             return Err(Error::<T>::TooHighBidPrice);
@@ -34,7 +39,7 @@ impl<T: Trait> Module<T> {
 
     fn check_ask_price(
         quote: T::Price,
-        highest_bid: T::Price,
+        highest_bid: Option<T::Price>,
         fluctuation: T::Price,
     ) -> Result<(), Error<T>> {
         debug!(
@@ -43,9 +48,10 @@ impl<T: Trait> Module<T> {
         );
 
         // There is no bid yet, this is the first one.
-        if highest_bid.is_zero() {
-            return Ok(());
-        }
+        let highest_bid = match highest_bid {
+            Some(highest_bid) => highest_bid,
+            None => return Ok(()),
+        };
 
         if quote < highest_bid && highest_bid - quote > fluctuation {
             return Err(Error::<T>::TooLowAskPrice);
@@ -75,22 +81,40 @@ impl<T: Trait> Module<T> {
         }
     }
 
-    /// Returns true if there are already too many orders at the `price` and `side` for a trading pair.
-    pub(crate) fn has_too_many_backlog_orders(
-        pair_id: TradingPairId,
-        price: T::Price,
+    /// Ensures the post-only order does not cross the handicap, i.e.,
+    /// it would not be matched immediately.
+    pub(crate) fn ensure_post_only_not_crossed(
+        quote: T::Price,
         side: Side,
+        pair_id: TradingPairId,
     ) -> Result<(), Error<T>> {
-        let quotations = <QuotationsOf<T>>::get(pair_id, price);
-        if quotations.len() >= MAX_BACKLOG_ORDER {
-            let (who, order_id) = &quotations[0];
-            if let Some(order) = <OrderInfoOf<T>>::get(who, order_id) {
-                if order.side() == side {
-                    return Err(Error::<T>::TooManyBacklogOrders);
-                }
-            }
-        }
+        let handicap = <HandicapOf<T>>::get(pair_id);
+        let crossed = match side {
+            Side::Buy => handicap
+                .lowest_ask
+                .map_or(false, |lowest_ask| quote >= lowest_ask),
+            Side::Sell => handicap
+                .highest_bid
+                .map_or(false, |highest_bid| quote <= highest_bid),
+        };
+        ensure!(!crossed, Error::<T>::PostOnlyOrderWouldMatch);
+        Ok(())
+    }
 
+    /// Checks the amount of an order against the order size limit of the trading pair.
+    pub(crate) fn ensure_valid_amount(
+        pair_id: TradingPairId,
+        amount: BalanceOf<T>,
+    ) -> Result<(), Error<T>> {
+        let OrderSizeLimit {
+            min_amount,
+            lot_size,
+        } = Self::order_size_limit_of(pair_id);
+        ensure!(amount >= min_amount, Error::<T>::AmountTooSmall);
+        ensure!(
+            lot_size.is_zero() || (amount % lot_size).is_zero(),
+            Error::<T>::InvalidLotSize
+        );
         Ok(())
     }
 
@@ -150,4 +174,48 @@ impl<T: Trait> Module<T> {
             Err(Error::<T>::InvalidTradingPairAsset)
         }
     }
+
+    /// Converts the quote currency to the base currency given the trading pair.
+    ///
+    /// This is the inverse of `convert_base_to_quote()`, the result is rounded down.
+    ///
+    /// amount
+    /// = volume * 10^(base.decimals + price.decimals - quote.decimals) / price
+    pub(crate) fn convert_quote_to_base(
+        volume: BalanceOf<T>,
+        price: T::Price,
+        pair: &TradingPairProfile,
+    ) -> Result<BalanceOf<T>, Error<T>> {
+        if let (Some(base_p), Some(quote_p)) = (
+            Self::currency_decimals_of(pair.base()),
+            Self::currency_decimals_of(pair.quote()),
+        ) {
+            let (base_p, quote_p, pair_p) =
+                (u32::from(base_p), u32::from(quote_p), pair.pip_decimals);
+
+            let volume = volume.saturated_into::<u128>();
+            let price = price.saturated_into::<u128>();
+
+            if price.is_zero() {
+                return Err(Error::<T>::InvalidPrice);
+            }
+
+            let amount = if (base_p + pair_p) >= quote_p {
+                volume
+                    .checked_mul(10_u128.pow(base_p + pair_p - quote_p))
+                    .ok_or(Error::<T>::AmountOverflow)?
+                    / price
+            } else {
+                volume / (price.saturating_mul(10_u128.pow(quote_p - base_p - pair_p)))
+            };
+
+            if !amount.is_zero() {
+                Ok(amount.saturated_into::<BalanceOf<T>>())
+            } else {
+                Err(Error::<T>::VolumeTooSmall)
+            }
+        } else {
+            Err(Error::<T>::InvalidTradingPairAsset)
+        }
+    }
 }
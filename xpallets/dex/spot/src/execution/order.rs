@@ -3,53 +3,141 @@
 //! This module takes care of the order processing.
 
 use super::*;
-use sp_runtime::traits::CheckedAdd;
+use sp_runtime::traits::{CheckedAdd, Saturating};
 use sp_std::cmp::Ordering;
 
 impl<T: Trait> Module<T> {
     /// When the price is far from the current handicap, i.e.,
     /// - buy: less than the lowest_ask
     /// - sell: larger than the highest_bid
-    /// what we only need to do is to check if the handicap should be updated.
+    /// what we only need to do is to insert the quotation.
     /// Or else we should match the order.
+    ///
+    /// The orders with an immediate time in force never rest in the order book,
+    /// a fill-or-kill order is canceled as a whole if it can't be filled completely.
+    ///
+    /// `makers` counts the maker orders touched in the current call, see
+    /// `apply_match_order_given_counterparty`.
     pub(crate) fn try_match_order(
         pair: &TradingPairProfile,
         order: &mut OrderInfo<T>,
         pair_index: TradingPairId,
         side: Side,
         price: T::Price,
-    ) {
+        makers: &mut u32,
+    ) -> DispatchResult {
+        if order.time_in_force() == TimeInForce::FillOrKill
+            && !Self::can_be_fully_filled(order, makers)
+        {
+            return Self::cancel_unfilled_remainder(pair, order);
+        }
+
         let handicap = <HandicapOf<T>>::get(pair_index);
-        let (lowest_ask, highest_bid) = (handicap.lowest_ask, handicap.highest_bid);
 
-        // If the price is too low or too high, we only need to check if the handicap should be updated,
-        // otherwise we should match this order.
+        // If there is no counterparty or the price is too low or too high, we only need to
+        // insert the quotation, otherwise we should match this order.
         let skip_match_order = match side {
-            Side::Buy => lowest_ask.is_zero() || price < lowest_ask,
-            Side::Sell => highest_bid.is_zero() || price > highest_bid,
+            Side::Buy => handicap
+                .lowest_ask
+                .map_or(true, |lowest_ask| price < lowest_ask),
+            Side::Sell => handicap
+                .highest_bid
+                .map_or(true, |highest_bid| price > highest_bid),
         };
 
-        // If there is no chance to match order, we only have to insert this quote and update handicap.
         if skip_match_order {
-            <QuotationsOf<T>>::mutate(order.pair_id(), order.price(), |quotations| {
-                quotations.push((order.submitter(), order.id()))
-            });
+            if order.time_in_force().is_immediate() {
+                return Self::cancel_unfilled_remainder(pair, order);
+            }
+
+            Self::insert_quotation(
+                order.pair_id(),
+                order.price(),
+                side,
+                (order.submitter(), order.id()),
+            );
+
+            Ok(())
+        } else {
+            Self::match_order(&pair, order, makers)
+        }
+    }
 
-            match side {
-                Side::Buy if price > highest_bid => {
-                    <HandicapOf<T>>::mutate(pair_index, |handicap| handicap.highest_bid = price);
+    /// Returns true if the counterparty orders at the crossed price levels
+    /// are enough to fill the order completely.
+    ///
+    /// Only as many maker orders as the order can still be matched against are
+    /// scanned, which are counted in `makers` if the order can't be filled.
+    fn can_be_fully_filled(order: &OrderInfo<T>, makers: &mut u32) -> bool {
+        let price = order.price();
+        let levels = match order.side() {
+            Side::Buy => Self::price_levels_of(order.pair_id(), Side::Sell),
+            Side::Sell => {
+                let mut levels = Self::price_levels_of(order.pair_id(), Side::Buy);
+                levels.reverse();
+                levels
+            }
+        };
+
+        let wanted = order.remaining_in_base();
+        let mut available: BalanceOf<T> = Zero::zero();
+        let mut scanned = *makers;
+
+        for counterparty_price in levels {
+            let crossed = match order.side() {
+                Side::Buy => counterparty_price <= price,
+                Side::Sell => counterparty_price >= price,
+            };
+            if !crossed {
+                break;
+            }
+            for (who, order_id) in Self::iter_quotations(order.pair_id(), counterparty_price) {
+                if scanned >= MAX_MAKERS_PER_ORDER {
+                    *makers = scanned;
+                    return false;
                 }
-                Side::Sell if lowest_ask.is_zero() || price < lowest_ask => {
-                    <HandicapOf<T>>::mutate(pair_index, |handicap| handicap.lowest_ask = price);
+                scanned += 1;
+                if let Some(maker_order) = <OrderInfoOf<T>>::get(who, order_id) {
+                    // The matching stops at the own order unless it's canceled.
+                    if maker_order.submitter() == order.submitter() {
+                        match order.self_trade_prevention() {
+                            SelfTradePrevention::None => (),
+                            SelfTradePrevention::CancelMaker => continue,
+                            _ => {
+                                *makers = scanned;
+                                return false;
+                            }
+                        }
+                    }
+                    available = available.saturating_add(maker_order.remaining_in_base());
+                    if available >= wanted {
+                        return true;
+                    }
                 }
-                _ => (),
             }
-        } else {
-            Self::match_order(&pair, order, &handicap);
         }
+
+        *makers = scanned;
+        false
+    }
+
+    /// Cancels the unfilled remainder of an order that can not rest in the order book.
+    ///
+    /// The order might have been canceled already due to the self-trade prevention.
+    fn cancel_unfilled_remainder(
+        pair: &TradingPairProfile,
+        order: &mut OrderInfo<T>,
+    ) -> DispatchResult {
+        let who = order.submitter();
+        if !order.is_canceled() {
+            Self::update_order_and_unreserve_on_cancel(order, pair, &who)?;
+        }
+        Self::remove_open_order(&who, order.pair_id(), order.id());
+        Ok(())
     }
 
     /// Insert a fresh order and return the inserted result.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn inject_order(
         who: T::AccountId,
         pair_id: TradingPairId,
@@ -58,16 +146,29 @@ impl<T: Trait> Module<T> {
         side: Side,
         amount: BalanceOf<T>,
         remaining: BalanceOf<T>,
+        time_in_force: TimeInForce,
+        expire_at: Option<T::BlockNumber>,
+        self_trade_prevention: SelfTradePrevention,
     ) -> Order<TradingPairId, T::AccountId, BalanceOf<T>, T::Price, T::BlockNumber> {
         let order_id = Self::order_count_of(&who);
 
         let submitter = who.clone();
         let order = Self::new_fresh_order(
-            pair_id, price, order_id, submitter, order_type, side, amount, remaining,
+            pair_id,
+            price,
+            order_id,
+            submitter,
+            order_type,
+            side,
+            amount,
+            remaining,
+            time_in_force,
+            expire_at,
+            self_trade_prevention,
         );
 
         debug!("[inject_order] New order:{:?}", order);
-        <OrderInfoOf<T>>::insert(&who, order_id, &order);
+        Self::insert_open_order(&order);
 
         // The order count of user should be increased after a new order is created.
         <OrderCountOf<T>>::insert(&who, order_id + 1);
@@ -88,6 +189,9 @@ impl<T: Trait> Module<T> {
         side: Side,
         amount: BalanceOf<T>,
         remaining: BalanceOf<T>,
+        time_in_force: TimeInForce,
+        expire_at: Option<T::BlockNumber>,
+        self_trade_prevention: SelfTradePrevention,
     ) -> Order<TradingPairId, T::AccountId, BalanceOf<T>, T::Price, T::BlockNumber> {
         let current_block = <frame_system::Module<T>>::block_number();
         let props = OrderProperty {
@@ -98,7 +202,10 @@ impl<T: Trait> Module<T> {
             price,
             id: order_id,
             order_type: class,
+            time_in_force,
+            self_trade_prevention,
             created_at: current_block,
+            expire_at,
         };
 
         Order::new(
@@ -114,62 +221,117 @@ impl<T: Trait> Module<T> {
     /// Match the newly created order.
     ///
     /// When the matching is complete, we should check if the order has been
-    /// fulfilled and update the handicap.
+    /// fulfilled, otherwise the remaining rests in the order book, unless
+    /// `MAX_MAKERS_PER_ORDER` maker orders have been touched, in which case
+    /// the remaining might still cross the order book and is canceled.
+    /// The remaining is canceled as well if it would index a new price level
+    /// while the side has `MAX_PRICE_LEVELS_PER_SIDE` price levels already.
     fn match_order(
         pair: &TradingPairProfile,
         order: &mut OrderInfo<T>,
-        handicap: &HandicapInfo<T>,
-    ) {
+        makers: &mut u32,
+    ) -> DispatchResult {
         #[cfg(feature = "std")]
         let now = std::time::Instant::now();
 
-        Self::apply_match_order(order, pair, handicap);
+        Self::apply_match_order(order, pair, makers);
 
         #[cfg(feature = "std")]
         debug!("Took {:?}ms to match this order", now.elapsed().as_millis());
 
-        // Remove the full filled order, otherwise the quotations, order status and handicap
+        // Remove the full filled order, otherwise the quotations and order status
         // should be updated.
         if order.is_fulfilled() {
             order.status = OrderStatus::Filled;
-            <OrderInfoOf<T>>::remove(order.submitter(), order.id());
+            Self::remove_open_order(&order.submitter(), order.pair_id(), order.id());
+        } else if order.is_canceled()
+            || order.time_in_force().is_immediate()
+            || *makers >= MAX_MAKERS_PER_ORDER
+            || !Self::can_rest_at(order.pair_id(), order.side(), order.price())
+        {
+            Self::cancel_unfilled_remainder(pair, order)?;
         } else {
-            <QuotationsOf<T>>::mutate(order.pair_id(), order.price(), |quotations| {
-                quotations.push((order.submitter(), order.id()))
-            });
+            Self::insert_quotation(
+                order.pair_id(),
+                order.price(),
+                order.side(),
+                (order.submitter(), order.id()),
+            );
 
-            // NOTE: Since the handicap is not always related to a real order,
-            // this guard statement is neccessary!
             if order.already_filled > Zero::zero() {
                 order.status = OrderStatus::PartialFill;
             }
 
             <OrderInfoOf<T>>::insert(order.submitter(), order.id(), order.clone());
-
-            Self::update_handicap_after_matching_order(pair, order);
         }
+
+        Ok(())
     }
 
+    /// Match the taker order against the maker orders at `counterparty_price`.
+    ///
+    /// `makers` counts the maker orders touched in the current call so far, including
+    /// the ones canceled due to the self-trade prevention, so that the cost of a call
+    /// is bounded. The matching stops at `MAX_MAKERS_PER_ORDER`.
     fn apply_match_order_given_counterparty(
         taker_order: &mut OrderInfo<T>,
         pair: &TradingPairProfile,
         counterparty_price: T::Price,
         counterparty_side: Side,
+        makers: &mut u32,
     ) {
-        let quotations = <QuotationsOf<T>>::get(pair.id, counterparty_price);
-        let mut fulfilled_orders = Vec::new();
-
-        for (who, order_index) in quotations.iter() {
-            if taker_order.is_fulfilled() {
+        let mut removed_orders = Vec::new();
+
+        // The fulfilled or canceled orders are removed after the iteration.
+        for (who, order_index) in Self::iter_quotations(pair.id, counterparty_price) {
+            if taker_order.is_fulfilled()
+                || taker_order.is_canceled()
+                || *makers >= MAX_MAKERS_PER_ORDER
+            {
                 break;
             }
+            *makers += 1;
             // Find the matched order.
-            if let Some(mut maker_order) = <OrderInfoOf<T>>::get(who, order_index) {
+            if let Some(mut maker_order) = <OrderInfoOf<T>>::get(&who, order_index) {
                 assert!(
                     counterparty_side == maker_order.side(),
                     "Opponent side should match the side of maker order."
                 );
 
+                let policy = taker_order.self_trade_prevention();
+                if maker_order.submitter() == taker_order.submitter()
+                    && policy != SelfTradePrevention::None
+                {
+                    debug!(
+                        "[apply_match_order_given_counterparty] Prevent self trade, who:{:?}, maker_order_id:{}, taker_order_id:{}, policy:{:?}",
+                        who, order_index, taker_order.id(), policy
+                    );
+
+                    if matches!(
+                        policy,
+                        SelfTradePrevention::CancelMaker | SelfTradePrevention::CancelBoth
+                    ) {
+                        let cancel_result = Self::update_order_and_unreserve_on_cancel(
+                            &mut maker_order,
+                            pair,
+                            &who,
+                        );
+                        assert!(cancel_result.is_ok(), "Cancel the maker order can not fail");
+                        removed_orders.push((maker_order.submitter(), maker_order.id()));
+                    }
+
+                    if matches!(
+                        policy,
+                        SelfTradePrevention::CancelTaker | SelfTradePrevention::CancelBoth
+                    ) {
+                        let cancel_result =
+                            Self::update_order_and_unreserve_on_cancel(taker_order, pair, &who);
+                        assert!(cancel_result.is_ok(), "Cancel the taker order can not fail");
+                    }
+
+                    continue;
+                }
+
                 let turnover = cmp::min(
                     taker_order.remaining_in_base(),
                     maker_order.remaining_in_base(),
@@ -188,35 +350,40 @@ impl<T: Trait> Module<T> {
 
                 // Remove maker_order if it has been full filled.
                 if maker_order.is_fulfilled() {
-                    fulfilled_orders.push((maker_order.submitter(), maker_order.id()));
-                    Self::update_handicap(&pair, counterparty_price, maker_order.side());
+                    removed_orders.push((maker_order.submitter(), maker_order.id()));
                 }
 
-                Self::update_latest_price(pair.id, counterparty_price);
+                Self::update_latest_price(pair.id, counterparty_price, turnover);
             }
         }
 
-        // Remove the fulfilled orders as well as the quotations.
-        if !fulfilled_orders.is_empty() {
-            Self::remove_orders_and_quotations(pair.id, counterparty_price, fulfilled_orders);
+        // Remove the fulfilled or canceled orders as well as the quotations.
+        if !removed_orders.is_empty() {
+            Self::remove_orders_and_quotations(
+                pair.id,
+                counterparty_price,
+                counterparty_side,
+                removed_orders,
+            );
         }
     }
 
+    /// Match the buy order against the asks from the lowest price level
+    /// up to the price of taker order.
     fn match_taker_order_buy(
         taker_order: &mut OrderInfo<T>,
         pair: &TradingPairProfile,
-        lowest_ask: T::Price,
+        makers: &mut u32,
     ) {
-        let tick = pair.tick();
         let my_quote = taker_order.price();
-
         let counterparty_side = Side::Sell;
-        let (floor, ceiling) = (lowest_ask, my_quote);
-
-        let mut counterparty_price = floor;
 
-        while !counterparty_price.is_zero() && counterparty_price <= ceiling {
-            if taker_order.is_fulfilled() {
+        for counterparty_price in Self::price_levels_of(pair.id, counterparty_side) {
+            if taker_order.is_fulfilled()
+                || taker_order.is_canceled()
+                || *makers >= MAX_MAKERS_PER_ORDER
+                || counterparty_price > my_quote
+            {
                 return;
             }
             Self::apply_match_order_given_counterparty(
@@ -224,26 +391,30 @@ impl<T: Trait> Module<T> {
                 pair,
                 counterparty_price,
                 counterparty_side,
+                makers,
             );
-            counterparty_price = Self::tick_up(counterparty_price, tick);
         }
     }
 
+    /// Match the sell order against the bids from the highest price level
+    /// down to the price of taker order.
     fn match_taker_order_sell(
         taker_order: &mut OrderInfo<T>,
         pair: &TradingPairProfile,
-        highest_bid: T::Price,
+        makers: &mut u32,
     ) {
-        let tick = pair.tick();
         let my_quote = taker_order.price();
-
         let counterparty_side = Side::Buy;
-        let (floor, ceiling) = (my_quote, highest_bid);
-
-        let mut counterparty_price = ceiling;
 
-        while !counterparty_price.is_zero() && counterparty_price >= floor {
-            if taker_order.is_fulfilled() {
+        for counterparty_price in Self::price_levels_of(pair.id, counterparty_side)
+            .into_iter()
+            .rev()
+        {
+            if taker_order.is_fulfilled()
+                || taker_order.is_canceled()
+                || *makers >= MAX_MAKERS_PER_ORDER
+                || counterparty_price < my_quote
+            {
                 return;
             }
             Self::apply_match_order_given_counterparty(
@@ -251,26 +422,96 @@ impl<T: Trait> Module<T> {
                 pair,
                 counterparty_price,
                 counterparty_side,
+                makers,
             );
-            counterparty_price = Self::tick_down(counterparty_price, tick);
         }
     }
 
-    /// TODO: optimize the matching order.
+    /// Match the taker order against the non-empty price levels of the counterparty.
     ///
-    /// Currently the matching is processed by iterating the tick one by one.
+    ///  Buy: [ lowest_ask  , my_quote ]
+    /// Sell: [ my_quote , highest_bid ]
     fn apply_match_order(
         taker_order: &mut OrderInfo<T>,
         pair: &TradingPairProfile,
-        handicap: &HandicapInfo<T>,
+        makers: &mut u32,
     ) {
-        let (lowest_ask, highest_bid) = (handicap.lowest_ask, handicap.highest_bid);
-
-        //  Buy: [ lowest_ask  , my_quote ]
-        // Sell: [ my_quote , highest_bid ]
         match taker_order.side() {
-            Side::Buy => Self::match_taker_order_buy(taker_order, pair, lowest_ask),
-            Side::Sell => Self::match_taker_order_sell(taker_order, pair, highest_bid),
+            Side::Buy => Self::match_taker_order_buy(taker_order, pair, makers),
+            Side::Sell => Self::match_taker_order_sell(taker_order, pair, makers),
+        }
+    }
+
+    /// Match the market buy order against the asks from the lowest price level
+    /// up to the slippage bound.
+    ///
+    /// The base amount of a market buy is unknown beforehand, it's determined by
+    /// how much base currency the remaining quote currency can afford at each level,
+    /// rounded down to the lot size so that the maker orders are filled in lots.
+    fn match_market_order_buy(
+        taker_order: &mut OrderInfo<T>,
+        pair: &TradingPairProfile,
+        makers: &mut u32,
+    ) {
+        let max_price = taker_order.price();
+        let counterparty_side = Side::Sell;
+        let lot_size = Self::order_size_limit_of(pair.id).lot_size;
+
+        for counterparty_price in Self::price_levels_of(pair.id, counterparty_side) {
+            if taker_order.is_canceled()
+                || *makers >= MAX_MAKERS_PER_ORDER
+                || counterparty_price > max_price
+            {
+                break;
+            }
+
+            let affordable = match Self::convert_quote_to_base(
+                taker_order.remaining,
+                counterparty_price,
+                pair,
+            ) {
+                Ok(affordable) if lot_size.is_zero() => affordable,
+                Ok(affordable) => affordable - affordable % lot_size,
+                Err(_) => break,
+            };
+            if affordable.is_zero() {
+                break;
+            }
+            taker_order.props.amount = taker_order.already_filled + affordable;
+
+            Self::apply_match_order_given_counterparty(
+                taker_order,
+                pair,
+                counterparty_price,
+                counterparty_side,
+                makers,
+            );
+        }
+
+        taker_order.props.amount = taker_order.already_filled;
+    }
+
+    /// Fill the market order against the book immediately.
+    ///
+    /// The market order never rests in the order book, the unfilled remainder
+    /// is refunded as if the order has been canceled.
+    ///
+    /// `makers` counts the maker orders touched in the current call.
+    pub(crate) fn match_market_order(
+        pair: &TradingPairProfile,
+        order: &mut OrderInfo<T>,
+        makers: &mut u32,
+    ) -> DispatchResult {
+        match order.side() {
+            Side::Buy => Self::match_market_order_buy(order, pair, makers),
+            Side::Sell => Self::match_taker_order_sell(order, pair, makers),
+        }
+
+        if order.status == OrderStatus::Filled {
+            Self::remove_open_order(&order.submitter(), order.pair_id(), order.id());
+            Ok(())
+        } else {
+            Self::cancel_unfilled_remainder(pair, order)
         }
     }
 
@@ -280,15 +521,12 @@ impl<T: Trait> Module<T> {
         price: T::Price,
         who: T::AccountId,
         order_index: OrderId,
-        pair: TradingPairProfile,
         order_side: Side,
     ) {
-        <OrderInfoOf<T>>::remove(&who, order_index);
+        Self::remove_open_order(&who, pair_id, order_index);
 
         let order_key = (who, order_index);
-        Self::remove_quotation(pair_id, price, order_key);
-
-        Self::update_handicap(&pair, price, order_side);
+        Self::remove_quotation(pair_id, price, order_side, order_key);
     }
 
     /// Update the status of order after the turnover is calculated.
@@ -358,14 +596,15 @@ impl<T: Trait> Module<T> {
         Self::update_order_on_execute(maker_order, &turnover, trading_history_idx);
         Self::update_order_on_execute(taker_order, &turnover, trading_history_idx);
 
-        let (maker_turnover_amount, taker_turnover_amount) = Self::delivery_asset_to_each_other(
-            maker_order.side(),
-            &pair,
-            turnover,
-            price,
-            maker_order,
-            taker_order,
-        )?;
+        let (maker_turnover_amount, taker_turnover_amount, maker_fee, taker_fee) =
+            Self::delivery_asset_to_each_other(
+                maker_order.side(),
+                &pair,
+                turnover,
+                price,
+                maker_order,
+                taker_order,
+            )?;
 
         maker_order.decrease_remaining_on_execute(maker_turnover_amount);
         taker_order.decrease_remaining_on_execute(taker_turnover_amount);
@@ -381,18 +620,28 @@ impl<T: Trait> Module<T> {
         Self::insert_executed_order(maker_order);
         Self::insert_executed_order(taker_order);
 
+        Self::refresh_order_mining_power(&maker_order.submitter(), maker_order.id());
+
         // FIXME: The information delivered by these events seems be redundant.
         Self::deposit_event(Event::<T>::MakerOrderUpdated(maker_order.clone()));
         Self::deposit_event(Event::<T>::TakerOrderUpdated(taker_order.clone()));
-        Self::deposit_event(Event::<T>::OrderExecuted(OrderExecutedInfo::new(
+        let executed_info = OrderExecutedInfo::new(
             trading_history_idx,
             pair_id,
             price,
             turnover,
+            maker_fee,
+            taker_fee,
             maker_order,
             taker_order,
             <frame_system::Module<T>>::block_number(),
-        )));
+        );
+        TradeHistoryOf::<T>::insert(
+            pair_id,
+            trading_history_idx % MAX_TRADE_HISTORY,
+            &executed_info,
+        );
+        Self::deposit_event(Event::<T>::OrderExecuted(executed_info));
 
         Ok(())
     }
@@ -0,0 +1,265 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of the constant-product liquidity pools.
+//!
+//! A pool holds the reserves of both currencies of a trading pair and always
+//! quotes at the price that keeps `base_reserve * quote_reserve` unchanged,
+//! which provides the baseline liquidity besides the order book.
+
+use super::*;
+use sp_arithmetic::{helpers_128bit::multiply_by_rational, traits::IntegerSquareRoot};
+use sp_runtime::{
+    traits::{AccountIdConversion, CheckedAdd, Saturating},
+    ModuleId,
+};
+
+/// The ID used to derive the account holding the reserves of each pool.
+const POOL_MODULE_ID: ModuleId = ModuleId(*b"pcx/pool");
+
+impl<T: Trait> Module<T> {
+    /// Returns the account holding the reserves of the liquidity pool of a trading pair.
+    pub fn pool_account_of(pair_id: TradingPairId) -> T::AccountId {
+        POOL_MODULE_ID.into_sub_account(pair_id)
+    }
+
+    /// Returns the output of swapping `amount_in` against the pool, after the swap fee.
+    ///
+    /// Sell: base currency in, quote currency out.
+    ///  Buy: quote currency in, base currency out.
+    pub fn swap_output(
+        pool: &LiquidityPoolInfo<T>,
+        side: Side,
+        amount_in: BalanceOf<T>,
+    ) -> Result<BalanceOf<T>, Error<T>> {
+        let (reserve_in, reserve_out) = pool.reserves_of(side);
+        ensure!(
+            !reserve_in.is_zero() && !reserve_out.is_zero(),
+            Error::<T>::InsufficientLiquidity
+        );
+
+        let amount_in_after_fee = amount_in.saturating_sub(pool.fee * amount_in);
+        let new_reserve_in = reserve_in
+            .checked_add(&amount_in_after_fee)
+            .ok_or(Error::<T>::AmountOverflow)?;
+
+        Self::multiply_by_rational_of(amount_in_after_fee, reserve_out, new_reserve_in)
+    }
+
+    fn liquidity_pool(pair_id: TradingPairId) -> Result<LiquidityPoolInfo<T>, Error<T>> {
+        Self::liquidity_pool_of(pair_id).ok_or(Error::<T>::NonexistentLiquidityPool)
+    }
+
+    /// Calculates `a * b / c`, rounded down.
+    fn multiply_by_rational_of(
+        a: BalanceOf<T>,
+        b: BalanceOf<T>,
+        c: BalanceOf<T>,
+    ) -> Result<BalanceOf<T>, Error<T>> {
+        multiply_by_rational(a.saturated_into(), b.saturated_into(), c.saturated_into())
+            .map(|x| x.saturated_into())
+            .map_err(|_| Error::<T>::AmountOverflow)
+    }
+
+    pub(crate) fn apply_create_liquidity_pool(
+        pair_id: TradingPairId,
+        lp_asset_id: AssetId,
+        fee: Permill,
+    ) -> DispatchResult {
+        let pair = Self::trading_pair(pair_id)?;
+        ensure!(
+            !LiquidityPoolOf::<T>::contains_key(pair_id),
+            Error::<T>::LiquidityPoolAlreadyExists
+        );
+
+        // The LP asset must be dedicated to this pool.
+        ensure!(
+            lp_asset_id != T::NativeAssetId::get()
+                && lp_asset_id != pair.base()
+                && lp_asset_id != pair.quote(),
+            Error::<T>::InvalidLpAsset
+        );
+        <xpallet_assets_registrar::Module<T>>::ensure_asset_is_valid(&lp_asset_id)
+            .map_err(|_| Error::<T>::InvalidLpAsset)?;
+        ensure!(
+            <xpallet_assets::Module<T>>::total_issuance(&lp_asset_id).is_zero()
+                && !LiquidityPoolOf::<T>::iter().any(|(_, pool)| pool.lp_asset_id == lp_asset_id),
+            Error::<T>::InvalidLpAsset
+        );
+
+        LiquidityPoolOf::<T>::insert(pair_id, LiquidityPool::new(lp_asset_id, fee));
+
+        Self::deposit_event(Event::<T>::LiquidityPoolCreated(pair_id, lp_asset_id, fee));
+
+        Ok(())
+    }
+
+    /// Deposits `base_amount` and the proportional quote currency into the pool
+    /// and issues the LP shares to `who`.
+    ///
+    /// The first liquidity provider decides the initial price of the pool, the
+    /// shares minted for them are the geometric mean of the deposits.
+    pub(crate) fn apply_add_liquidity(
+        who: &T::AccountId,
+        pair_id: TradingPairId,
+        base_amount: BalanceOf<T>,
+        max_quote_amount: BalanceOf<T>,
+        min_shares: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(
+            !base_amount.is_zero() && !max_quote_amount.is_zero(),
+            Error::<T>::ZeroAmount
+        );
+        let pair = Self::trading_pair(pair_id)?;
+        ensure!(pair.tradable, Error::<T>::TradingPairUntradable);
+        ensure!(
+            !Self::is_delisting(pair_id),
+            Error::<T>::TradingPairDelisting
+        );
+        let mut pool = Self::liquidity_pool(pair_id)?;
+
+        let (quote_amount, shares) = if pool.total_shares.is_zero() {
+            let product = base_amount
+                .saturated_into::<u128>()
+                .checked_mul(max_quote_amount.saturated_into())
+                .ok_or(Error::<T>::AmountOverflow)?;
+            (max_quote_amount, product.integer_sqrt().saturated_into())
+        } else {
+            // Rounded down, so that the shares minted never dilute the existing ones.
+            let quote_amount =
+                Self::multiply_by_rational_of(base_amount, pool.quote_reserve, pool.base_reserve)?;
+            ensure!(
+                quote_amount <= max_quote_amount,
+                Error::<T>::SlippageExceeded
+            );
+            let shares =
+                Self::multiply_by_rational_of(quote_amount, pool.total_shares, pool.quote_reserve)?;
+            (quote_amount, shares)
+        };
+
+        ensure!(
+            !quote_amount.is_zero() && !shares.is_zero(),
+            Error::<T>::InsufficientLiquidity
+        );
+        ensure!(shares >= min_shares, Error::<T>::SlippageExceeded);
+
+        let pool_account = Self::pool_account_of(pair_id);
+        Self::deposit_into_pool(who, &pool_account, pair.base(), base_amount)?;
+        Self::deposit_into_pool(who, &pool_account, pair.quote(), quote_amount)?;
+        <xpallet_assets::Module<T>>::issue(&pool.lp_asset_id, who, shares)?;
+
+        pool.base_reserve = pool.base_reserve.saturating_add(base_amount);
+        pool.quote_reserve = pool.quote_reserve.saturating_add(quote_amount);
+        pool.total_shares = pool.total_shares.saturating_add(shares);
+        LiquidityPoolOf::<T>::insert(pair_id, pool);
+
+        Self::deposit_event(Event::<T>::LiquidityAdded(
+            who.clone(),
+            pair_id,
+            base_amount,
+            quote_amount,
+            shares,
+        ));
+
+        Ok(())
+    }
+
+    /// Burns the LP `shares` of `who` and withdraws the proportional reserves from the pool.
+    ///
+    /// The liquidity can still be removed when the trading pair is untradable.
+    pub(crate) fn apply_remove_liquidity(
+        who: &T::AccountId,
+        pair_id: TradingPairId,
+        shares: BalanceOf<T>,
+        min_base_amount: BalanceOf<T>,
+        min_quote_amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(!shares.is_zero(), Error::<T>::ZeroAmount);
+        let pair = Self::trading_pair(pair_id)?;
+        let mut pool = Self::liquidity_pool(pair_id)?;
+        ensure!(
+            shares <= pool.total_shares,
+            Error::<T>::InsufficientLiquidity
+        );
+
+        let base_amount =
+            Self::multiply_by_rational_of(shares, pool.base_reserve, pool.total_shares)?;
+        let quote_amount =
+            Self::multiply_by_rational_of(shares, pool.quote_reserve, pool.total_shares)?;
+        ensure!(
+            base_amount >= min_base_amount && quote_amount >= min_quote_amount,
+            Error::<T>::SlippageExceeded
+        );
+
+        <xpallet_assets::Module<T>>::destroy_usable(&pool.lp_asset_id, who, shares)?;
+        let pool_account = Self::pool_account_of(pair_id);
+        Self::withdraw_from_pool(&pool_account, who, pair.base(), base_amount)?;
+        Self::withdraw_from_pool(&pool_account, who, pair.quote(), quote_amount)?;
+
+        pool.base_reserve = pool.base_reserve.saturating_sub(base_amount);
+        pool.quote_reserve = pool.quote_reserve.saturating_sub(quote_amount);
+        pool.total_shares = pool.total_shares.saturating_sub(shares);
+        LiquidityPoolOf::<T>::insert(pair_id, pool);
+
+        Self::deposit_event(Event::<T>::LiquidityRemoved(
+            who.clone(),
+            pair_id,
+            base_amount,
+            quote_amount,
+            shares,
+        ));
+
+        Ok(())
+    }
+
+    /// Swaps `amount_in` against the pool, the swap fee is left in the pool for the LPs.
+    pub(crate) fn apply_swap(
+        who: &T::AccountId,
+        pair_id: TradingPairId,
+        side: Side,
+        amount_in: BalanceOf<T>,
+        min_amount_out: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(!amount_in.is_zero(), Error::<T>::ZeroAmount);
+        let pair = Self::trading_pair(pair_id)?;
+        ensure!(pair.tradable, Error::<T>::TradingPairUntradable);
+        ensure!(
+            !Self::is_delisting(pair_id),
+            Error::<T>::TradingPairDelisting
+        );
+        let mut pool = Self::liquidity_pool(pair_id)?;
+
+        let amount_out = Self::swap_output(&pool, side, amount_in)?;
+        ensure!(!amount_out.is_zero(), Error::<T>::InsufficientLiquidity);
+        ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+        let (asset_in, asset_out) = match side {
+            Side::Buy => (pair.quote(), pair.base()),
+            Side::Sell => (pair.base(), pair.quote()),
+        };
+        let pool_account = Self::pool_account_of(pair_id);
+        Self::deposit_into_pool(who, &pool_account, asset_in, amount_in)?;
+        Self::withdraw_from_pool(&pool_account, who, asset_out, amount_out)?;
+
+        match side {
+            Side::Buy => {
+                pool.quote_reserve = pool.quote_reserve.saturating_add(amount_in);
+                pool.base_reserve = pool.base_reserve.saturating_sub(amount_out);
+            }
+            Side::Sell => {
+                pool.base_reserve = pool.base_reserve.saturating_add(amount_in);
+                pool.quote_reserve = pool.quote_reserve.saturating_sub(amount_out);
+            }
+        }
+        LiquidityPoolOf::<T>::insert(pair_id, pool);
+
+        Self::deposit_event(Event::<T>::Swapped(
+            who.clone(),
+            pair_id,
+            side,
+            amount_in,
+            amount_out,
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of routing a swap through the order books of several
+//! trading pairs, e.g., X_BTC -> PCX -> X_DOT.
+
+use super::*;
+use sp_runtime::traits::Saturating;
+
+impl<T: Trait> Module<T> {
+    /// Returns the trading pair and the side of the market order which swaps
+    /// `asset_in` for `asset_out`.
+    fn swap_hop_of(
+        asset_in: AssetId,
+        asset_out: AssetId,
+    ) -> Result<(TradingPairProfile, Side), Error<T>> {
+        if let Some(pair) =
+            Self::get_trading_pair_by_currency_pair(&CurrencyPair::new(asset_in, asset_out))
+        {
+            Ok((pair, Side::Sell))
+        } else if let Some(pair) =
+            Self::get_trading_pair_by_currency_pair(&CurrencyPair::new(asset_out, asset_in))
+        {
+            Ok((pair, Side::Buy))
+        } else {
+            Err(Error::<T>::InvalidSwapPath)
+        }
+    }
+
+    /// Returns the worst price a market order of the route can accept, i.e., the
+    /// price fluctuation away from the best price of the counterparty.
+    fn market_price_bound(pair: &TradingPairProfile, side: Side) -> Result<T::Price, Error<T>> {
+        let handicap = Self::handicap_of(pair.id);
+        let fluctuation: T::Price = pair.calc_fluctuation::<T>().saturated_into();
+        let bound = match side {
+            Side::Buy => handicap
+                .lowest_ask
+                .map(|lowest_ask| lowest_ask.saturating_add(fluctuation)),
+            Side::Sell => handicap.highest_bid.map(|highest_bid| {
+                if highest_bid > fluctuation {
+                    highest_bid - fluctuation
+                } else {
+                    pair.tick().saturated_into()
+                }
+            }),
+        };
+        bound.ok_or(Error::<T>::InsufficientLiquidity)
+    }
+
+    /// Puts a market order of `amount` on the trading pair and returns how much
+    /// `asset_out` has been received as well as the number of the maker orders touched.
+    ///
+    /// The amount of a market sell is rounded down to the lot size, the unfilled
+    /// remainder is refunded to `who` as usual.
+    fn swap_through_order_book(
+        who: &T::AccountId,
+        pair: &TradingPairProfile,
+        side: Side,
+        asset_out: AssetId,
+        amount: BalanceOf<T>,
+    ) -> Result<(BalanceOf<T>, u32), DispatchError> {
+        let amount = match side {
+            Side::Buy => amount,
+            Side::Sell => {
+                let lot_size = Self::order_size_limit_of(pair.id).lot_size;
+                if lot_size.is_zero() {
+                    amount
+                } else {
+                    amount - amount % lot_size
+                }
+            }
+        };
+        let price = Self::market_price_bound(pair, side)?;
+
+        let before = Self::usable_balance_of(who, asset_out);
+        let mut makers = 0;
+        Self::do_put_order(
+            who.clone(),
+            OrderRequest {
+                pair_id: pair.id,
+                order_type: OrderType::Market,
+                side,
+                amount,
+                price,
+                time_in_force: TimeInForce::ImmediateOrCancel,
+                expire_at: None,
+                self_trade_prevention: None,
+            },
+            &mut makers,
+        )?;
+        let received = Self::usable_balance_of(who, asset_out).saturating_sub(before);
+
+        ensure!(!received.is_zero(), Error::<T>::InsufficientLiquidity);
+        Ok((received, makers))
+    }
+
+    /// Returns the weight of a swap of `hops` hops which touches `makers` maker orders
+    /// in total, the weight function itself is benchmarked with a single hop.
+    pub(crate) fn swap_exact_in_weight(hops: u32, makers: u32) -> Weight {
+        let per_hop = <T as Trait>::WeightInfo::swap_exact_in(0);
+        per_hop
+            .saturating_mul(hops as Weight)
+            .saturating_add(<T as Trait>::WeightInfo::swap_exact_in(makers).saturating_sub(per_hop))
+    }
+
+    /// Swaps `amount_in` of the first asset in `path` for the last one by filling
+    /// a market order on the trading pair of each two adjacent assets in turn.
+    ///
+    /// Each hop spends what has been received from the previous one, the swap
+    /// fails if the final output is less than `min_amount_out`.
+    ///
+    /// Returns the number of the maker orders touched by all the hops.
+    pub(crate) fn apply_swap_exact_in(
+        who: &T::AccountId,
+        path: &[AssetId],
+        amount_in: BalanceOf<T>,
+        min_amount_out: BalanceOf<T>,
+    ) -> Result<u32, DispatchError> {
+        ensure!(
+            path.len() >= 2 && path.len() <= MAX_SWAP_PATH_LEN,
+            Error::<T>::InvalidSwapPath
+        );
+        ensure!(!amount_in.is_zero(), Error::<T>::ZeroAmount);
+
+        let hops = path
+            .windows(2)
+            .map(|hop| Self::swap_hop_of(hop[0], hop[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut amount = amount_in;
+        let mut makers = 0u32;
+        for ((pair, side), &asset_out) in hops.iter().zip(path.iter().skip(1)) {
+            let (received, touched) =
+                Self::swap_through_order_book(who, pair, *side, asset_out, amount)?;
+            amount = received;
+            makers = makers.saturating_add(touched);
+        }
+        ensure!(amount >= min_amount_out, Error::<T>::SlippageExceeded);
+
+        Self::deposit_event(Event::<T>::SwappedExactIn(
+            who.clone(),
+            path.to_vec(),
+            amount_in,
+            amount,
+        ));
+
+        Ok(makers)
+    }
+}
@@ -1,152 +1,212 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
-//! This module handles the internal state of spot, mainly the handicap and the quotation list.
+//! This module handles the internal state of spot, mainly the handicap and the quotations.
 
 use super::*;
-use crate::types::Side::{Buy, Sell};
-use sp_runtime::traits::{CheckedAdd, CheckedSub};
 
 /// Internal mutables
 impl<T: Trait> Module<T> {
-    /// It's worth noting that the handicap is not always related to some real orders, i.e.,
-    /// current lowest_ask(or highest_bid) is suprious.
+    /// Derives the handicap of `side` from the non-empty price levels.
     ///
-    /// When there is no quotions at a certain price given the trading pair, we should check out
-    /// whether the current handicap is true. If it's not true, adjust a tick accordingly.
-    pub(super) fn update_handicap(pair: &TradingPairProfile, price: T::Price, side: Side) {
-        let tick_decimals = pair.tick_decimals;
-
-        if <QuotationsOf<T>>::get(pair.id, price).is_empty() {
-            let mut handicap = <HandicapOf<T>>::get(pair.id);
-            match side {
-                Side::Sell => {
-                    if !handicap.lowest_ask.is_zero()
-                        && <QuotationsOf<T>>::get(pair.id, handicap.lowest_ask).is_empty()
-                    {
-                        handicap.tick_up_lowest_ask(tick_decimals);
-                        <HandicapOf<T>>::insert(pair.id, &handicap);
-
-                        debug!(
-                            "[update_handicap] pair_index: {:?}, lowest_ask: {:?}, side: {:?}",
-                            pair.id, handicap.lowest_ask, Sell,
-                        );
-                    }
-                }
-                Side::Buy => {
-                    if !handicap.highest_bid.is_zero()
-                        && <QuotationsOf<T>>::get(pair.id, handicap.highest_bid).is_empty()
-                    {
-                        handicap.tick_down_highest_bid(tick_decimals);
-                        <HandicapOf<T>>::insert(pair.id, &handicap);
-
-                        debug!(
-                            "[update_handicap] pair_index: {:?}, highest_bid: {:?}, side: {:?}",
-                            pair.id, handicap.highest_bid, Buy
-                        );
-                    }
-                }
-            };
+    /// This is called whenever a price level is indexed or removed, so that the
+    /// handicap always reflects the best prices of the real orders. The orders
+    /// moving into or out of the maker mining band are queued to be refreshed.
+    fn update_handicap(pair_id: TradingPairId, side: Side, levels: &[T::Price]) {
+        let new_best = match side {
+            Side::Buy => levels.last().copied(),
+            Side::Sell => levels.first().copied(),
         };
+        let old_best = <HandicapOf<T>>::mutate(pair_id, |handicap| match side {
+            Side::Buy => sp_std::mem::replace(&mut handicap.highest_bid, new_best),
+            Side::Sell => sp_std::mem::replace(&mut handicap.lowest_ask, new_best),
+        });
+        Self::refresh_mining_power_on_handicap_change(pair_id, side, levels, old_best, new_best);
+        debug!(
+            "[update_handicap] pair_id: {:?}, side: {:?}, handicap: {:?}",
+            pair_id,
+            side,
+            Self::handicap_of(pair_id)
+        );
     }
 
-    pub(super) fn update_handicap_after_matching_order(
-        pair: &TradingPairProfile,
-        order: &mut OrderInfo<T>,
-    ) {
-        match order.side() {
-            Side::Buy => Self::update_handicap_of_buyers(pair, order),
-            Side::Sell => Self::update_handicap_of_sellers(pair, order),
-        }
+    /// Inserts the new order and indexes it in the open orders of the submitter.
+    pub(super) fn insert_open_order(order: &OrderInfo<T>) {
+        let who = order.submitter();
+        <OpenOrdersOf<T>>::insert((who.clone(), order.pair_id()), order.id(), ());
+        <OrderInfoOf<T>>::insert(&who, order.id(), order);
     }
 
-    pub(super) fn tick_up(v: T::Price, tick: Tick) -> T::Price {
-        match v.checked_add(&tick.saturated_into()) {
-            Some(x) => x,
-            None => panic!("Fail to tick up"),
-        }
+    /// Removes the order as well as the index in the open orders of the submitter.
+    pub(super) fn remove_open_order(who: &T::AccountId, pair_id: TradingPairId, order_id: OrderId) {
+        <OpenOrdersOf<T>>::remove((who.clone(), pair_id), order_id);
+        <OrderInfoOf<T>>::remove(who, order_id);
+    }
+
+    /// Returns true if there are any quotations at `price` of a trading pair.
+    pub(crate) fn has_quotations(pair_id: TradingPairId, price: T::Price) -> bool {
+        <QuotationQueueOf<T>>::contains_key(pair_id, price)
     }
 
-    /// This is only used for updating the handicap. Return zero when underflow.
-    pub(super) fn tick_down(v: T::Price, tick: Tick) -> T::Price {
-        v.checked_sub(&tick.saturated_into())
-            .unwrap_or_else(Zero::zero)
+    /// Returns true if an order can rest at `price`, i.e., the price level is non-empty
+    /// already or there is still room for a new one on the side.
+    pub(crate) fn can_rest_at(pair_id: TradingPairId, side: Side, price: T::Price) -> bool {
+        Self::has_quotations(pair_id, price)
+            || Self::price_levels_of(pair_id, side).len() < MAX_PRICE_LEVELS_PER_SIDE
     }
 
-    fn update_handicap_of_buyers(pair: &TradingPairProfile, order: &mut OrderInfo<T>) {
-        HandicapOf::<T>::mutate(pair.id, |handicap| {
-            let order_price = order.price();
+    /// Returns the quotations at `price` of a trading pair in the time priority.
+    pub(crate) fn iter_quotations(pair_id: TradingPairId, price: T::Price) -> Quotations<T> {
+        Quotations {
+            next: <QuotationQueueOf<T>>::get(pair_id, price).map(|queue| queue.head),
+        }
+    }
 
-            if order_price > handicap.highest_bid || handicap.highest_bid.is_zero() {
-                let new_highest_bid = order_price;
+    /// Returns the quotations following `order_key` at its price level, the order
+    /// itself included.
+    pub(crate) fn iter_quotations_from(order_key: (T::AccountId, OrderId)) -> Quotations<T> {
+        Quotations {
+            next: Some(order_key),
+        }
+    }
 
-                if new_highest_bid >= handicap.lowest_ask {
-                    handicap.lowest_ask = Self::tick_up(new_highest_bid, pair.tick());
-                    debug!(
-                        "[update_handicap] pair_id: {:?}, lowest_ask: {:?}, side: {:?}",
-                        order.pair_id(),
-                        handicap.lowest_ask,
-                        Side::Sell,
-                    );
-                }
+    /// Returns all the quotations at `price` of a trading pair in the time priority.
+    pub fn quotations_of(pair_id: TradingPairId, price: T::Price) -> Vec<(T::AccountId, OrderId)> {
+        Self::iter_quotations(pair_id, price).collect()
+    }
 
-                handicap.highest_bid = new_highest_bid;
-                debug!(
-                    "[update_handicap] pair_id: {:?}, highest_bid: {:?}, side: {:?}",
-                    order.pair_id(),
-                    new_highest_bid,
-                    Side::Buy
-                );
+    /// Appends the order to the tail of the quotations at `price`.
+    ///
+    /// The price level is indexed in `PriceLevelsOf` if it was empty before,
+    /// in which case the handicap is updated too.
+    pub(super) fn insert_quotation(
+        pair_id: TradingPairId,
+        price: T::Price,
+        side: Side,
+        order_key: (T::AccountId, OrderId),
+    ) {
+        let node = match <QuotationQueueOf<T>>::get(pair_id, price) {
+            Some(mut queue) => {
+                let (tail_who, tail_order_id) = &queue.tail;
+                <QuotationNodeOf<T>>::mutate(tail_who, tail_order_id, |tail| {
+                    if let Some(tail) = tail {
+                        tail.next = Some(order_key.clone());
+                    }
+                });
+                let prev = sp_std::mem::replace(&mut queue.tail, order_key.clone());
+                <QuotationQueueOf<T>>::insert(pair_id, price, queue);
+                QuotationNode {
+                    prev: Some(prev),
+                    next: None,
+                }
             }
-        });
+            None => {
+                let queue = QuotationQueue {
+                    head: order_key.clone(),
+                    tail: order_key.clone(),
+                };
+                <QuotationQueueOf<T>>::insert(pair_id, price, queue);
+                QuotationNode {
+                    prev: None,
+                    next: None,
+                }
+            }
+        };
+        <QuotationNodeOf<T>>::insert(&order_key.0, order_key.1, node);
+
+        let mut levels = Self::price_levels_of(pair_id, side);
+        if let Err(pos) = levels.binary_search(&price) {
+            levels.insert(pos, price);
+            Self::update_handicap(pair_id, side, &levels);
+            <PriceLevelsOf<T>>::insert(pair_id, side, levels);
+        }
+
+        Self::refresh_order_mining_power(&order_key.0, order_key.1);
     }
 
-    fn update_handicap_of_sellers(pair: &TradingPairProfile, order: &mut OrderInfo<T>) {
-        HandicapOf::<T>::mutate(pair.id, |handicap| {
-            let order_price = order.price();
+    /// Unlinks the order from the quotations at `price`.
+    ///
+    /// Returns false if the order is not in the quotations.
+    fn unlink_quotation(
+        pair_id: TradingPairId,
+        price: T::Price,
+        (who, order_id): &(T::AccountId, OrderId),
+    ) -> bool {
+        let QuotationNode { prev, next } = match <QuotationNodeOf<T>>::take(who, order_id) {
+            Some(node) => node,
+            None => return false,
+        };
+        Self::set_order_mining_power(who, *order_id, 0);
 
-            if order_price < handicap.lowest_ask || handicap.lowest_ask.is_zero() {
-                let new_lowest_ask = order_price;
+        if let Some((prev_who, prev_order_id)) = &prev {
+            <QuotationNodeOf<T>>::mutate(prev_who, prev_order_id, |node| {
+                if let Some(node) = node {
+                    node.next = next.clone();
+                }
+            });
+        }
+        if let Some((next_who, next_order_id)) = &next {
+            <QuotationNodeOf<T>>::mutate(next_who, next_order_id, |node| {
+                if let Some(node) = node {
+                    node.prev = prev.clone();
+                }
+            });
+        }
 
-                if new_lowest_ask <= handicap.highest_bid {
-                    handicap.highest_bid = Self::tick_down(new_lowest_ask, pair.tick());
-                    debug!(
-                        "[update_handicap] pair_id: {:?}, highest_bid: {:?}, side: {:?}",
-                        order.pair_id(),
-                        handicap.highest_bid,
-                        Side::Buy
-                    );
+        match (prev, next) {
+            (None, None) => <QuotationQueueOf<T>>::remove(pair_id, price),
+            (None, Some(next)) => <QuotationQueueOf<T>>::mutate(pair_id, price, |queue| {
+                if let Some(queue) = queue {
+                    queue.head = next;
+                }
+            }),
+            (Some(prev), None) => <QuotationQueueOf<T>>::mutate(pair_id, price, |queue| {
+                if let Some(queue) = queue {
+                    queue.tail = prev;
                 }
+            }),
+            (Some(_), Some(_)) => (),
+        }
 
-                handicap.lowest_ask = new_lowest_ask;
-                debug!(
-                    "[update_handicap] pair_id: {:?}, lowest_ask: {:?}, side: {:?}",
-                    order.pair_id(),
-                    new_lowest_ask,
-                    Side::Sell,
-                );
+        true
+    }
+
+    /// Removes the price level from `PriceLevelsOf` if there are no quotations at `price`,
+    /// the handicap falls back to the next best price level.
+    fn try_remove_price_level(pair_id: TradingPairId, price: T::Price, side: Side) {
+        if !Self::has_quotations(pair_id, price) {
+            let mut levels = Self::price_levels_of(pair_id, side);
+            if let Ok(pos) = levels.binary_search(&price) {
+                levels.remove(pos);
+                Self::update_handicap(pair_id, side, &levels);
+                <PriceLevelsOf<T>>::insert(pair_id, side, levels);
             }
-        });
+            debug!(
+                "[try_remove_price_level] pair_id: {:?}, price: {:?}, side: {:?}",
+                pair_id, price, side
+            );
+        }
     }
 
     /// Removes the order as well as the quotations from the order list.
     ///
-    /// This happens when the maker orders have been completely filled.
+    /// This happens when the maker orders have been completely filled,
+    /// or canceled due to the self-trade prevention.
     pub(super) fn remove_orders_and_quotations(
         pair_id: TradingPairId,
         price: T::Price,
+        side: Side,
         fulfilled_orders: Vec<(T::AccountId, OrderId)>,
     ) {
         debug!(
             "[remove_orders_and_quotations] These fulfilled orders will be removed: {:?}",
             fulfilled_orders
         );
-        for (who, order_idx) in fulfilled_orders.iter() {
-            <OrderInfoOf<T>>::remove(who, order_idx);
+        for order_key in fulfilled_orders.iter() {
+            Self::remove_open_order(&order_key.0, pair_id, order_key.1);
+            Self::unlink_quotation(pair_id, price, order_key);
         }
 
-        <QuotationsOf<T>>::mutate(pair_id, price, |quotations| {
-            quotations.retain(|i| !fulfilled_orders.contains(i));
-        });
+        Self::try_remove_price_level(pair_id, price, side);
     }
 
     /// Removes the quotation only.
@@ -155,24 +215,27 @@ impl<T: Trait> Module<T> {
     pub(super) fn remove_quotation(
         pair_id: TradingPairId,
         price: T::Price,
+        side: Side,
         order_key: (T::AccountId, OrderId),
     ) {
-        <QuotationsOf<T>>::mutate(pair_id, price, |quotations| {
-            if let Some(idx) = quotations.iter().position(|i| i == &order_key) {
-                // NOTE: Can't use swap_remove since the original order must be preserved.
-                let _removed = quotations.remove(idx);
-                debug!(
-                    "[remove_quotation] (who, order_index): {:?}, removed order: {:?}",
-                    order_key, _removed
-                );
-            }
-        });
+        if Self::unlink_quotation(pair_id, price, &order_key) {
+            debug!(
+                "[remove_quotation] (who, order_index): {:?}, removed from the quotations",
+                order_key
+            );
+        }
+
+        Self::try_remove_price_level(pair_id, price, side);
     }
 
     /// Updates the latest price of a trading pair.
     ///
     /// This happens after an order is executed every time.
-    pub(crate) fn update_latest_price(pair_index: TradingPairId, latest: T::Price) {
+    pub(crate) fn update_latest_price(
+        pair_index: TradingPairId,
+        latest: T::Price,
+        volume: BalanceOf<T>,
+    ) {
         let current_block = <frame_system::Module<T>>::block_number();
 
         <TradingPairInfoOf<T>>::insert(
@@ -182,5 +245,54 @@ impl<T: Trait> Module<T> {
                 last_updated: current_block,
             },
         );
+
+        Self::update_klines(pair_index, latest, volume, current_block);
+        Self::update_triggering_range(pair_index, latest);
+    }
+
+    /// Aggregates an execution into the klines of each interval.
+    fn update_klines(
+        pair_index: TradingPairId,
+        price: T::Price,
+        volume: BalanceOf<T>,
+        now: T::BlockNumber,
+    ) {
+        for interval in Self::kline_intervals() {
+            if interval.is_zero() {
+                continue;
+            }
+            let start_at = now - now % interval;
+            <KlinesOf<T>>::mutate(
+                (pair_index, interval),
+                Self::kline_slot(start_at, interval),
+                |kline| match kline {
+                    Some(kline) if kline.start_at == start_at => kline.update(price, volume),
+                    _ => *kline = Some(Kline::new(start_at, price, volume)),
+                },
+            );
+        }
+    }
+
+    /// Returns the slot in `KlinesOf` of the kline starting at `start_at`.
+    pub(crate) fn kline_slot(start_at: T::BlockNumber, interval: T::BlockNumber) -> u32 {
+        (start_at / interval).saturated_into::<u32>() % MAX_KLINES
+    }
+}
+
+/// Iterator over the quotations at a price level from the head to the tail.
+///
+/// The next order is looked up before the current one is yielded, so it's fine
+/// to remove the yielded orders while iterating.
+pub(crate) struct Quotations<T: Trait> {
+    next: Option<(T::AccountId, OrderId)>,
+}
+
+impl<T: Trait> Iterator for Quotations<T> {
+    type Item = (T::AccountId, OrderId);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = <QuotationNodeOf<T>>::get(&current.0, current.1).and_then(|node| node.next);
+        Some(current)
     }
 }
@@ -0,0 +1,239 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module checks the consistency between the order book, the handicap
+//! and the reserved balances of Spot.
+
+use super::*;
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{traits::Saturating, RuntimeDebug};
+use sp_std::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
+use xpallet_assets::AssetType;
+
+/// An invariant of Spot that is violated.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum IntegrityViolation<AccountId, Balance, Price> {
+    /// The price level is not indexed in `PriceLevelsOf` or has no quotations. [pair_id, price]
+    InconsistentPriceLevel(TradingPairId, Price),
+    /// The links of the quotations at the price level are broken. [pair_id, price]
+    BrokenQuotations(TradingPairId, Price),
+    /// The quotation points to an order which is not open at the price level.
+    /// [pair_id, price, who, order_id]
+    DanglingQuotation(TradingPairId, Price, AccountId, OrderId),
+    /// The open order is absent from the quotations. [who, order_id]
+    MissingQuotation(AccountId, OrderId),
+    /// The open order is absent from the open orders of the submitter. [who, order_id]
+    UnindexedOrder(AccountId, OrderId),
+    /// The reserved balance differs from the sum of reserves of the open orders.
+    /// [who, asset_id, expected, actual]
+    ReservedMismatch(AccountId, AssetId, Balance, Balance),
+    /// The handicap differs from the best price of the open orders.
+    /// [pair_id, side, expected, actual]
+    HandicapMismatch(TradingPairId, Side, Option<Price>, Option<Price>),
+}
+
+pub type IntegrityViolationOf<T> =
+    IntegrityViolation<<T as frame_system::Trait>::AccountId, BalanceOf<T>, <T as Trait>::Price>;
+
+impl<T: Trait> Module<T> {
+    /// Returns all the violated invariants of Spot.
+    ///
+    /// This iterates over all the orders and the asset balances, it's meant to be
+    /// used by the tests, the tooling and the RPC only.
+    pub fn integrity_check() -> Vec<IntegrityViolationOf<T>> {
+        let mut violations = Vec::new();
+        let mut foreign_assets = BTreeSet::new();
+
+        for pair_id in 0..Self::trading_pair_count() {
+            if let Some(pair) = Self::trading_pair_of(pair_id) {
+                for asset_id in [pair.base(), pair.quote()].iter() {
+                    if *asset_id != T::NativeAssetId::get() {
+                        foreign_assets.insert(*asset_id);
+                    }
+                }
+                Self::check_order_book(&pair, &mut violations);
+            }
+        }
+
+        Self::check_reserves(&foreign_assets, &mut violations);
+
+        violations
+    }
+
+    /// Checks the price levels, the quotations and the handicap of a trading pair.
+    fn check_order_book(pair: &TradingPairProfile, violations: &mut Vec<IntegrityViolationOf<T>>) {
+        let bids = Self::price_levels_of(pair.id, Side::Buy);
+        let asks = Self::price_levels_of(pair.id, Side::Sell);
+
+        for (price, _) in <QuotationQueueOf<T>>::iter_prefix(pair.id) {
+            if bids.binary_search(&price).is_err() && asks.binary_search(&price).is_err() {
+                violations.push(IntegrityViolation::InconsistentPriceLevel(pair.id, price));
+            }
+        }
+
+        for (side, levels) in [(Side::Buy, &bids), (Side::Sell, &asks)].iter() {
+            for &price in levels.iter() {
+                if !Self::has_quotations(pair.id, price) {
+                    violations.push(IntegrityViolation::InconsistentPriceLevel(pair.id, price));
+                    continue;
+                }
+                Self::check_quotations(pair.id, price, *side, violations);
+            }
+        }
+
+        let handicap = Self::handicap_of(pair.id);
+        let best_prices = [
+            (Side::Buy, bids.last().copied(), handicap.highest_bid),
+            (Side::Sell, asks.first().copied(), handicap.lowest_ask),
+        ];
+        for &(side, expected, actual) in best_prices.iter() {
+            if expected != actual {
+                violations.push(IntegrityViolation::HandicapMismatch(
+                    pair.id, side, expected, actual,
+                ));
+            }
+        }
+    }
+
+    /// Walks through the quotations at `price` and checks the links as well as the orders.
+    fn check_quotations(
+        pair_id: TradingPairId,
+        price: T::Price,
+        side: Side,
+        violations: &mut Vec<IntegrityViolationOf<T>>,
+    ) {
+        let queue = match Self::quotation_queue_of(pair_id, price) {
+            Some(queue) => queue,
+            None => return,
+        };
+
+        let mut visited = BTreeSet::new();
+        let mut prev = None;
+        let mut next = Some(queue.head);
+
+        while let Some((who, order_id)) = next {
+            let node = match Self::quotation_node_of(&who, order_id) {
+                Some(node) if node.prev == prev && visited.insert((who.clone(), order_id)) => node,
+                _ => {
+                    violations.push(IntegrityViolation::BrokenQuotations(pair_id, price));
+                    return;
+                }
+            };
+
+            let is_open = Self::order_info_of(&who, order_id)
+                .map(|order| {
+                    order.pair_id() == pair_id
+                        && order.price() == price
+                        && order.side() == side
+                        && !order.is_fulfilled()
+                        && !order.is_canceled()
+                })
+                .unwrap_or(false);
+            if !is_open {
+                violations.push(IntegrityViolation::DanglingQuotation(
+                    pair_id,
+                    price,
+                    who.clone(),
+                    order_id,
+                ));
+            }
+
+            prev = Some((who, order_id));
+            next = node.next;
+        }
+
+        if prev.as_ref() != Some(&queue.tail) {
+            violations.push(IntegrityViolation::BrokenQuotations(pair_id, price));
+        }
+    }
+
+    /// Checks the reserved balances against the reserves of the open orders,
+    /// the conditional orders and the liquidity pools.
+    fn check_reserves(
+        foreign_assets: &BTreeSet<AssetId>,
+        violations: &mut Vec<IntegrityViolationOf<T>>,
+    ) {
+        let mut expected = BTreeMap::<(T::AccountId, AssetId), BalanceOf<T>>::new();
+
+        for (who, order_id, order) in <OrderInfoOf<T>>::iter() {
+            if !<QuotationNodeOf<T>>::contains_key(&who, order_id) {
+                violations.push(IntegrityViolation::MissingQuotation(who.clone(), order_id));
+            }
+            if !<OpenOrdersOf<T>>::contains_key((who.clone(), order.pair_id()), order_id) {
+                violations.push(IntegrityViolation::UnindexedOrder(who.clone(), order_id));
+            }
+            if let Some(pair) = Self::trading_pair_of(order.pair_id()) {
+                let asset_id = match order.side() {
+                    Side::Buy => pair.quote(),
+                    Side::Sell => pair.base(),
+                };
+                let reserved = expected.entry((who, asset_id)).or_insert_with(Zero::zero);
+                *reserved = reserved.saturating_add(order.remaining);
+            }
+        }
+
+        for (who, _, order) in <ConditionalOrderInfoOf<T>>::iter() {
+            if let Some(pair) = Self::trading_pair_of(order.pair_id) {
+                let asset_id = match order.side {
+                    Side::Buy => pair.quote(),
+                    Side::Sell => pair.base(),
+                };
+                let reserved = expected.entry((who, asset_id)).or_insert_with(Zero::zero);
+                *reserved = reserved.saturating_add(order.reserved);
+            }
+        }
+
+        for (pair_id, pool) in <LiquidityPoolOf<T>>::iter() {
+            if let Some(pair) = Self::trading_pair_of(pair_id) {
+                let pool_account = Self::pool_account_of(pair_id);
+                for &(asset_id, reserve) in [
+                    (pair.base(), pool.base_reserve),
+                    (pair.quote(), pool.quote_reserve),
+                ]
+                .iter()
+                {
+                    let reserved = expected
+                        .entry((pool_account.clone(), asset_id))
+                        .or_insert_with(Zero::zero);
+                    *reserved = reserved.saturating_add(reserve);
+                }
+            }
+        }
+
+        let mut actual = BTreeMap::<(T::AccountId, AssetId), BalanceOf<T>>::new();
+        for (who, reserved) in <NativeReserves<T>>::iter() {
+            actual.insert((who, T::NativeAssetId::get()), reserved);
+        }
+        for (who, asset_id, balances) in <xpallet_assets::AssetBalance<T>>::iter() {
+            if foreign_assets.contains(&asset_id) {
+                if let Some(&reserved) = balances.get(&AssetType::ReservedDexSpot) {
+                    actual.insert((who, asset_id), reserved);
+                }
+            }
+        }
+
+        let keys = expected
+            .keys()
+            .chain(actual.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        for (who, asset_id) in keys {
+            let expected = expected
+                .get(&(who.clone(), asset_id))
+                .copied()
+                .unwrap_or_else(Zero::zero);
+            let actual = actual
+                .get(&(who.clone(), asset_id))
+                .copied()
+                .unwrap_or_else(Zero::zero);
+            if expected != actual {
+                violations.push(IntegrityViolation::ReservedMismatch(
+                    who, asset_id, expected, actual,
+                ));
+            }
+        }
+    }
+}
@@ -6,6 +6,9 @@
 #![allow(clippy::type_complexity)]
 
 mod execution;
+mod integrity;
+mod migration;
+mod mining;
 mod rpc;
 mod types;
 pub mod weights;
@@ -19,32 +22,81 @@ mod tests;
 
 use codec::Codec;
 
-use sp_runtime::traits::{
-    AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member, SaturatedConversion, StaticLookup,
-    Zero,
+use sp_runtime::{
+    traits::{
+        AtLeast32BitUnsigned, MaybeSerializeDeserialize, Member, One, SaturatedConversion,
+        StaticLookup, Zero,
+    },
+    Permill,
 };
 use sp_std::prelude::*;
 use sp_std::{cmp, fmt::Debug};
 
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage,
-    dispatch::{DispatchError, DispatchResult},
+    dispatch::{DispatchError, DispatchResult, DispatchResultWithPostInfo},
     ensure,
     traits::{Currency, Get, ReservableCurrency},
+    weights::Weight,
     Parameter,
 };
 use frame_system::{ensure_root, ensure_signed};
 
 use chainx_primitives::AssetId;
-use xp_logging::info;
+use orml_utilities::with_transaction_result;
+use xp_logging::{error, info};
+use xp_mining_common::ZeroMiningWeightError;
+use xp_mining_staking::MiningPower;
 use xpallet_assets::AssetErr;
+use xpallet_support::traits::TreasuryAccount;
 
+pub use self::integrity::*;
 pub use self::rpc::*;
 pub use self::types::*;
 pub use self::weights::WeightInfo;
 
-/// Maximum of backlog orders.
-const MAX_BACKLOG_ORDER: usize = 1000;
+/// Maximum of expired orders that can be canceled in one block.
+const MAX_EXPIRATIONS_PER_BLOCK: usize = 100;
+
+/// Maximum of orders that can be put or canceled in one batch call.
+const MAX_ORDERS_PER_BATCH: usize = 100;
+
+/// Maximum of the maker orders that can be matched against in one call, the unfilled
+/// remainder of the taker order is canceled once it's reached.
+const MAX_MAKERS_PER_ORDER: u32 = 50;
+
+/// Maximum of the recent executions kept in the trading history of a trading pair.
+const MAX_TRADE_HISTORY: TradingHistoryIndex = 1000;
+
+/// Maximum of the recent klines kept for each interval of a trading pair.
+const MAX_KLINES: u32 = 1000;
+
+/// Maximum of the kline intervals.
+const MAX_KLINE_INTERVALS: usize = 8;
+
+/// Maximum of the assets in the path of a swap routed through the order books.
+const MAX_SWAP_PATH_LEN: usize = 4;
+
+/// Maximum of the triggered conditional orders that can be activated in one block.
+const MAX_ACTIVATIONS_PER_BLOCK: u32 = 100;
+
+/// Maximum of the conditional orders at a trigger price of a trading pair.
+const MAX_CONDITIONAL_ORDERS_PER_PRICE: usize = 100;
+
+/// Maximum of the trigger prices whose conditional orders can be collected in one block.
+const MAX_COLLECTIONS_PER_BLOCK: u32 = 100;
+
+/// Maximum of the resting orders whose maker mining power can be refreshed in one block.
+const MAX_MINING_REFRESHES_PER_BLOCK: u32 = 100;
+
+/// Maximum of the orders that can be canceled on delisting the trading pairs in one block.
+const MAX_DELISTING_CANCELS_PER_BLOCK: usize = 100;
+
+/// Maximum of the non-empty price levels on each side of a trading pair.
+///
+/// `PriceLevelsOf` is read and rewritten as a whole whenever a price level is
+/// indexed or removed, which is bounded by this cap.
+const MAX_PRICE_LEVELS_PER_SIDE: usize = 1000;
 
 /// The maximum ticks that a price can deviated from the handicap.
 ///
@@ -53,10 +105,11 @@ const MAX_BACKLOG_ORDER: usize = 1000;
 /// which resulted in the endless loop when matching the orders.
 /// Now we use the fixed size of ticks to restrict the quote.
 ///
-/// Currently we match the order by trying one tick at a time, if the
-/// order prices have a large gap, the matching logic can take much
-/// more time than the Block time to finish.
-const DEFAULT_FLUCTUATION: u32 = 100;
+/// The matching jumps from one non-empty price level to the next one
+/// via `PriceLevelsOf`, so the cost of matching no longer depends on
+/// this value, it merely restricts how far a quote can go from the handicap,
+/// hence the cap is lifted to 1000 ticks.
+const DEFAULT_FLUCTUATION: u32 = 1000;
 
 pub type BalanceOf<T> = <<T as xpallet_assets::Trait>::Currency as Currency<
     <T as frame_system::Trait>::AccountId,
@@ -72,6 +125,33 @@ pub type OrderInfo<T> = Order<
 
 pub type HandicapInfo<T> = Handicap<<T as Trait>::Price>;
 
+pub type ConditionalOrderInfo<T> = ConditionalOrder<
+    TradingPairId,
+    <T as frame_system::Trait>::AccountId,
+    BalanceOf<T>,
+    <T as Trait>::Price,
+    <T as frame_system::Trait>::BlockNumber,
+>;
+
+pub type OrderExecutedInfoOf<T> = OrderExecutedInfo<
+    <T as frame_system::Trait>::AccountId,
+    BalanceOf<T>,
+    <T as frame_system::Trait>::BlockNumber,
+    <T as Trait>::Price,
+>;
+
+pub type KlineOf<T> =
+    Kline<<T as Trait>::Price, BalanceOf<T>, <T as frame_system::Trait>::BlockNumber>;
+
+pub type LiquidityPoolInfo<T> = LiquidityPool<BalanceOf<T>>;
+
+pub type OrderRequestOf<T> = OrderRequest<
+    TradingPairId,
+    BalanceOf<T>,
+    <T as Trait>::Price,
+    <T as frame_system::Trait>::BlockNumber,
+>;
+
 pub trait Trait: xpallet_assets::Trait {
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
@@ -102,6 +182,21 @@ decl_storage! {
         pub TradingPairOf get(fn trading_pair_of):
             map hasher(twox_64_concat) TradingPairId => Option<TradingPairProfile>;
 
+        /// The maker and taker fee rates of a trading pair.
+        pub TradingFeeOf get(fn trading_fee_of):
+            map hasher(twox_64_concat) TradingPairId => TradingFee;
+
+        /// The restrictions on the amount of the orders of a trading pair.
+        pub OrderSizeLimitOf get(fn order_size_limit_of):
+            map hasher(twox_64_concat) TradingPairId => OrderSizeLimit<BalanceOf<T>>;
+
+        /// The default self-trade prevention policy of the orders of a trading pair.
+        pub SelfTradePreventionOf get(fn self_trade_prevention_of):
+            map hasher(twox_64_concat) TradingPairId => SelfTradePrevention;
+
+        /// The account receiving the trading fees, the treasury account is used if not set.
+        pub FeeDestination get(fn fee_destination): Option<T::AccountId>;
+
         /// (latest price, last update height) of trading pair
         pub TradingPairInfoOf get(fn trading_pair_info_of):
             map hasher(twox_64_concat) TradingPairId => Option<TradingPairInfo<T::Price, T::BlockNumber>>;
@@ -110,6 +205,21 @@ decl_storage! {
         pub TradingHistoryIndexOf get(fn trading_history_index_of):
             map hasher(twox_64_concat) TradingPairId => TradingHistoryIndex;
 
+        /// The recent executions of a trading pair, organized as a ring buffer of
+        /// `MAX_TRADE_HISTORY` slots indexed by `trading_history_idx % MAX_TRADE_HISTORY`.
+        pub TradeHistoryOf get(fn trade_history_of):
+            double_map hasher(twox_64_concat) TradingPairId, hasher(twox_64_concat) TradingHistoryIndex
+            => Option<OrderExecutedInfoOf<T>>;
+
+        /// The intervals in blocks of the klines maintained for each trading pair.
+        pub KlineIntervals get(fn kline_intervals) config(): Vec<T::BlockNumber>;
+
+        /// The recent klines of a trading pair given the interval, organized as a ring buffer
+        /// of `MAX_KLINES` slots indexed by `(start_at / interval) % MAX_KLINES`.
+        pub KlinesOf get(fn klines_of):
+            double_map hasher(twox_64_concat) (TradingPairId, T::BlockNumber), hasher(twox_64_concat) u32
+            => Option<KlineOf<T>>;
+
         /// Total orders made by an account.
         pub OrderCountOf get(fn order_count_of):
             map hasher(twox_64_concat) T::AccountId => OrderId;
@@ -119,23 +229,138 @@ decl_storage! {
             double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) OrderId
             => Option<OrderInfo<T>>;
 
-        /// All the accounts and the order number given the trading pair ID and price.
-        pub QuotationsOf get(fn quotations_of):
+        /// The open orders of an account given the account ID and trading pair ID.
+        pub OpenOrdersOf get(fn open_orders_of):
+            double_map hasher(twox_64_concat) (T::AccountId, TradingPairId), hasher(twox_64_concat) OrderId
+            => ();
+
+        /// The first and the last order of the quotations given the trading pair ID and price.
+        pub QuotationQueueOf get(fn quotation_queue_of):
             double_map hasher(twox_64_concat) TradingPairId, hasher(twox_64_concat) T::Price
-            => Vec<(T::AccountId, OrderId)>;
+            => Option<QuotationQueue<T::AccountId>>;
+
+        /// The adjacent orders of an order in the quotations at its price level.
+        pub QuotationNodeOf get(fn quotation_node_of):
+            double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) OrderId
+            => Option<QuotationNode<T::AccountId>>;
+
+        /// All the non-empty price levels in ascending order given the trading pair ID and side.
+        pub PriceLevelsOf get(fn price_levels_of):
+            double_map hasher(twox_64_concat) TradingPairId, hasher(twox_64_concat) Side
+            => Vec<T::Price>;
 
         /// TradingPairId => (highest_bid, lowest_ask)
+        ///
+        /// Derived from the best non-empty price levels in `PriceLevelsOf`.
         pub HandicapOf get(fn handicap_of):
             map hasher(twox_64_concat) TradingPairId => HandicapInfo<T>;
 
+        /// Total conditional orders made by an account.
+        pub ConditionalOrderCountOf get(fn conditional_order_count_of):
+            map hasher(twox_64_concat) T::AccountId => ConditionalOrderId;
+
+        /// Details of a pending conditional order given the account ID and conditional order ID.
+        pub ConditionalOrderInfoOf get(fn conditional_order_info_of):
+            double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) ConditionalOrderId
+            => Option<ConditionalOrderInfo<T>>;
+
+        /// The trigger book, i.e., all the accounts and the conditional order number
+        /// given the trading pair ID, trigger direction and trigger price.
+        pub TriggersOf get(fn triggers_of):
+            double_map hasher(twox_64_concat) (TradingPairId, TriggerDirection), hasher(twox_64_concat) T::Price
+            => Vec<(T::AccountId, ConditionalOrderId)>;
+
+        /// All the trigger prices in ascending order given the trading pair ID and trigger direction.
+        pub TriggerPricesOf get(fn trigger_prices_of):
+            double_map hasher(twox_64_concat) TradingPairId, hasher(twox_64_concat) TriggerDirection
+            => Vec<T::Price>;
+
+        /// The lowest and the highest execution prices of a trading pair since the
+        /// triggered conditional orders were collected last time.
+        pub TriggeringRangeOf get(fn triggering_range_of):
+            map hasher(twox_64_concat) TradingPairId => Option<(T::Price, T::Price)>;
+
+        /// The triggered conditional orders waiting for being activated, each item holds
+        /// the conditional orders of a trigger price in order.
+        pub TriggeredQueue get(fn triggered_queue):
+            map hasher(twox_64_concat) u64 => Vec<(T::AccountId, ConditionalOrderId)>;
+
+        /// The index of the first item in `TriggeredQueue`.
+        pub TriggeredQueueHead get(fn triggered_queue_head): u64;
+
+        /// The index at which the next item will be pushed into `TriggeredQueue`.
+        pub TriggeredQueueTail get(fn triggered_queue_tail): u64;
+
+        /// The open orders that will expire at the given block number.
+        pub ExpiringOrdersAt get(fn expiring_orders_at):
+            map hasher(twox_64_concat) T::BlockNumber => Vec<(T::AccountId, OrderId)>;
+
+        /// The block number at which point the trading pair is going to be delisted.
+        pub DelistingAt get(fn delisting_at):
+            map hasher(twox_64_concat) TradingPairId => Option<T::BlockNumber>;
+
+        /// The trading pairs that will be delisted at the given block number.
+        pub DelistingPairsAt get(fn delisting_pairs_at):
+            map hasher(twox_64_concat) T::BlockNumber => Vec<TradingPairId>;
+
+        /// The block number at which point the trading pair was delisted.
+        pub DelistedAt get(fn delisted_at):
+            map hasher(twox_64_concat) TradingPairId => Option<T::BlockNumber>;
+
+        /// The constant-product liquidity pool given the trading pair ID.
+        pub LiquidityPoolOf get(fn liquidity_pool_of):
+            map hasher(twox_64_concat) TradingPairId => Option<LiquidityPoolInfo<T>>;
+
+        /// The liquidity mining rule of the makers given the trading pair ID.
+        pub MakerMiningRuleOf get(fn maker_mining_rule_of):
+            map hasher(twox_64_concat) TradingPairId => Option<MakerMiningRule>;
+
+        /// The mining weight ledger of each maker.
+        pub MakerMiningLedgers get(fn maker_mining_ledgers):
+            map hasher(twox_64_concat) T::AccountId => MakerMiningLedger<T::BlockNumber>;
+
+        /// The mining weight ledger of all the makers.
+        pub TotalMakerMiningLedger get(fn total_maker_mining_ledger):
+            MakerMiningLedger<T::BlockNumber>;
+
+        /// The mining power of a resting order counted in the ledger of its maker,
+        /// which is zero if the order is out of the band.
+        pub OrderMiningPowerOf get(fn order_mining_power_of):
+            double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) OrderId
+            => MiningPower;
+
+        /// The price ranges of the levels which moved into or out of the band, each item
+        /// holds (pair_id, side, lowest_price, highest_price) of a handicap change.
+        pub MiningRefreshQueue get(fn mining_refresh_queue):
+            map hasher(twox_64_concat) u64 => Option<(TradingPairId, Side, T::Price, T::Price)>;
+
+        /// The index of the first item in `MiningRefreshQueue`.
+        pub MiningRefreshQueueHead get(fn mining_refresh_queue_head): u64;
+
+        /// The index at which the next item will be pushed into `MiningRefreshQueue`.
+        pub MiningRefreshQueueTail get(fn mining_refresh_queue_tail): u64;
+
+        /// The price and the order from which the refresh of the first item in
+        /// `MiningRefreshQueue` resumes.
+        pub MiningRefreshCursor get(fn mining_refresh_cursor):
+            Option<(T::Price, (T::AccountId, OrderId))>;
+
         /// The map of trading pair ID to the price fluctuation. Use with caution!
         pub PriceFluctuationOf get(fn price_fluctuation_of):
             map hasher(twox_64_concat) TradingPairId => PriceFluctuation = DEFAULT_FLUCTUATION;
+
+        /// The storage version of Spot, the migrations are applied on the runtime upgrade.
+        pub StorageVersion get(fn storage_version)
+            build(|_: &GenesisConfig<T>| Releases::V2_0_0): Releases;
     }
 
     add_extra_genesis {
         config(trading_pairs): Vec<(AssetId, AssetId, u32, u32, T::Price, bool)>;
         build(|config| {
+            assert!(
+                Module::<T>::is_valid_kline_intervals(&config.kline_intervals),
+                "Kline intervals must be non-zero and distinct"
+            );
             for (base, quote, pip_decimals, tick_decimals, price, tradable) in config.trading_pairs.iter() {
                 Module::<T>::apply_add_trading_pair(
                     CurrencyPair::new(*base, *quote),
@@ -167,12 +392,50 @@ decl_event!(
         OrderExecuted(OrderExecutedInfo<AccountId, Balance, BlockNumber, Price>),
         /// There is an update to the order due to it gets canceled. [order_info]
         CanceledOrderUpdated(Order<TradingPairId, AccountId, Balance, Price, BlockNumber>),
+        /// A new conditional order was created. [conditional_order_info]
+        NewConditionalOrder(ConditionalOrder<TradingPairId, AccountId, Balance, Price, BlockNumber>),
+        /// The conditional order has been triggered and will be put as a limit order. [conditional_order_info]
+        ConditionalOrderTriggered(ConditionalOrder<TradingPairId, AccountId, Balance, Price, BlockNumber>),
+        /// The conditional order has been canceled. [conditional_order_info]
+        ConditionalOrderCanceled(ConditionalOrder<TradingPairId, AccountId, Balance, Price, BlockNumber>),
         /// A new trading pair is added. [pair_profile]
         TradingPairAdded(TradingPairProfile),
         /// Trading pair profile has been updated. [pair_profile]
         TradingPairUpdated(TradingPairProfile),
+        /// The trading pair is going to be delisted, no new orders are accepted. [pair_id, delist_at]
+        TradingPairDelistingScheduled(TradingPairId, BlockNumber),
+        /// All the orders of the trading pair were canceled and the order book was cleared. [pair_id]
+        TradingPairDelisted(TradingPairId),
+        /// The delisted trading pair was listed again. [pair_profile]
+        TradingPairRelisted(TradingPairProfile),
         /// Price fluctuation of trading pair has been updated. [pair_id, price_fluctuation]
         PriceFluctuationUpdated(TradingPairId, PriceFluctuation),
+        /// Trading fee rates of trading pair has been updated. [pair_id, trading_fee]
+        TradingFeeUpdated(TradingPairId, TradingFee),
+        /// The account receiving the trading fees has been updated. [fee_destination]
+        FeeDestinationUpdated(Option<AccountId>),
+        /// The order size limit of trading pair has been updated. [pair_id, order_size_limit]
+        OrderSizeLimitUpdated(TradingPairId, OrderSizeLimit<Balance>),
+        /// The kline intervals have been updated. [intervals]
+        KlineIntervalsUpdated(Vec<BlockNumber>),
+        /// Default self-trade prevention policy of trading pair has been updated. [pair_id, policy]
+        SelfTradePreventionUpdated(TradingPairId, SelfTradePrevention),
+        /// A liquidity pool was created for the trading pair. [pair_id, lp_asset_id, fee]
+        LiquidityPoolCreated(TradingPairId, AssetId, Permill),
+        /// Some liquidity was added to the pool. [who, pair_id, base_amount, quote_amount, shares]
+        LiquidityAdded(AccountId, TradingPairId, Balance, Balance, Balance),
+        /// Some liquidity was removed from the pool. [who, pair_id, base_amount, quote_amount, shares]
+        LiquidityRemoved(AccountId, TradingPairId, Balance, Balance, Balance),
+        /// A swap against the pool was executed. [who, pair_id, side, amount_in, amount_out]
+        Swapped(AccountId, TradingPairId, Side, Balance, Balance),
+        /// A swap was routed through the order books. [who, path, amount_in, amount_out]
+        SwappedExactIn(AccountId, Vec<AssetId>, Balance, Balance),
+        /// The maker mining rule of trading pair has been updated. [pair_id, rule]
+        MakerMiningRuleUpdated(TradingPairId, Option<MakerMiningRule>),
+        /// Some reward was issued to the DEX reward pot. [reward_pot, amount]
+        MakerRewardMinted(AccountId, Balance),
+        /// A maker claimed the reward from the DEX reward pot. [who, amount]
+        MakerRewardClaimed(AccountId, Balance),
     }
 );
 
@@ -199,20 +462,66 @@ decl_error! {
         TradingPairUntradable,
         /// The trading pair does not exist.
         NonexistentTradingPair,
+        /// The trading pair is going to be delisted, no new orders are accepted.
+        TradingPairDelisting,
+        /// The trading pair has been delisted.
+        TradingPairDelisted,
+        /// Only the delisted trading pair can be re-listed.
+        TradingPairNotDelisted,
+        /// The delisting block must be later than the current block.
+        InvalidDelistingBlock,
         /// tick_decimals can not less than the one of pair.
         InvalidTickdecimals,
         /// Price volatility must be less 100.
         InvalidPriceVolatility,
         /// The trading pair already exists.
         TradingPairAlreadyExists,
-        /// Too many orders for the same price.
-        TooManyBacklogOrders,
         /// Can not retrieve the asset info given the trading pair.
         InvalidTradingPairAsset,
         /// Only the orders with ZeroFill or PartialFill can be canceled.
         CancelOrderNotAllowed,
         /// Can not find the order given the order index.
         InvalidOrderId,
+        /// The time in force is not supported by the order type.
+        InvalidTimeInForce,
+        /// The post-only order would be matched immediately.
+        PostOnlyOrderWouldMatch,
+        /// The expiry of an order must be later than the current block.
+        InvalidExpiry,
+        /// The batch is empty or contains too many orders.
+        InvalidBatchSize,
+        /// Neither the account nor the trading pair is specified.
+        InvalidCancelTarget,
+        /// The kline interval is zero or there are too many kline intervals.
+        InvalidKlineInterval,
+        /// The trigger price is invalid or has been crossed already.
+        InvalidTriggerPrice,
+        /// The amount of order is less than the minimum amount of trading pair.
+        AmountTooSmall,
+        /// The amount of order is not a multiple of the lot size of trading pair.
+        InvalidLotSize,
+        /// Can not find the conditional order given the conditional order index.
+        InvalidConditionalOrderId,
+        /// There are too many conditional orders at the trigger price.
+        TooManyConditionalOrders,
+        /// There are too many non-empty price levels on the side of the trading pair.
+        TooManyPriceLevels,
+        /// The liquidity pool of the trading pair already exists.
+        LiquidityPoolAlreadyExists,
+        /// The trading pair has no liquidity pool.
+        NonexistentLiquidityPool,
+        /// The LP asset is invalid, already issued or used by another pool.
+        InvalidLpAsset,
+        /// The pool or the order book has no liquidity, or the amount is too small to make any change.
+        InsufficientLiquidity,
+        /// The result of the liquidity operation or swap is worse than the given limit.
+        SlippageExceeded,
+        /// The amount overflows when calculating the liquidity operation or swap.
+        AmountOverflow,
+        /// The swap path is too short or too long, or two adjacent assets have no trading pair.
+        InvalidSwapPath,
+        /// The maker has no mining weight to claim the reward.
+        ZeroMiningWeight,
         /// Error from assets module.
         AssetError,
     }
@@ -224,6 +533,12 @@ impl<T: Trait> From<AssetErr> for Error<T> {
     }
 }
 
+impl<T: Trait> From<ZeroMiningWeightError> for Error<T> {
+    fn from(_: ZeroMiningWeightError) -> Self {
+        Self::ZeroMiningWeight
+    }
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 
@@ -231,36 +546,91 @@ decl_module! {
 
         fn deposit_event() = default;
 
-        #[weight = <T as Trait>::WeightInfo::put_order()]
+        fn on_runtime_upgrade() -> Weight {
+            migration::on_runtime_upgrade::<T>()
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            Self::cancel_expired_orders(now)
+                .saturating_add(Self::delist_trading_pairs(now))
+                .saturating_add(Self::collect_triggered_orders())
+                .saturating_add(Self::activate_triggered_orders())
+                .saturating_add(Self::refresh_queued_mining_power())
+        }
+
+        /// Put a new order.
+        ///
+        /// For the limit orders, `amount` is measured in the base currency.
+        ///
+        /// The market orders are filled against the book immediately and never rest
+        /// in the order book, the unfilled remainder will be refunded. The `amount` of
+        /// a market buy is measured in the quote currency, a market sell in the base
+        /// currency. `price` serves as the slippage bound of a market order, i.e., the
+        /// highest price a buy can pay or the lowest price a sell can accept, which is
+        /// restricted by the price fluctuation relative to the handicap as usual.
+        ///
+        /// `time_in_force` decides how long the order remains active, the market orders
+        /// can only be `GoodTillCancel` or `ImmediateOrCancel` which are identical for them.
+        ///
+        /// The order still open at block `expire_at` will be canceled automatically.
+        ///
+        /// `self_trade_prevention` decides what to do when the order would be matched
+        /// with an order of the same submitter, the default policy of the trading pair
+        /// is used if it's `None`.
+        ///
+        /// An order is matched against at most `MAX_MAKERS_PER_ORDER` maker orders,
+        /// the weight of the maker orders not touched is refunded.
+        #[weight = <T as Trait>::WeightInfo::put_order(MAX_MAKERS_PER_ORDER)]
         pub fn put_order(
             origin,
             #[compact] pair_id: TradingPairId,
             order_type: OrderType,
             side: Side,
             #[compact] amount: BalanceOf<T>,
-            #[compact] price: T::Price
-        ) {
+            #[compact] price: T::Price,
+            time_in_force: TimeInForce,
+            expire_at: Option<T::BlockNumber>,
+            self_trade_prevention: Option<SelfTradePrevention>
+        ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
+            let mut makers = 0;
+            Self::do_put_order(
+                who,
+                OrderRequest {
+                    pair_id,
+                    order_type,
+                    side,
+                    amount,
+                    price,
+                    time_in_force,
+                    expire_at,
+                    self_trade_prevention,
+                },
+                &mut makers,
+            )?;
+            Ok(Some(<T as Trait>::WeightInfo::put_order(makers)).into())
+        }
 
-            ensure!(!price.is_zero(), Error::<T>::InvalidPrice);
-            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
-            ensure!(order_type == OrderType::Limit, Error::<T>::InvalidOrderType);
-
-            let pair = Self::trading_pair(pair_id)?;
-
-            ensure!(pair.tradable, Error::<T>::TradingPairUntradable);
-            ensure!(pair.is_valid_price(price), Error::<T>::InvalidPrice);
-
-            Self::is_valid_quote(price, side, pair_id)?;
-            Self::has_too_many_backlog_orders(pair_id, price, side)?;
-
-            // Reserve the token according to the order side.
-            let (reserve_asset, reserve_amount) = match side {
-                Side::Buy => (pair.quote(), Self::convert_base_to_quote(amount, price, &pair)?),
-                Side::Sell => (pair.base(), amount)
-            };
-            Self::put_order_reserve(&who, reserve_asset, reserve_amount)?;
-            Self::apply_put_order(who, pair_id, order_type, side, amount, price, reserve_amount)?;
+        /// Put a batch of new orders, either all of them are put or none.
+        ///
+        /// See `put_order` for the details of each order, the orders of a batch are
+        /// matched against at most `MAX_MAKERS_PER_ORDER` maker orders in total.
+        #[weight = <T as Trait>::WeightInfo::put_orders(orders.len() as u32, MAX_MAKERS_PER_ORDER)]
+        pub fn put_orders(origin, orders: Vec<OrderRequestOf<T>>) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                !orders.is_empty() && orders.len() <= MAX_ORDERS_PER_BATCH,
+                Error::<T>::InvalidBatchSize
+            );
+            let n = orders.len() as u32;
+            let mut makers = 0;
+            with_transaction_result(|| {
+                for order in orders {
+                    Self::do_put_order(who.clone(), order, &mut makers)?;
+                }
+                Ok(())
+            })?;
+            Ok(Some(<T as Trait>::WeightInfo::put_orders(n, makers)).into())
         }
 
         #[weight = <T as Trait>::WeightInfo::cancel_order()]
@@ -286,11 +656,255 @@ decl_module! {
             Self::do_cancel_order(&who, pair_id, order_id)?;
         }
 
-        #[weight = <T as Trait>::WeightInfo::set_handicap()]
-        fn set_handicap(origin, #[compact] pair_id: TradingPairId, new: Handicap< T::Price>) {
+        /// Cancel a batch of orders, either all of them are canceled or none.
+        #[weight = <T as Trait>::WeightInfo::cancel_orders(orders.len() as u32)]
+        pub fn cancel_orders(origin, orders: Vec<(TradingPairId, OrderId)>) {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                !orders.is_empty() && orders.len() <= MAX_ORDERS_PER_BATCH,
+                Error::<T>::InvalidBatchSize
+            );
+            with_transaction_result(|| {
+                for (pair_id, order_id) in orders {
+                    Self::do_cancel_order(&who, pair_id, order_id)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        /// Put a conditional order, which is put as a limit order of `amount` at `price`
+        /// once the latest price of the trading pair crosses `trigger_price`.
+        ///
+        /// The asset of the limit order is reserved at once. The triggered orders are
+        /// collected and activated at the beginning of the next block, at most
+        /// `MAX_ACTIVATIONS_PER_BLOCK` orders in one block.
+        ///
+        /// There can be at most `MAX_CONDITIONAL_ORDERS_PER_PRICE` conditional orders
+        /// at a trigger price of the trading pair.
+        #[weight = <T as Trait>::WeightInfo::put_conditional_order()]
+        pub fn put_conditional_order(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            kind: ConditionalOrderKind,
+            side: Side,
+            #[compact] amount: BalanceOf<T>,
+            #[compact] price: T::Price,
+            #[compact] trigger_price: T::Price
+        ) {
+            let who = ensure_signed(origin)?;
+
+            ensure!(!price.is_zero(), Error::<T>::InvalidPrice);
+            ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+
+            let pair = Self::trading_pair(pair_id)?;
+
+            ensure!(pair.tradable, Error::<T>::TradingPairUntradable);
+            ensure!(!Self::is_delisting(pair_id), Error::<T>::TradingPairDelisting);
+            ensure!(pair.is_valid_price(price), Error::<T>::InvalidPrice);
+            ensure!(
+                !trigger_price.is_zero() && pair.is_valid_price(trigger_price),
+                Error::<T>::InvalidTriggerPrice
+            );
+            Self::ensure_valid_amount(pair_id, amount)?;
+
+            Self::apply_put_conditional_order(who, &pair, kind, side, amount, price, trigger_price)?;
+        }
+
+        /// Cancel a conditional order which has not been activated yet.
+        #[weight = <T as Trait>::WeightInfo::cancel_conditional_order()]
+        pub fn cancel_conditional_order(origin, #[compact] order_id: ConditionalOrderId) {
+            let who = ensure_signed(origin)?;
+            Self::apply_cancel_conditional_order(&who, order_id)?;
+        }
+
+        /// Cancel all the open orders of the sender, optionally on the given trading pair.
+        ///
+        /// At most `MAX_ORDERS_PER_BATCH` orders of the sender are canceled in one call,
+        /// the rest can be canceled by calling again. The orders on the untradable trading
+        /// pairs are left to `force_cancel_all_orders`.
+        #[weight = <T as Trait>::WeightInfo::cancel_all_orders(MAX_ORDERS_PER_BATCH as u32)]
+        pub fn cancel_all_orders(origin, pair_id: Option<TradingPairId>) {
+            let who = ensure_signed(origin)?;
+            if let Some(pair_id) = pair_id {
+                let pair = Self::trading_pair(pair_id)?;
+                ensure!(pair.tradable, Error::<T>::TradingPairUntradable);
+            }
+            Self::apply_cancel_all_orders(&who, pair_id, false)?;
+        }
+
+        /// Force cancel all the open orders of an account or a trading pair.
+        ///
+        /// Unlike `cancel_all_orders`, the orders on the untradable trading pairs are
+        /// canceled too, which is used to refund the orders on the delisted trading pairs.
+        /// If `who` is `None`, the orders of all accounts on `pair_id` are canceled.
+        ///
+        /// At most `MAX_ORDERS_PER_BATCH` orders are canceled in one call.
+        #[weight = <T as Trait>::WeightInfo::force_cancel_all_orders(MAX_ORDERS_PER_BATCH as u32)]
+        fn force_cancel_all_orders(
+            origin,
+            who: Option<<T::Lookup as StaticLookup>::Source>,
+            pair_id: Option<TradingPairId>
+        ) {
+            ensure_root(origin)?;
+            match (who, pair_id) {
+                (Some(who), pair_id) => {
+                    let who = T::Lookup::lookup(who)?;
+                    Self::apply_cancel_all_orders(&who, pair_id, true)?;
+                }
+                (None, Some(pair_id)) => Self::apply_cancel_pair_orders(pair_id)?,
+                (None, None) => return Err(Error::<T>::InvalidCancelTarget.into()),
+            }
+        }
+
+        /// Cancel an order and put a new one with the new `amount` and `price` atomically.
+        ///
+        /// The new order inherits the rest properties of the canceled one, e.g., the side
+        /// and time in force, the reserved asset is refunded before the new reservation.
+        #[weight = <T as Trait>::WeightInfo::replace_order(MAX_MAKERS_PER_ORDER)]
+        pub fn replace_order(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] order_id: OrderId,
+            #[compact] amount: BalanceOf<T>,
+            #[compact] price: T::Price
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let order = Self::get_order(&who, order_id)?;
+            ensure!(order.pair_id() == pair_id, Error::<T>::InvalidOrderId);
+            let mut makers = 0;
+            with_transaction_result(|| {
+                Self::do_cancel_order(&who, pair_id, order_id)?;
+                Self::do_put_order(
+                    who.clone(),
+                    OrderRequest {
+                        pair_id,
+                        order_type: order.order_type(),
+                        side: order.side(),
+                        amount,
+                        price,
+                        time_in_force: order.time_in_force(),
+                        expire_at: order.expire_at(),
+                        self_trade_prevention: Some(order.self_trade_prevention()),
+                    },
+                    &mut makers,
+                )
+            })?;
+            Ok(Some(<T as Trait>::WeightInfo::replace_order(makers)).into())
+        }
+
+        /// Add liquidity to the pool of a trading pair and receive the LP shares.
+        ///
+        /// The quote currency is deposited in proportion to the reserves of the pool,
+        /// which can not exceed `max_quote_amount`. The first liquidity provider decides
+        /// the initial price of the pool by depositing `max_quote_amount` in full.
+        #[weight = <T as Trait>::WeightInfo::add_liquidity()]
+        pub fn add_liquidity(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] base_amount: BalanceOf<T>,
+            #[compact] max_quote_amount: BalanceOf<T>,
+            #[compact] min_shares: BalanceOf<T>
+        ) {
+            let who = ensure_signed(origin)?;
+            with_transaction_result(|| {
+                Self::apply_add_liquidity(&who, pair_id, base_amount, max_quote_amount, min_shares)
+            })?;
+        }
+
+        /// Redeem the LP `shares` for the reserves of the pool proportionally.
+        #[weight = <T as Trait>::WeightInfo::remove_liquidity()]
+        pub fn remove_liquidity(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] shares: BalanceOf<T>,
+            #[compact] min_base_amount: BalanceOf<T>,
+            #[compact] min_quote_amount: BalanceOf<T>
+        ) {
+            let who = ensure_signed(origin)?;
+            with_transaction_result(|| {
+                Self::apply_remove_liquidity(&who, pair_id, shares, min_base_amount, min_quote_amount)
+            })?;
+        }
+
+        /// Swap against the liquidity pool of a trading pair.
+        ///
+        /// Sell `amount_in` of the base currency for the quote currency, or buy the
+        /// base currency with `amount_in` of the quote currency. The swap fails if
+        /// the output is less than `min_amount_out`.
+        #[weight = <T as Trait>::WeightInfo::swap()]
+        pub fn swap(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            side: Side,
+            #[compact] amount_in: BalanceOf<T>,
+            #[compact] min_amount_out: BalanceOf<T>
+        ) {
+            let who = ensure_signed(origin)?;
+            with_transaction_result(|| {
+                Self::apply_swap(&who, pair_id, side, amount_in, min_amount_out)
+            })?;
+        }
+
+        /// Swap exactly `amount_in` of the first asset in `path` for the last one.
+        ///
+        /// The swap is routed through the order books of the trading pairs of each two
+        /// adjacent assets in `path`, a market order is filled on each of them in turn.
+        /// Either all the hops are executed or none, the swap fails if the final output
+        /// is less than `min_amount_out`.
+        ///
+        /// The unfilled remainder of each hop is refunded like a market order.
+        #[weight = {
+            let hops = (path.len() as u32).saturating_sub(1).max(1);
+            Module::<T>::swap_exact_in_weight(hops, MAX_MAKERS_PER_ORDER.saturating_mul(hops))
+        }]
+        pub fn swap_exact_in(
+            origin,
+            path: Vec<AssetId>,
+            #[compact] amount_in: BalanceOf<T>,
+            #[compact] min_amount_out: BalanceOf<T>
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let makers = with_transaction_result(|| {
+                Self::apply_swap_exact_in(&who, &path, amount_in, min_amount_out)
+            })?;
+            Ok(Some(Self::swap_exact_in_weight(path.len() as u32 - 1, makers)).into())
+        }
+
+        /// Claim the maker mining reward from the DEX reward pot.
+        #[weight = <T as Trait>::WeightInfo::claim_maker_reward()]
+        pub fn claim_maker_reward(origin) {
+            let who = ensure_signed(origin)?;
+            Self::apply_claim_maker_reward(&who)?;
+        }
+
+        /// Set or remove the maker mining rule of a trading pair.
+        ///
+        /// Only the orders within the band of the handicap accrue the maker mining
+        /// weight, the mining power of all the resting orders is recalculated in
+        /// the following blocks.
+        #[weight = <T as Trait>::WeightInfo::set_maker_mining_rule()]
+        fn set_maker_mining_rule(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            rule: Option<MakerMiningRule>
+        ) {
             ensure_root(origin)?;
-            info!("[set_handicap] pair_id:{:?}, new handicap:{:?}", pair_id, new);
-            HandicapOf::<T>::insert(pair_id, new);
+            Self::apply_set_maker_mining_rule(pair_id, rule)?;
+        }
+
+        /// Create the liquidity pool of a trading pair.
+        ///
+        /// `lp_asset_id` must be a registered asset which has never been issued,
+        /// it's issued as the shares of the pool. `fee` is the rate of the swap fee.
+        #[weight = <T as Trait>::WeightInfo::create_liquidity_pool()]
+        fn create_liquidity_pool(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] lp_asset_id: AssetId,
+            fee: Permill
+        ) {
+            ensure_root(origin)?;
+            Self::apply_create_liquidity_pool(pair_id, lp_asset_id, fee)?;
         }
 
         #[weight = <T as Trait>::WeightInfo::set_price_fluctuation()]
@@ -304,6 +918,80 @@ decl_module! {
             Self::deposit_event(Event::<T>::PriceFluctuationUpdated(pair_id, new));
         }
 
+        /// Set the maker and taker fee rates of a trading pair.
+        #[weight = <T as Trait>::WeightInfo::set_trading_fee()]
+        fn set_trading_fee(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            maker: Permill,
+            taker: Permill
+        ) {
+            ensure_root(origin)?;
+            Self::trading_pair(pair_id)?;
+            let fee = TradingFee::new(maker, taker);
+            TradingFeeOf::insert(pair_id, fee);
+            Self::deposit_event(Event::<T>::TradingFeeUpdated(pair_id, fee));
+        }
+
+        /// Set the account receiving the trading fees, `None` for the treasury account.
+        ///
+        /// The fees in the native token are waived while the account doesn't exist.
+        #[weight = <T as Trait>::WeightInfo::set_fee_destination()]
+        fn set_fee_destination(origin, new: Option<<T::Lookup as StaticLookup>::Source>) {
+            ensure_root(origin)?;
+            let new = new.map(T::Lookup::lookup).transpose()?;
+            FeeDestination::<T>::set(new.clone());
+            Self::deposit_event(Event::<T>::FeeDestinationUpdated(new));
+        }
+
+        /// Set the minimum amount and the lot size of the orders of a trading pair.
+        ///
+        /// The minimum amount must be a multiple of the lot size.
+        #[weight = <T as Trait>::WeightInfo::set_order_size_limit()]
+        fn set_order_size_limit(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] min_amount: BalanceOf<T>,
+            #[compact] lot_size: BalanceOf<T>
+        ) {
+            ensure_root(origin)?;
+            Self::trading_pair(pair_id)?;
+            ensure!(
+                lot_size.is_zero() || (min_amount % lot_size).is_zero(),
+                Error::<T>::InvalidLotSize
+            );
+            let limit = OrderSizeLimit::new(min_amount, lot_size);
+            OrderSizeLimitOf::<T>::insert(pair_id, limit);
+            Self::deposit_event(Event::<T>::OrderSizeLimitUpdated(pair_id, limit));
+        }
+
+        /// Set the default self-trade prevention policy of a trading pair.
+        #[weight = <T as Trait>::WeightInfo::set_self_trade_prevention()]
+        fn set_self_trade_prevention(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            new: SelfTradePrevention
+        ) {
+            ensure_root(origin)?;
+            Self::trading_pair(pair_id)?;
+            SelfTradePreventionOf::insert(pair_id, new);
+            Self::deposit_event(Event::<T>::SelfTradePreventionUpdated(pair_id, new));
+        }
+
+        /// Set the intervals in blocks of the klines.
+        ///
+        /// The klines of the removed intervals are no longer updated.
+        #[weight = <T as Trait>::WeightInfo::set_kline_intervals()]
+        fn set_kline_intervals(origin, intervals: Vec<T::BlockNumber>) {
+            ensure_root(origin)?;
+            ensure!(
+                Self::is_valid_kline_intervals(&intervals),
+                Error::<T>::InvalidKlineInterval
+            );
+            KlineIntervals::<T>::put(&intervals);
+            Self::deposit_event(Event::<T>::KlineIntervalsUpdated(intervals));
+        }
+
         /// Add a new trading pair.
         #[weight = <T as Trait>::WeightInfo::add_trading_pair()]
         pub fn add_trading_pair(
@@ -328,6 +1016,33 @@ decl_module! {
             );
         }
 
+        /// Schedule the delisting of a trading pair at block `at`.
+        ///
+        /// No new orders are accepted since then, and all the open orders of the trading
+        /// pair will be canceled and refunded at block `at`. The previous schedule of the
+        /// trading pair is replaced if any.
+        #[weight = <T as Trait>::WeightInfo::schedule_delisting()]
+        fn schedule_delisting(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] at: T::BlockNumber
+        ) {
+            ensure_root(origin)?;
+            Self::apply_schedule_delisting(pair_id, at)?;
+        }
+
+        /// List a delisted trading pair again with the new `tick_decimals` and `latest_price`.
+        #[weight = <T as Trait>::WeightInfo::relist_trading_pair()]
+        fn relist_trading_pair(
+            origin,
+            #[compact] pair_id: TradingPairId,
+            #[compact] tick_decimals: u32,
+            #[compact] latest_price: T::Price
+        ) {
+            ensure_root(origin)?;
+            Self::apply_relist_trading_pair(pair_id, tick_decimals, latest_price)?;
+        }
+
         /// Update the trading pair profile.
         #[weight = <T as Trait>::WeightInfo::update_trading_pair()]
         pub fn update_trading_pair(
@@ -339,6 +1054,7 @@ decl_module! {
             ensure_root(origin)?;
             let pair = Self::trading_pair(pair_id)?;
             ensure!(tick_decimals >= pair.tick_decimals, Error::<T>::InvalidTickdecimals);
+            ensure!(Self::delisted_at(pair_id).is_none(), Error::<T>::TradingPairDelisted);
             Self::apply_update_trading_pair(pair_id, tick_decimals, tradable);
         }
     }
@@ -417,6 +1133,7 @@ impl<T: Trait> Module<T> {
         });
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn apply_put_order(
         who: T::AccountId,
         pair_id: TradingPairId,
@@ -425,29 +1142,137 @@ impl<T: Trait> Module<T> {
         amount: BalanceOf<T>,
         price: T::Price,
         reserve_amount: BalanceOf<T>,
-    ) -> Result<(), Error<T>> {
+        time_in_force: TimeInForce,
+        expire_at: Option<T::BlockNumber>,
+        self_trade_prevention: SelfTradePrevention,
+        makers: &mut u32,
+    ) -> DispatchResult {
         info!(
-            "transactor:{:?}, pair_id:{:}, type:{:?}, side:{:?}, amount:{:?}, price:{:?}",
-            who, pair_id, order_type, side, amount, price
+            "transactor:{:?}, pair_id:{:}, type:{:?}, side:{:?}, amount:{:?}, price:{:?}, time_in_force:{:?}",
+            who, pair_id, order_type, side, amount, price, time_in_force
         );
 
         let pair = Self::trading_pair(pair_id)?;
 
+        // The base amount of a market buy is unknown until it gets filled.
+        let base_amount = match (order_type, side) {
+            (OrderType::Market, Side::Buy) => Zero::zero(),
+            _ => amount,
+        };
+
         let mut order = Self::inject_order(
             who,
             pair_id,
             price,
             order_type,
             side,
-            amount,
+            base_amount,
             reserve_amount,
+            time_in_force,
+            expire_at,
+            self_trade_prevention,
         );
 
-        Self::try_match_order(&pair, &mut order, pair_id, side, price);
+        match order_type {
+            OrderType::Limit => {
+                Self::try_match_order(&pair, &mut order, pair_id, side, price, makers)?
+            }
+            OrderType::Market => Self::match_market_order(&pair, &mut order, makers)?,
+        }
+
+        // Only the orders resting in the order book are able to expire.
+        if let Some(expire_at) = expire_at {
+            if <OrderInfoOf<T>>::contains_key(order.submitter(), order.id()) {
+                <ExpiringOrdersAt<T>>::append(expire_at, (order.submitter(), order.id()));
+            }
+        }
 
         Ok(())
     }
 
+    /// Puts a new order, `makers` counts the maker orders touched in the current call.
+    fn do_put_order(
+        who: T::AccountId,
+        request: OrderRequestOf<T>,
+        makers: &mut u32,
+    ) -> DispatchResult {
+        let OrderRequest {
+            pair_id,
+            order_type,
+            side,
+            amount,
+            price,
+            time_in_force,
+            expire_at,
+            self_trade_prevention,
+        } = request;
+
+        ensure!(!price.is_zero(), Error::<T>::InvalidPrice);
+        ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
+        if let Some(expire_at) = expire_at {
+            ensure!(
+                expire_at > <frame_system::Module<T>>::block_number(),
+                Error::<T>::InvalidExpiry
+            );
+        }
+        ensure!(
+            order_type == OrderType::Limit
+                || !matches!(
+                    time_in_force,
+                    TimeInForce::FillOrKill | TimeInForce::PostOnly
+                ),
+            Error::<T>::InvalidTimeInForce
+        );
+
+        let pair = Self::trading_pair(pair_id)?;
+
+        ensure!(pair.tradable, Error::<T>::TradingPairUntradable);
+        ensure!(
+            !Self::is_delisting(pair_id),
+            Error::<T>::TradingPairDelisting
+        );
+        ensure!(pair.is_valid_price(price), Error::<T>::InvalidPrice);
+
+        // The amount of a market buy is measured in the quote currency.
+        if !(order_type == OrderType::Market && side == Side::Buy) {
+            Self::ensure_valid_amount(pair_id, amount)?;
+        }
+        Self::is_valid_quote(price, side, pair_id)?;
+        if time_in_force == TimeInForce::PostOnly {
+            Self::ensure_post_only_not_crossed(price, side, pair_id)?;
+        }
+        if order_type == OrderType::Limit && !time_in_force.is_immediate() {
+            ensure!(
+                Self::can_rest_at(pair_id, side, price),
+                Error::<T>::TooManyPriceLevels
+            );
+        }
+
+        // Reserve the token according to the order side.
+        let (reserve_asset, reserve_amount) = match (order_type, side) {
+            (OrderType::Limit, Side::Buy) => (
+                pair.quote(),
+                Self::convert_base_to_quote(amount, price, &pair)?,
+            ),
+            (OrderType::Market, Side::Buy) => (pair.quote(), amount),
+            (_, Side::Sell) => (pair.base(), amount),
+        };
+        Self::put_order_reserve(&who, reserve_asset, reserve_amount)?;
+        Self::apply_put_order(
+            who,
+            pair_id,
+            order_type,
+            side,
+            amount,
+            price,
+            reserve_amount,
+            time_in_force,
+            expire_at,
+            self_trade_prevention.unwrap_or_else(|| Self::self_trade_prevention_of(pair_id)),
+            makers,
+        )
+    }
+
     fn do_cancel_order(
         who: &T::AccountId,
         pair_id: TradingPairId,
@@ -482,28 +1307,123 @@ impl<T: Trait> Module<T> {
 
         Self::update_order_and_unreserve_on_cancel(&mut order, &pair, who)?;
 
-        Self::kill_order(
-            pair_id,
-            order.price(),
-            who.clone(),
-            order_id,
-            pair,
-            order.side(),
-        );
+        Self::kill_order(pair_id, order.price(), who.clone(), order_id, order.side());
+
+        Ok(())
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Returns true if there are at most `MAX_KLINE_INTERVALS` intervals which are all
+    /// non-zero and distinct, a duplicate interval would aggregate every execution twice.
+    fn is_valid_kline_intervals(intervals: &[T::BlockNumber]) -> bool {
+        intervals.len() <= MAX_KLINE_INTERVALS
+            && intervals
+                .iter()
+                .enumerate()
+                .all(|(i, interval)| !interval.is_zero() && !intervals[..i].contains(interval))
+    }
+
+    /// Cancels the open orders of `who`, optionally on the given trading pair.
+    ///
+    /// The orders on the untradable trading pairs are skipped unless `force` is true.
+    fn apply_cancel_all_orders(
+        who: &T::AccountId,
+        pair_id: Option<TradingPairId>,
+        force: bool,
+    ) -> DispatchResult {
+        let pair_ids = match pair_id {
+            Some(pair_id) => vec![pair_id],
+            None => (0..Self::trading_pair_count()).collect(),
+        };
+        let orders = pair_ids
+            .into_iter()
+            .filter(|pair_id| {
+                force || Self::trading_pair_of(pair_id).map_or(false, |pair| pair.tradable)
+            })
+            .flat_map(|pair_id| {
+                <OpenOrdersOf<T>>::iter_prefix((who.clone(), pair_id))
+                    .map(move |(order_id, _)| (pair_id, order_id))
+            })
+            .take(MAX_ORDERS_PER_BATCH)
+            .collect::<Vec<_>>();
+
+        for (pair_id, order_id) in orders {
+            Self::apply_cancel_order(who, pair_id, order_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Cancels the open orders of all accounts on the trading pair.
+    fn apply_cancel_pair_orders(pair_id: TradingPairId) -> DispatchResult {
+        Self::trading_pair(pair_id)?;
+
+        let orders = [Side::Buy, Side::Sell]
+            .iter()
+            .flat_map(|side| Self::price_levels_of(pair_id, *side))
+            .flat_map(|price| Self::iter_quotations(pair_id, price))
+            .take(MAX_ORDERS_PER_BATCH)
+            .collect::<Vec<_>>();
+
+        for (who, order_id) in orders {
+            if <OrderInfoOf<T>>::contains_key(&who, order_id) {
+                Self::apply_cancel_order(&who, pair_id, order_id)?;
+            }
+        }
 
         Ok(())
     }
+
+    /// Cancels the open orders expired at block `now`.
+    ///
+    /// At most `MAX_EXPIRATIONS_PER_BLOCK` orders are processed in one block,
+    /// the rest are postponed to the next block.
+    fn cancel_expired_orders(now: T::BlockNumber) -> Weight {
+        let mut expired = <ExpiringOrdersAt<T>>::take(now);
+        if expired.is_empty() {
+            return T::DbWeight::get().reads(1);
+        }
+
+        if expired.len() > MAX_EXPIRATIONS_PER_BLOCK {
+            let postponed = expired.split_off(MAX_EXPIRATIONS_PER_BLOCK);
+            <ExpiringOrdersAt<T>>::mutate(now + One::one(), |orders| orders.extend(postponed));
+        }
+
+        let count = expired.len() as Weight;
+        for (who, order_id) in expired {
+            // The order might have been filled or canceled already.
+            if let Some(order) = Self::order_info_of(&who, order_id) {
+                if let Err(err) = Self::apply_cancel_order(&who, order.pair_id(), order_id) {
+                    error!(
+                        "[cancel_expired_orders] Fail to cancel the expired order, who:{:?}, order_id:{}, err:{:?}",
+                        who, order_id, err
+                    );
+                }
+            }
+        }
+
+        T::DbWeight::get()
+            .reads_writes(2, 2)
+            .saturating_add(<T as Trait>::WeightInfo::cancel_order().saturating_mul(count))
+    }
 }
 
 impl<T: Trait> xpallet_assets_registrar::RegistrarHandler for Module<T> {
+    /// The trading pairs of the deregistered asset become untradable at once,
+    /// and will be delisted in the next block.
     fn on_deregister(token: &AssetId) -> DispatchResult {
         let pair_len = TradingPairCount::get();
+        let next = <frame_system::Module<T>>::block_number() + One::one();
         for i in 0..pair_len {
             if let Some(mut pair) = TradingPairOf::get(i) {
-                if pair.base().eq(token) || pair.quote().eq(token) {
+                if (pair.base().eq(token) || pair.quote().eq(token))
+                    && Self::delisted_at(i).is_none()
+                {
                     pair.tradable = false;
                     TradingPairOf::insert(i, &pair);
                     Self::deposit_event(Event::<T>::TradingPairUpdated(pair));
+                    Self::apply_schedule_delisting(i, next)?;
                 }
             }
         }
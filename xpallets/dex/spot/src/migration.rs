@@ -0,0 +1,227 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of the storage migrations of Spot.
+
+use super::*;
+use codec::{Decode, Encode};
+use frame_support::{storage::migration::StorageIterator, IterableStorageDoubleMap};
+use sp_std::cell::Cell;
+use xp_logging::warn;
+
+/// Applies the migrations from the current storage version.
+pub(crate) fn on_runtime_upgrade<T: Trait>() -> Weight {
+    let mut weight = T::DbWeight::get().reads(1);
+
+    if Module::<T>::storage_version() == Releases::V1_0_0 {
+        weight = weight.saturating_add(migrate_order_info::<T>());
+        weight = weight.saturating_add(index_open_orders::<T>());
+        weight = weight.saturating_add(migrate_quotations::<T>());
+        weight = weight.saturating_add(backfill_price_levels::<T>());
+        weight = weight.saturating_add(rebuild_handicap::<T>());
+        StorageVersion::put(Releases::V2_0_0);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+    }
+
+    weight
+}
+
+/// `OrderProperty` of `Releases::V1_0_0`.
+#[derive(Encode, Decode)]
+pub(crate) struct OldOrderProperty<PairId, AccountId, Amount, Price, BlockNumber> {
+    pub id: OrderId,
+    pub side: Side,
+    pub price: Price,
+    pub amount: Amount,
+    pub pair_id: PairId,
+    pub submitter: AccountId,
+    pub order_type: OrderType,
+    pub created_at: BlockNumber,
+}
+
+/// `Order` of `Releases::V1_0_0`.
+#[derive(Encode, Decode)]
+pub(crate) struct OldOrder<PairId, AccountId, Balance, Price, BlockNumber> {
+    pub props: OldOrderProperty<PairId, AccountId, Balance, Price, BlockNumber>,
+    pub status: OrderStatus,
+    pub remaining: Balance,
+    pub executed_indices: Vec<TradingHistoryIndex>,
+    pub already_filled: Balance,
+    pub last_update_at: BlockNumber,
+}
+
+pub(crate) type OldOrderInfo<T> = OldOrder<
+    TradingPairId,
+    <T as frame_system::Trait>::AccountId,
+    BalanceOf<T>,
+    <T as Trait>::Price,
+    <T as frame_system::Trait>::BlockNumber,
+>;
+
+/// Translates the orders in `OrderInfoOf` to the current layout.
+///
+/// The orders put before are good till canceled, have no expiration and used to be
+/// matched with the orders of the same submitter, hence `SelfTradePrevention::None`.
+fn migrate_order_info<T: Trait>() -> Weight {
+    let count = Cell::new(0 as Weight);
+    OrderInfoOf::<T>::translate::<OldOrderInfo<T>, _>(|_, _, old| {
+        count.set(count.get() + 1);
+        let props = OrderProperty {
+            id: old.props.id,
+            side: old.props.side,
+            price: old.props.price,
+            amount: old.props.amount,
+            pair_id: old.props.pair_id,
+            submitter: old.props.submitter,
+            order_type: old.props.order_type,
+            time_in_force: TimeInForce::GoodTillCancel,
+            self_trade_prevention: SelfTradePrevention::None,
+            created_at: old.props.created_at,
+            expire_at: None,
+        };
+        Some(Order {
+            props,
+            status: old.status,
+            remaining: old.remaining,
+            executed_indices: old.executed_indices,
+            already_filled: old.already_filled,
+            last_update_at: old.last_update_at,
+        })
+    });
+    info!("[migrate_order_info] {} orders translated", count.get());
+    T::DbWeight::get().reads_writes(count.get(), count.get())
+}
+
+/// Indexes the orders in `OrderInfoOf` in `OpenOrdersOf`.
+fn index_open_orders<T: Trait>() -> Weight {
+    let mut count: Weight = 0;
+    for (who, order_id, order) in OrderInfoOf::<T>::iter() {
+        OpenOrdersOf::<T>::insert((who, order.pair_id()), order_id, ());
+        count += 1;
+    }
+    info!("[index_open_orders] {} orders indexed", count);
+    T::DbWeight::get().reads_writes(count, count)
+}
+
+/// Decodes the trading pair ID and price from the key of `QuotationsOf`, both of
+/// which are hashed by `twox_64_concat`.
+fn decode_quotations_key<T: Trait>(key: &[u8]) -> Option<(TradingPairId, T::Price)> {
+    let mut input = key.get(8..)?;
+    let pair_id = TradingPairId::decode(&mut input).ok()?;
+    let mut input = input.get(8..)?;
+    let price = T::Price::decode(&mut input).ok()?;
+    Some((pair_id, price))
+}
+
+/// Moves the quotations from the removed `QuotationsOf` to `QuotationQueueOf` and
+/// `QuotationNodeOf`, the time priority is kept.
+///
+/// The quotations whose order no longer exists are dropped.
+fn migrate_quotations<T: Trait>() -> Weight {
+    let mut reads_writes: Weight = 0;
+
+    for (key, quotations) in
+        StorageIterator::<Vec<(T::AccountId, OrderId)>>::new(b"XSpot", b"QuotationsOf").drain()
+    {
+        reads_writes += 1;
+        let (pair_id, price) = match decode_quotations_key::<T>(&key) {
+            Some(pair_and_price) => pair_and_price,
+            None => {
+                warn!("[migrate_quotations] failed to decode the key:{:?}", key);
+                continue;
+            }
+        };
+
+        let quotations = quotations
+            .into_iter()
+            .filter(|(who, order_id)| {
+                reads_writes += 1;
+                Module::<T>::order_info_of(who, order_id).map_or(false, |order| {
+                    order.pair_id() == pair_id && order.price() == price
+                })
+            })
+            .collect::<Vec<_>>();
+        let (head, tail) = match (quotations.first(), quotations.last()) {
+            (Some(head), Some(tail)) => (head.clone(), tail.clone()),
+            _ => continue,
+        };
+
+        for (idx, (who, order_id)) in quotations.iter().enumerate() {
+            let node = QuotationNode {
+                prev: idx.checked_sub(1).map(|prev| quotations[prev].clone()),
+                next: quotations.get(idx + 1).cloned(),
+            };
+            QuotationNodeOf::<T>::insert(who, order_id, node);
+        }
+        QuotationQueueOf::<T>::insert(pair_id, price, QuotationQueue { head, tail });
+        reads_writes += 1;
+
+        info!(
+            "[migrate_quotations] pair_id:{}, price:{:?}, quotations:{:?}",
+            pair_id, price, quotations
+        );
+    }
+
+    T::DbWeight::get().reads_writes(reads_writes, reads_writes)
+}
+
+/// Backfills `PriceLevelsOf` with the prices of the non-empty quotations.
+///
+/// The side of a price level is the one of the first order in the quotations.
+fn backfill_price_levels<T: Trait>() -> Weight {
+    let pair_count = TradingPairCount::get();
+    let mut reads: Weight = 1;
+
+    for pair_id in 0..pair_count {
+        let mut bids = Vec::new();
+        let mut asks = Vec::new();
+        for (price, queue) in QuotationQueueOf::<T>::iter_prefix(pair_id) {
+            reads += 2;
+            let (who, order_id) = queue.head;
+            match Module::<T>::order_info_of(who, order_id).map(|order| order.side()) {
+                Some(Side::Buy) => bids.push(price),
+                Some(Side::Sell) => asks.push(price),
+                None => warn!(
+                    "[backfill_price_levels] the order of the quotations doesn't exist, pair_id:{}, price:{:?}",
+                    pair_id, price
+                ),
+            }
+        }
+        bids.sort();
+        asks.sort();
+        info!(
+            "[backfill_price_levels] pair_id:{}, bids:{:?}, asks:{:?}",
+            pair_id, bids, asks
+        );
+        PriceLevelsOf::<T>::insert(pair_id, Side::Buy, bids);
+        PriceLevelsOf::<T>::insert(pair_id, Side::Sell, asks);
+    }
+
+    T::DbWeight::get().reads_writes(reads, 2 * pair_count as Weight)
+}
+
+/// Rebuilds `HandicapOf` from `PriceLevelsOf`.
+///
+/// The handicap used to be a pair of prices, which can't be decoded as the optional
+/// best price levels any more, all of them are overwritten here.
+fn rebuild_handicap<T: Trait>() -> Weight {
+    let pair_count = TradingPairCount::get();
+
+    for pair_id in 0..pair_count {
+        let handicap = Handicap {
+            highest_bid: Module::<T>::price_levels_of(pair_id, Side::Buy)
+                .last()
+                .copied(),
+            lowest_ask: Module::<T>::price_levels_of(pair_id, Side::Sell)
+                .first()
+                .copied(),
+        };
+        info!(
+            "[rebuild_handicap] pair_id:{}, handicap:{:?}",
+            pair_id, handicap
+        );
+        HandicapOf::<T>::insert(pair_id, handicap);
+    }
+
+    let pair_count = pair_count as Weight;
+    T::DbWeight::get().reads_writes(1 + 2 * pair_count, pair_count)
+}
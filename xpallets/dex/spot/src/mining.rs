@@ -0,0 +1,332 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of the liquidity mining of the makers.
+//!
+//! The orders resting within the band of the handicap accrue the mining weight
+//! of their makers in the same time-sensitive way as the other kinds of mining:
+//!
+//! ```no_compile
+//! in_band_amount(Balance) * power * time(BlockNumber) = maker_mining_weight
+//! ```
+//!
+//! All the makers split the DEX reward pot funded by Staking according to the
+//! proportion of maker mining weight.
+//!
+//! An order is refreshed at once when it's put, matched or canceled. The orders at
+//! the price levels moving into or out of the band due to a handicap change are
+//! refreshed in the following blocks instead, `MAX_MINING_REFRESHES_PER_BLOCK` at most
+//! per block, so that the calls changing the handicap don't pay for them.
+
+use super::*;
+use frame_support::traits::ExistenceRequirement;
+use sp_runtime::{traits::AccountIdConversion, ModuleId};
+use xp_mining_common::{
+    generic_weight_factors, ComputeMiningWeight, Delta, MiningWeight as _, WeightFactors,
+};
+use xp_mining_staking::DexMining;
+
+/// The ID used to derive the account of the DEX reward pot.
+const DEX_MINING_MODULE_ID: ModuleId = ModuleId(*b"pcx/dexm");
+
+impl<T: Trait> ComputeMiningWeight<T::AccountId, T::BlockNumber> for Module<T> {
+    /// All the makers share one reward pot.
+    type Claimee = ();
+    type Error = Error<T>;
+
+    fn claimer_weight_factors(
+        who: &T::AccountId,
+        _: &Self::Claimee,
+        current_block: T::BlockNumber,
+    ) -> WeightFactors {
+        generic_weight_factors::<MiningPower, T::BlockNumber, _>(
+            Self::maker_mining_ledgers(who),
+            current_block,
+        )
+    }
+
+    fn claimee_weight_factors(_: &Self::Claimee, current_block: T::BlockNumber) -> WeightFactors {
+        generic_weight_factors::<MiningPower, T::BlockNumber, _>(
+            Self::total_maker_mining_ledger(),
+            current_block,
+        )
+    }
+}
+
+impl<T: Trait> DexMining<BalanceOf<T>> for Module<T> {
+    /// Issues reward to the DEX reward pot.
+    fn reward(value: BalanceOf<T>) {
+        let reward_pot = Self::dex_reward_pot();
+        <T as xpallet_assets::Trait>::Currency::deposit_creating(&reward_pot, value);
+        Self::deposit_event(Event::<T>::MakerRewardMinted(reward_pot, value));
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Returns the account of the DEX reward pot shared by all the makers.
+    pub fn dex_reward_pot() -> T::AccountId {
+        DEX_MINING_MODULE_ID.into_account()
+    }
+
+    /// Returns the reward `who` is able to claim from the DEX reward pot now.
+    pub fn maker_dividend_of(who: &T::AccountId) -> BalanceOf<T> {
+        let reward_pot_balance =
+            <T as xpallet_assets::Trait>::Currency::free_balance(&Self::dex_reward_pot());
+        <Self as ComputeMiningWeight<T::AccountId, T::BlockNumber>>::compute_dividend(
+            who,
+            &(),
+            <frame_system::Module<T>>::block_number(),
+            reward_pot_balance,
+        )
+        .map(|(dividend, _, _)| dividend)
+        .unwrap_or_else(|_| Zero::zero())
+    }
+
+    /// Returns the mining power the order should have according to its latest state.
+    fn expected_order_mining_power(who: &T::AccountId, order_id: OrderId) -> MiningPower {
+        // Only the orders resting in the order book are able to mine.
+        if !<QuotationNodeOf<T>>::contains_key(who, order_id) {
+            return 0;
+        }
+        let order = match Self::order_info_of(who, order_id) {
+            Some(order) if !order.is_fulfilled() && !order.is_canceled() => order,
+            _ => return 0,
+        };
+        let rule = match Self::maker_mining_rule_of(order.pair_id()) {
+            Some(rule) => rule,
+            None => return 0,
+        };
+
+        let handicap = Self::handicap_of(order.pair_id());
+        let best = match order.side() {
+            Side::Buy => handicap.highest_bid,
+            Side::Sell => handicap.lowest_ask,
+        };
+        match best {
+            Some(best) if rule.is_in_band(order.side(), order.price(), best) => order
+                .remaining_in_base()
+                .saturated_into::<MiningPower>()
+                .saturating_mul(rule.power.into()),
+            _ => 0,
+        }
+    }
+
+    /// Settles the mining weight of the maker as well as all the makers before
+    /// the mining power of the order changes.
+    pub(crate) fn set_order_mining_power(who: &T::AccountId, order_id: OrderId, new: MiningPower) {
+        let old = Self::order_mining_power_of(who, order_id);
+        if old == new {
+            return;
+        }
+        let delta = if new > old {
+            Delta::Add(new - old)
+        } else {
+            Delta::Sub(old - new)
+        };
+        let current_block = <frame_system::Module<T>>::block_number();
+
+        let maker_weight =
+            <Self as ComputeMiningWeight<T::AccountId, T::BlockNumber>>::settle_claimer_weight(
+                who,
+                &(),
+                current_block,
+            );
+        <MakerMiningLedgers<T>>::mutate(who, |ledger| {
+            ledger.set_state(maker_weight, current_block, &delta)
+        });
+
+        let total_weight =
+            <Self as ComputeMiningWeight<T::AccountId, T::BlockNumber>>::settle_claimee_weight(
+                &(),
+                current_block,
+            );
+        <TotalMakerMiningLedger<T>>::mutate(|ledger| {
+            ledger.set_state(total_weight, current_block, &delta)
+        });
+
+        if new.is_zero() {
+            <OrderMiningPowerOf<T>>::remove(who, order_id);
+        } else {
+            <OrderMiningPowerOf<T>>::insert(who, order_id, new);
+        }
+    }
+
+    /// Updates the mining power of the order according to its latest state.
+    pub(crate) fn refresh_order_mining_power(who: &T::AccountId, order_id: OrderId) {
+        Self::set_order_mining_power(
+            who,
+            order_id,
+            Self::expected_order_mining_power(who, order_id),
+        );
+    }
+
+    /// Queues the refresh of the orders at the price levels which move into or out
+    /// of the band when the best price of `side` changes.
+    ///
+    /// The levels within the band are contiguous, so are the ones whose state changes,
+    /// only their price range is queued here.
+    pub(crate) fn refresh_mining_power_on_handicap_change(
+        pair_id: TradingPairId,
+        side: Side,
+        levels: &[T::Price],
+        old_best: Option<T::Price>,
+        new_best: Option<T::Price>,
+    ) {
+        if old_best == new_best {
+            return;
+        }
+        let rule = match Self::maker_mining_rule_of(pair_id) {
+            Some(rule) => rule,
+            None => return,
+        };
+
+        let is_in_band = |best: Option<T::Price>, price: T::Price| {
+            best.map_or(false, |best| rule.is_in_band(side, price, best))
+        };
+        let mut changed = levels
+            .iter()
+            .filter(|&&price| is_in_band(old_best, price) != is_in_band(new_best, price));
+        if let Some(&lowest) = changed.next() {
+            let highest = changed.last().copied().unwrap_or(lowest);
+            Self::queue_mining_refresh(pair_id, side, lowest, highest);
+        }
+    }
+
+    /// Appends the price range of `side` to `MiningRefreshQueue`.
+    fn queue_mining_refresh(
+        pair_id: TradingPairId,
+        side: Side,
+        lowest: T::Price,
+        highest: T::Price,
+    ) {
+        let tail = Self::mining_refresh_queue_tail();
+        <MiningRefreshQueue<T>>::insert(tail, (pair_id, side, lowest, highest));
+        MiningRefreshQueueTail::put(tail + 1);
+    }
+
+    /// Refreshes the mining power of the orders in the price ranges of `MiningRefreshQueue`.
+    ///
+    /// At most `MAX_MINING_REFRESHES_PER_BLOCK` orders are refreshed in one block, the rest
+    /// are resumed from `MiningRefreshCursor` in the next block.
+    pub(crate) fn refresh_queued_mining_power() -> Weight {
+        let mut head = Self::mining_refresh_queue_head();
+        let tail = Self::mining_refresh_queue_tail();
+        if head == tail {
+            return T::DbWeight::get().reads(2);
+        }
+
+        let mut cursor = Self::mining_refresh_cursor();
+        let mut refreshed = 0u32;
+        let mut items: Weight = 0;
+        let mut levels_visited: Weight = 0;
+
+        while head < tail && refreshed < MAX_MINING_REFRESHES_PER_BLOCK {
+            items += 1;
+            if let Some((pair_id, side, lowest, highest)) = Self::mining_refresh_queue(head) {
+                let start = cursor.as_ref().map_or(lowest, |(price, _)| *price);
+                let levels = Self::price_levels_of(pair_id, side)
+                    .into_iter()
+                    .filter(|&price| price >= start && price <= highest)
+                    .collect::<Vec<_>>();
+
+                let mut exhausted = true;
+                'levels: for price in levels {
+                    levels_visited += 1;
+                    // Start over at this level if the order at the cursor has gone.
+                    let resumed = cursor.take().filter(|(cursor_price, (who, order_id))| {
+                        *cursor_price == price && <QuotationNodeOf<T>>::contains_key(who, order_id)
+                    });
+                    let quotations = match resumed {
+                        Some((_, order_key)) => Self::iter_quotations_from(order_key),
+                        None => Self::iter_quotations(pair_id, price),
+                    };
+                    for (who, order_id) in quotations {
+                        if refreshed == MAX_MINING_REFRESHES_PER_BLOCK {
+                            cursor = Some((price, (who, order_id)));
+                            exhausted = false;
+                            break 'levels;
+                        }
+                        Self::refresh_order_mining_power(&who, order_id);
+                        refreshed += 1;
+                    }
+                }
+                if !exhausted {
+                    break;
+                }
+            }
+            <MiningRefreshQueue<T>>::remove(head);
+            cursor = None;
+            head += 1;
+        }
+
+        MiningRefreshQueueHead::put(head);
+        match cursor {
+            Some(cursor) => <MiningRefreshCursor<T>>::put(cursor),
+            None => <MiningRefreshCursor<T>>::kill(),
+        }
+
+        T::DbWeight::get()
+            .reads_writes(3, 2)
+            .saturating_add(T::DbWeight::get().reads_writes(2, 1).saturating_mul(items))
+            .saturating_add(T::DbWeight::get().reads(2).saturating_mul(levels_visited))
+            .saturating_add(
+                T::DbWeight::get()
+                    .reads_writes(9, 3)
+                    .saturating_mul(refreshed as Weight),
+            )
+    }
+
+    pub(crate) fn apply_set_maker_mining_rule(
+        pair_id: TradingPairId,
+        rule: Option<MakerMiningRule>,
+    ) -> DispatchResult {
+        Self::trading_pair(pair_id)?;
+
+        match rule {
+            Some(rule) => <MakerMiningRuleOf>::insert(pair_id, rule),
+            None => <MakerMiningRuleOf>::remove(pair_id),
+        }
+
+        // The mining power of all the resting orders has to be recalculated.
+        for &side in [Side::Buy, Side::Sell].iter() {
+            let levels = Self::price_levels_of(pair_id, side);
+            if let (Some(&lowest), Some(&highest)) = (levels.first(), levels.last()) {
+                Self::queue_mining_refresh(pair_id, side, lowest, highest);
+            }
+        }
+
+        Self::deposit_event(Event::<T>::MakerMiningRuleUpdated(pair_id, rule));
+
+        Ok(())
+    }
+
+    /// Transfers the dividend of `who` from the DEX reward pot.
+    pub(crate) fn apply_claim_maker_reward(who: &T::AccountId) -> DispatchResult {
+        let current_block = <frame_system::Module<T>>::block_number();
+        let reward_pot = Self::dex_reward_pot();
+        let reward_pot_balance = <T as xpallet_assets::Trait>::Currency::free_balance(&reward_pot);
+
+        let (dividend, source_weight, target_weight) =
+            <Self as ComputeMiningWeight<T::AccountId, T::BlockNumber>>::compute_dividend(
+                who,
+                &(),
+                current_block,
+                reward_pot_balance,
+            )?;
+
+        <T as xpallet_assets::Trait>::Currency::transfer(
+            &reward_pot,
+            who,
+            dividend,
+            ExistenceRequirement::AllowDeath,
+        )?;
+
+        <MakerMiningLedgers<T>>::mutate(who, |ledger| ledger.set_state_weight(0, current_block));
+        <TotalMakerMiningLedger<T>>::mutate(|ledger| {
+            ledger.set_state_weight(target_weight - source_weight, current_block)
+        });
+
+        Self::deposit_event(Event::<T>::MakerRewardClaimed(who.clone(), dividend));
+
+        Ok(())
+    }
+}
@@ -9,13 +9,15 @@ use sp_runtime::{traits::Saturating, RuntimeDebug};
 #[derive(PartialEq, Eq, Clone, Default, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
-pub struct FullPairInfo<Price, BlockNumber> {
+pub struct FullPairInfo<Price, Balance, BlockNumber> {
     #[cfg_attr(feature = "std", serde(flatten))]
     pub profile: TradingPairProfile,
     #[cfg_attr(feature = "std", serde(flatten))]
     pub handicap: Handicap<Price>,
     #[cfg_attr(feature = "std", serde(flatten))]
     pub pair_info: TradingPairInfo<Price, BlockNumber>,
+    #[cfg_attr(feature = "std", serde(flatten))]
+    pub order_size_limit: OrderSizeLimit<Balance>,
     /// The maximum valid bid price.
     pub max_valid_bid: Price,
     /// The minimum valid ask price.
@@ -62,21 +64,19 @@ impl<T: Trait> Module<T> {
     fn get_quotation_range(profile: &TradingPairProfile) -> (T::Price, T::Price) {
         let handicap = Self::handicap_of(profile.id);
         let pair_fluctuation: T::Price = profile.calc_fluctuation::<T>().saturated_into();
-        let max_valid_bid = if !handicap.lowest_ask.is_zero() {
-            handicap.lowest_ask + pair_fluctuation
-        } else {
-            Zero::zero()
+        let max_valid_bid = match handicap.lowest_ask {
+            Some(lowest_ask) => lowest_ask + pair_fluctuation,
+            None => Zero::zero(),
         };
-        let min_valid_ask = if handicap.highest_bid > pair_fluctuation {
-            handicap.highest_bid - pair_fluctuation
-        } else {
-            profile.tick().saturated_into()
+        let min_valid_ask = match handicap.highest_bid {
+            Some(highest_bid) if highest_bid > pair_fluctuation => highest_bid - pair_fluctuation,
+            _ => profile.tick().saturated_into(),
         };
         (min_valid_ask, max_valid_bid)
     }
 
     /// Get the overall info of all trading pairs.
-    pub fn trading_pairs() -> Vec<FullPairInfo<T::Price, T::BlockNumber>> {
+    pub fn trading_pairs() -> Vec<FullPairInfo<T::Price, BalanceOf<T>, T::BlockNumber>> {
         let pair_count = Self::trading_pair_count();
         let mut pairs = Vec::with_capacity(pair_count as usize);
         for pair_id in 0..pair_count {
@@ -89,6 +89,7 @@ impl<T: Trait> Module<T> {
                     profile,
                     handicap,
                     pair_info,
+                    order_size_limit: Self::order_size_limit_of(pair_id),
                     max_valid_bid,
                     min_valid_ask,
                 });
@@ -137,8 +138,7 @@ impl<T: Trait> Module<T> {
 
     /// Returns the sum of unfilled quantities at `price` of a trading pair `pair_id`.
     fn get_commulative_qty(pair_id: TradingPairId, price: T::Price) -> u128 {
-        QuotationsOf::<T>::get(pair_id, price)
-            .iter()
+        Self::iter_quotations(pair_id, price)
             .filter_map(|(trader, order_id)| OrderInfoOf::<T>::get(trader, order_id))
             .map(|order| {
                 order
@@ -159,21 +159,15 @@ impl<T: Trait> Module<T> {
 
             let (min_valid_ask, max_valid_bid) = Self::get_quotation_range(&pair);
 
-            let step = pair.tick().saturated_into::<u128>();
-
-            let generic_depth = |start: T::Price, end: T::Price| {
-                let start = start.saturated_into::<u128>();
-                let end = end.saturated_into::<u128>();
-                (0..)
-                    .map(|x| start + step * x)
-                    .take_while(|&x| x <= end)
+            let generic_depth = |side: Side, start: T::Price, end: T::Price| {
+                Self::price_levels_of(pair_id, side)
+                    .into_iter()
+                    .filter(|&price| price >= start && price <= end)
                     .filter_map(|price| {
-                        let cummulative_qty =
-                            Self::get_commulative_qty(pair_id, price.saturated_into());
+                        let cummulative_qty = Self::get_commulative_qty(pair_id, price);
                         if cummulative_qty.is_zero() {
                             None
                         } else {
-                            let price: T::Price = price.saturated_into();
                             let cummulative_qty: BalanceOf<T> = cummulative_qty.saturated_into();
                             Some((price, cummulative_qty))
                         }
@@ -182,20 +176,74 @@ impl<T: Trait> Module<T> {
                     .collect::<Vec<_>>()
             };
 
-            let asks = generic_depth(lowest_ask, max_valid_bid);
-            let bids = generic_depth(min_valid_ask, highest_bid);
+            // The side without any orders has no depth.
+            let asks = lowest_ask
+                .map(|lowest_ask| generic_depth(Side::Sell, lowest_ask, max_valid_bid))
+                .unwrap_or_default();
+            let bids = highest_bid
+                .map(|highest_bid| generic_depth(Side::Buy, min_valid_ask, highest_bid))
+                .unwrap_or_default();
 
             Depth { asks, bids }
         })
     }
+
+    /// Get at most `limit` executions of a trading pair starting from `from_index`.
+    ///
+    /// Only the recent `MAX_TRADE_HISTORY` executions are available.
+    pub fn trade_history(
+        pair_id: TradingPairId,
+        from_index: TradingHistoryIndex,
+        limit: u32,
+    ) -> Vec<OrderExecutedInfoOf<T>> {
+        let next_index = Self::trading_history_index_of(pair_id);
+        let start = cmp::max(from_index, next_index.saturating_sub(MAX_TRADE_HISTORY));
+        let end = cmp::min(next_index, start.saturating_add(limit.into()));
+        (start..end)
+            .filter_map(|idx| Self::trade_history_of(pair_id, idx % MAX_TRADE_HISTORY))
+            .collect()
+    }
+
+    /// Get the klines of a trading pair given the interval, which start within `[from, to]`.
+    ///
+    /// Only the recent `MAX_KLINES` klines of each interval are available.
+    pub fn klines(
+        pair_id: TradingPairId,
+        interval: T::BlockNumber,
+        from: T::BlockNumber,
+        to: T::BlockNumber,
+    ) -> Vec<KlineOf<T>> {
+        if interval.is_zero() {
+            return Vec::new();
+        }
+
+        let now = <frame_system::Module<T>>::block_number();
+        let oldest =
+            (now - now % interval).saturating_sub(interval.saturating_mul((MAX_KLINES - 1).into()));
+        let to = cmp::min(to, now);
+
+        let mut start_at = cmp::max(from - from % interval, oldest);
+        let mut klines = Vec::new();
+        while start_at <= to {
+            if let Some(kline) =
+                Self::klines_of((pair_id, interval), Self::kline_slot(start_at, interval))
+            {
+                if kline.start_at == start_at {
+                    klines.push(kline);
+                }
+            }
+            start_at += interval;
+        }
+        klines
+    }
 }
 
 #[cfg(test)]
 mod rpc_tests {
     use super::*;
     use crate::mock::*;
-    use crate::tests::{t_issue_pcx, t_put_order_sell, t_set_handicap};
-    use frame_support::assert_ok;
+    use crate::tests::{t_generic_issue, t_issue_pcx, t_put_order_buy, t_put_order_sell};
+    use frame_support::{assert_noop, assert_ok};
 
     #[test]
     fn rpc_depth_should_work() {
@@ -203,8 +251,6 @@ mod rpc_tests {
             let pair_id = 0;
             let who = 1;
 
-            t_set_handicap(pair_id, 1_000_000, 1_100_000);
-
             t_issue_pcx(who, 1000);
             // The depth does not count this order in.
             assert_ok!(t_put_order_sell(who, pair_id, 100, 1_210_000));
@@ -219,4 +265,91 @@ mod rpc_tests {
             });
         });
     }
+
+    #[test]
+    fn rpc_trade_history_should_work() {
+        ExtBuilder::default().build_and_execute(|| {
+            let pair_id = 0;
+            let trading_pair = XSpot::trading_pair_of(pair_id).unwrap();
+
+            t_issue_pcx(1, 3_000_000);
+            t_generic_issue(trading_pair.quote(), 2, 3_000);
+
+            assert_ok!(t_put_order_sell(1, pair_id, 3_000_000, 1_000_100));
+            for _ in 0..3 {
+                assert_ok!(t_put_order_buy(2, pair_id, 1_000_000, 1_000_100));
+            }
+
+            let history = XSpot::trade_history(pair_id, 0, 100);
+            assert_eq!(history.len(), 3);
+            assert_eq!(
+                history
+                    .iter()
+                    .map(|info| (info.trading_history_idx, info.taker_order_id))
+                    .collect::<Vec<_>>(),
+                vec![(0, 0), (1, 1), (2, 2)]
+            );
+            assert!(history
+                .iter()
+                .all(|info| info.maker == 1 && info.taker == 2 && info.turnover == 1_000_000));
+
+            let history = XSpot::trade_history(pair_id, 1, 1);
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].trading_history_idx, 1);
+
+            assert!(XSpot::trade_history(pair_id, 3, 100).is_empty());
+        });
+    }
+
+    #[test]
+    fn rpc_klines_should_work() {
+        ExtBuilder::default().build_and_execute(|| {
+            let pair_id = 0;
+            let trading_pair = XSpot::trading_pair_of(pair_id).unwrap();
+
+            assert_noop!(
+                XSpot::set_kline_intervals(Origin::root(), vec![10, 0]),
+                Error::<Test>::InvalidKlineInterval
+            );
+            assert_noop!(
+                XSpot::set_kline_intervals(Origin::root(), vec![10, 20, 10]),
+                Error::<Test>::InvalidKlineInterval
+            );
+            assert_ok!(XSpot::set_kline_intervals(Origin::root(), vec![10]));
+
+            t_issue_pcx(1, 3_000_000);
+            t_generic_issue(trading_pair.quote(), 2, 4_000);
+
+            assert_ok!(t_put_order_sell(1, pair_id, 1_000_000, 1_000_100));
+            assert_ok!(t_put_order_sell(1, pair_id, 1_000_000, 1_000_200));
+            assert_ok!(t_put_order_sell(1, pair_id, 1_000_000, 1_000_300));
+
+            // Block 1 falls into the kline starting at block 0.
+            assert_ok!(t_put_order_buy(2, pair_id, 1_000_000, 1_000_200));
+            assert_ok!(t_put_order_buy(2, pair_id, 1_000_000, 1_000_200));
+
+            System::set_block_number(12);
+            assert_ok!(t_put_order_buy(2, pair_id, 1_000_000, 1_000_300));
+
+            assert_eq!(
+                XSpot::klines(pair_id, 10, 0, 100),
+                vec![
+                    Kline {
+                        start_at: 0,
+                        open: 1_000_100,
+                        high: 1_000_200,
+                        low: 1_000_100,
+                        close: 1_000_200,
+                        volume: 2_000_000,
+                    },
+                    Kline::new(10, 1_000_300, 1_000_000),
+                ]
+            );
+            assert_eq!(
+                XSpot::klines(pair_id, 10, 5, 100),
+                vec![Kline::new(10, 1_000_300, 1_000_000)]
+            );
+            assert!(XSpot::klines(pair_id, 20, 0, 100).is_empty());
+        });
+    }
 }
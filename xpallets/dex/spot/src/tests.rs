@@ -4,7 +4,13 @@
 
 use sp_std::collections::btree_map::BTreeMap;
 
-use frame_support::{assert_noop, assert_ok};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::migration::{put_storage_value, StorageIterator},
+    traits::{OnInitialize, OnRuntimeUpgrade},
+    IterableStorageDoubleMap, StorageHasher, Twox64Concat,
+};
 use xpallet_assets::AssetType;
 
 use super::mock::*;
@@ -37,12 +43,12 @@ fn t_trading_pair_of(idx: TradingPairId) -> TradingPairProfile {
     XSpot::trading_pair_of(idx).unwrap()
 }
 
-fn t_put_order_buy(
+pub(crate) fn t_put_order_buy(
     who: AccountId,
     pair_idx: TradingPairId,
     amount: Balance,
     price: Price,
-) -> DispatchResult {
+) -> DispatchResultWithPostInfo {
     XSpot::put_order(
         Origin::signed(who),
         pair_idx,
@@ -50,6 +56,9 @@ fn t_put_order_buy(
         Side::Buy,
         amount,
         price,
+        TimeInForce::GoodTillCancel,
+        None,
+        None,
     )
 }
 
@@ -58,7 +67,7 @@ pub(crate) fn t_put_order_sell(
     pair_idx: TradingPairId,
     amount: Balance,
     price: Price,
-) -> DispatchResult {
+) -> DispatchResultWithPostInfo {
     XSpot::put_order(
         Origin::signed(who),
         pair_idx,
@@ -66,19 +75,75 @@ pub(crate) fn t_put_order_sell(
         Side::Sell,
         amount,
         price,
+        TimeInForce::GoodTillCancel,
+        None,
+        None,
     )
 }
 
-fn t_cancel_order(who: AccountId, pair_id: TradingPairId, order_id: OrderId) -> DispatchResult {
-    XSpot::cancel_order(Origin::signed(who), pair_id, order_id)
+fn t_put_order_with_time_in_force(
+    who: AccountId,
+    pair_idx: TradingPairId,
+    side: Side,
+    amount: Balance,
+    price: Price,
+    time_in_force: TimeInForce,
+) -> DispatchResultWithPostInfo {
+    XSpot::put_order(
+        Origin::signed(who),
+        pair_idx,
+        OrderType::Limit,
+        side,
+        amount,
+        price,
+        time_in_force,
+        None,
+        None,
+    )
 }
 
-pub(crate) fn t_set_handicap(pair_idx: TradingPairId, highest_bid: Price, lowest_ask: Price) {
-    assert_ok!(XSpot::set_handicap(
-        Origin::root(),
+fn t_put_order_buy_with_stp(
+    who: AccountId,
+    pair_idx: TradingPairId,
+    amount: Balance,
+    price: Price,
+    self_trade_prevention: Option<SelfTradePrevention>,
+) -> DispatchResultWithPostInfo {
+    XSpot::put_order(
+        Origin::signed(who),
         pair_idx,
-        Handicap::new(highest_bid, lowest_ask)
-    ));
+        OrderType::Limit,
+        Side::Buy,
+        amount,
+        price,
+        TimeInForce::GoodTillCancel,
+        None,
+        self_trade_prevention,
+    )
+}
+
+fn t_put_market_order(
+    who: AccountId,
+    pair_idx: TradingPairId,
+    side: Side,
+    amount: Balance,
+    price: Price,
+) -> DispatchResultWithPostInfo {
+    XSpot::put_order(
+        Origin::signed(who),
+        pair_idx,
+        OrderType::Market,
+        side,
+        amount,
+        price,
+        TimeInForce::GoodTillCancel,
+        None,
+        None,
+    )
+}
+
+fn t_cancel_order(who: AccountId, pair_id: TradingPairId, order_id: OrderId) -> DispatchResult {
+    XSpot::cancel_order(Origin::signed(who), pair_id, order_id)
 }
 
 fn t_set_price_fluctution(pair_idx: TradingPairId, new: PriceFluctuation) {
@@ -154,8 +219,6 @@ fn put_order_reserve_should_work() {
         let who = 1;
         let trading_pair = XSpot::trading_pair_of(pair_id).unwrap();
 
-        t_set_handicap(pair_id, 1_000_000, 1_100_000);
-
         // Reserve asset.
         t_generic_issue(trading_pair.quote(), who, 10);
         assert_eq!(t_generic_free_balance(who, trading_pair.quote()), 10);
@@ -208,7 +271,6 @@ fn put_order_reserve_should_work() {
 fn inject_order_should_work() {
     ExtBuilder::default().build_and_execute(|| {
         let trading_pair = XSpot::trading_pair_of(0).unwrap();
-        t_set_handicap(0, 1_000_000, 1_100_000);
         assert_ok!(XAssets::issue(&trading_pair.quote(), &1, 10));
 
         assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_100,));
@@ -232,35 +294,27 @@ fn price_too_high_or_too_low_should_not_work() {
     ExtBuilder::default().build_and_execute(|| {
         let trading_pair = XSpot::trading_pair_of(0).unwrap();
 
-        //  Buy: (~, 1_100_000 + 1_100_000 * 10%) = 1_210_000]
-        // Sell: [1_000_000 * (1 - 10%) = 900_000, ~)
-        t_set_handicap(0, 1_000_000, 1_100_000);
-
         assert_ok!(XAssets::issue(&trading_pair.quote(), &1, 10));
+        t_issue_pcx(1, 1000);
 
-        // put order without matching
-        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_200));
-
-        t_set_handicap(0, 1_000_000, 1_100_000);
+        // There is no restriction on the price when the order book is empty.
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_sell(1, 0, 1000, 1_100_000));
 
+        //  Buy: (~, 1_100_000 + 1000 * 100 = 1_200_000]
+        // Sell: [1_000_000 - 1000 * 100 = 900_000, ~)
         assert_noop!(
             t_put_order_buy(1, 0, 1000, 2_210_000,),
             Error::<Test>::TooHighBidPrice
         );
-
-        t_set_handicap(0, 1_000_000, 1_100_000);
-        t_issue_pcx(1, 1000);
-
-        assert_ok!(t_put_order_sell(1, 0, 1000, 1_210_000));
-
         assert_noop!(
             t_put_order_sell(1, 0, 1000, 890_000,),
             Error::<Test>::TooLowAskPrice
         );
 
-        assert_eq!(XSpot::price_fluctuation_of(0), 100);
-        t_set_price_fluctution(0, 200);
-        assert_eq!(XSpot::price_fluctuation_of(0), 200);
+        assert_eq!(XSpot::price_fluctuation_of(0), 1000);
+        t_set_price_fluctution(0, 2000);
+        assert_eq!(XSpot::price_fluctuation_of(0), 2000);
     })
 }
 
@@ -273,21 +327,258 @@ fn update_handicap_should_work() {
         t_issue_pcx(2, 2000);
         t_issue_pcx(3, 2000);
 
+        assert_eq!(XSpot::handicap_of(0), Handicap::default());
+
         assert_ok!(t_put_order_buy(1, 0, 1000, 1_210_000,));
 
-        assert_eq!(XSpot::handicap_of(0).highest_bid, 1_210_000);
+        assert_eq!(XSpot::handicap_of(0).highest_bid, Some(1_210_000));
 
         assert_ok!(t_put_order_buy(1, 0, 1000, 1_310_000,));
 
-        assert_eq!(XSpot::handicap_of(0).highest_bid, 1_310_000);
+        assert_eq!(XSpot::handicap_of(0).highest_bid, Some(1_310_000));
 
         assert_ok!(t_put_order_sell(2, 0, 500, 1_310_000 - 100));
 
-        assert_eq!(XSpot::handicap_of(0).lowest_ask, 0);
+        assert_eq!(XSpot::handicap_of(0).highest_bid, Some(1_310_000));
+        assert_eq!(XSpot::handicap_of(0).lowest_ask, None);
 
         assert_ok!(t_put_order_sell(2, 0, 800, 1_3200_000));
 
-        assert_eq!(XSpot::handicap_of(0).lowest_ask, 1_3200_000);
+        assert_eq!(XSpot::handicap_of(0).lowest_ask, Some(1_3200_000));
+
+        // The best bid is filled, the handicap falls back to the next price level.
+        assert_ok!(t_put_order_sell(3, 0, 500, 1_310_000));
+
+        assert_eq!(XSpot::handicap_of(0).highest_bid, Some(1_210_000));
+
+        assert_ok!(t_cancel_order(1, 0, 0));
+        assert_ok!(t_cancel_order(2, 0, 1));
+
+        assert_eq!(XSpot::handicap_of(0), Handicap::default());
+    })
+}
+
+#[test]
+fn price_levels_should_be_capped_on_each_side() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_generic_issue(XSpot::trading_pair_of(0).unwrap().quote(), 1, 20_000);
+        t_issue_pcx(2, 1_000);
+
+        let max = MAX_PRICE_LEVELS_PER_SIDE as Price;
+        for i in 0..max {
+            assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000 + i * 100));
+        }
+        assert_eq!(
+            XSpot::price_levels_of(0, Side::Buy).len(),
+            MAX_PRICE_LEVELS_PER_SIDE
+        );
+
+        // No more price level can be indexed on the side.
+        assert_noop!(
+            t_put_order_buy(1, 0, 1000, 1_000_000 + max * 100),
+            Error::<Test>::TooManyPriceLevels
+        );
+
+        // The non-empty price levels are still open.
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_eq!(
+            XSpot::quotations_of(0, 1_000_000),
+            vec![(1, 0), (1, max as OrderId)]
+        );
+
+        // The other side is not affected.
+        assert_ok!(t_put_order_sell(2, 0, 1000, 1_200_000));
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![1_200_000]);
+    })
+}
+
+#[test]
+fn handicap_should_be_rebuilt_on_runtime_upgrade() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_generic_issue(XSpot::trading_pair_of(0).unwrap().quote(), 1, 10);
+        t_issue_pcx(2, 2000);
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_sell(2, 0, 1000, 1_000_200));
+        let handicap = XSpot::handicap_of(0);
+
+        // The handicap of the previous storage version is a pair of prices.
+        assert_eq!(XSpot::storage_version(), Releases::V2_0_0);
+        t_downgrade_orders_and_quotations();
+        frame_support::storage::unhashed::put_raw(
+            &HandicapOf::<Test>::hashed_key_for(0),
+            &(1_000_000 as Price, 1_000_200 as Price).encode(),
+        );
+        assert_eq!(XSpot::handicap_of(0), Handicap::default());
+
+        XSpot::on_runtime_upgrade();
+        assert_eq!(XSpot::handicap_of(0), handicap);
+        assert_eq!(XSpot::storage_version(), Releases::V2_0_0);
+    })
+}
+
+/// Overwrites the order with the layout of `Releases::V1_0_0`.
+fn t_put_old_order_info(order: &OrderInfo<Test>) {
+    let old = migration::OldOrder {
+        props: migration::OldOrderProperty {
+            id: order.id(),
+            side: order.side(),
+            price: order.price(),
+            amount: order.amount(),
+            pair_id: order.pair_id(),
+            submitter: order.submitter(),
+            order_type: order.order_type(),
+            created_at: order.created_at(),
+        },
+        status: order.status,
+        remaining: order.remaining,
+        executed_indices: order.executed_indices.clone(),
+        already_filled: order.already_filled,
+        last_update_at: order.last_update_at,
+    };
+    frame_support::storage::unhashed::put_raw(
+        &OrderInfoOf::<Test>::hashed_key_for(order.submitter(), order.id()),
+        &old.encode(),
+    );
+}
+
+/// Puts the quotations at `price` into `QuotationsOf` of `Releases::V1_0_0`.
+fn t_put_old_quotations(
+    pair_id: TradingPairId,
+    price: Price,
+    quotations: Vec<(AccountId, OrderId)>,
+) {
+    let hash = [
+        Twox64Concat::hash(&pair_id.encode()),
+        Twox64Concat::hash(&price.encode()),
+    ]
+    .concat();
+    put_storage_value(b"XSpot", b"QuotationsOf", &hash, quotations);
+}
+
+/// Moves all the orders and quotations back to the storage of `Releases::V1_0_0`.
+fn t_downgrade_orders_and_quotations() {
+    StorageVersion::put(Releases::V1_0_0);
+    for order in OrderInfoOf::<Test>::iter_values().collect::<Vec<_>>() {
+        t_put_old_order_info(&order);
+        OpenOrdersOf::<Test>::remove((order.submitter(), order.pair_id()), order.id());
+    }
+    for (pair_id, price, _) in QuotationQueueOf::<Test>::iter().collect::<Vec<_>>() {
+        let quotations = XSpot::quotations_of(pair_id, price);
+        for (who, order_id) in quotations.iter() {
+            QuotationNodeOf::<Test>::remove(who, order_id);
+        }
+        QuotationQueueOf::<Test>::remove(pair_id, price);
+        t_put_old_quotations(pair_id, price, quotations);
+    }
+    for pair_id in 0..TradingPairCount::get() {
+        PriceLevelsOf::<Test>::remove(pair_id, Side::Buy);
+        PriceLevelsOf::<Test>::remove(pair_id, Side::Sell);
+    }
+}
+
+#[test]
+fn quotations_should_be_migrated_on_runtime_upgrade() {
+    ExtBuilder::default().build_and_execute(|| {
+        let quote = XSpot::trading_pair_of(0).unwrap().quote();
+        t_generic_issue(quote, 1, 10);
+        t_generic_issue(quote, 2, 10);
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_buy(2, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        let quotations = XSpot::quotations_of(0, 1_000_000);
+        assert_eq!(quotations, vec![(1, 0), (2, 0), (1, 1)]);
+
+        t_downgrade_orders_and_quotations();
+        // The quotation of an order which no longer exists.
+        t_put_old_quotations(0, 1_000_000, vec![(1, 0), (3, 0), (2, 0), (1, 1)]);
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![]);
+
+        XSpot::on_runtime_upgrade();
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), quotations);
+        assert_eq!(XSpot::quotation_node_of(3, 0), None);
+        assert!(
+            StorageIterator::<Vec<(AccountId, OrderId)>>::new(b"XSpot", b"QuotationsOf")
+                .next()
+                .is_none()
+        );
+
+        // The migrated quotations are matched in the time priority.
+        t_issue_pcx(3, 1000);
+        assert_ok!(t_put_order_sell(3, 0, 1000, 1_000_000));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![(2, 0), (1, 1)]);
+    })
+}
+
+#[test]
+fn price_levels_should_be_backfilled_on_runtime_upgrade() {
+    ExtBuilder::default().build_and_execute(|| {
+        let quote = XSpot::trading_pair_of(0).unwrap().quote();
+        t_generic_issue(quote, 1, 20);
+        t_generic_issue(quote, 2, 10);
+        t_issue_pcx(3, 3000);
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_100));
+        assert_ok!(t_put_order_buy(2, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_sell(3, 0, 1000, 1_000_300));
+        assert_ok!(t_put_order_sell(3, 0, 1000, 1_000_200));
+        let handicap = XSpot::handicap_of(0);
+
+        t_downgrade_orders_and_quotations();
+        frame_support::storage::unhashed::put_raw(
+            &HandicapOf::<Test>::hashed_key_for(0),
+            &(1_000_100 as Price, 1_000_200 as Price).encode(),
+        );
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![]);
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![]);
+
+        XSpot::on_runtime_upgrade();
+        assert_eq!(
+            XSpot::price_levels_of(0, Side::Buy),
+            vec![1_000_000, 1_000_100]
+        );
+        assert_eq!(
+            XSpot::price_levels_of(0, Side::Sell),
+            vec![1_000_200, 1_000_300]
+        );
+        assert_eq!(XSpot::handicap_of(0), handicap);
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![(2, 0), (1, 1)]);
+        assert_eq!(XSpot::integrity_check(), vec![]);
+
+        // The matching goes on level by level from the migrated orders.
+        assert_ok!(t_put_order_sell(3, 0, 1000, 1_000_000));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![1_000_000]);
+        assert_eq!(XSpot::handicap_of(0).highest_bid, Some(1_000_000));
+    })
+}
+
+#[test]
+fn order_info_should_be_translated_on_runtime_upgrade() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_generic_issue(XSpot::trading_pair_of(0).unwrap().quote(), 1, 10);
+
+        assert_ok!(t_put_order_buy_with_stp(
+            1,
+            0,
+            1000,
+            1_000_000,
+            Some(SelfTradePrevention::CancelBoth)
+        ));
+        let order = XSpot::order_info_of(1, 0).unwrap();
+
+        StorageVersion::put(Releases::V1_0_0);
+        t_put_old_order_info(&order);
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+
+        XSpot::on_runtime_upgrade();
+        let mut expected = order;
+        expected.props.self_trade_prevention = SelfTradePrevention::None;
+        assert_eq!(XSpot::order_info_of(1, 0), Some(expected));
+        assert_eq!(XSpot::storage_version(), Releases::V2_0_0);
     })
 }
 
@@ -296,8 +587,6 @@ fn match_order_should_work() {
     ExtBuilder::default().build_and_execute(|| {
         let trading_pair = XSpot::trading_pair_of(0).unwrap();
 
-        t_set_handicap(0, 1_000_000, 1_100_000);
-
         t_generic_issue(trading_pair.quote(), 1, 10);
         t_issue_pcx(2, 2000);
         t_issue_pcx(3, 2000);
@@ -332,8 +621,6 @@ fn cancel_order_should_work() {
     ExtBuilder::default().build_and_execute(|| {
         let trading_pair = XSpot::trading_pair_of(0).unwrap();
 
-        t_set_handicap(0, 1_000_000, 1_100_000);
-
         assert_ok!(XAssets::issue(&trading_pair.quote(), &1, 10));
         t_issue_pcx(1, 2000);
         t_issue_pcx(2, 2000);
@@ -507,3 +794,1511 @@ fn quotations_order_should_be_preserved_when_removing_orders_and_quotations() {
         assert_eq!(XSpot::quotations_of(0, 2_000_000), [(2, 1), (5, 0), (6, 0)]);
     })
 }
+
+#[test]
+fn quotations_should_be_linked_in_the_time_priority() {
+    ExtBuilder::default().build_and_execute(|| {
+        for who in 1..=4 {
+            t_issue_pcx(who, 1_000);
+            assert_ok!(t_put_order_sell(who, 0, 1_000, 1_000_100));
+        }
+        assert_eq!(
+            XSpot::quotations_of(0, 1_000_100),
+            [(1, 0), (2, 0), (3, 0), (4, 0)]
+        );
+
+        // Remove the orders in the middle, at the head and at the tail.
+        assert_ok!(t_cancel_order(2, 0, 0));
+        assert_ok!(t_cancel_order(1, 0, 0));
+        assert_ok!(t_cancel_order(4, 0, 0));
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), [(3, 0)]);
+        assert_eq!(
+            XSpot::quotation_queue_of(0, 1_000_100),
+            Some(QuotationQueue {
+                head: (3, 0),
+                tail: (3, 0)
+            })
+        );
+        assert_eq!(XSpot::quotation_node_of(1, 0), None);
+
+        t_issue_pcx(1, 1_000);
+        assert_ok!(t_put_order_sell(1, 0, 1_000, 1_000_100));
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), [(3, 0), (1, 1)]);
+        assert_eq!(
+            XSpot::quotation_node_of(1, 1),
+            Some(QuotationNode {
+                prev: Some((3, 0)),
+                next: None
+            })
+        );
+
+        assert_ok!(t_cancel_order(3, 0, 0));
+        assert_ok!(t_cancel_order(1, 0, 1));
+        assert_eq!(XSpot::quotation_queue_of(0, 1_000_100), None);
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![]);
+    })
+}
+
+#[test]
+fn price_levels_should_be_maintained_when_matching_orders() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+
+        t_generic_issue(trading_pair.quote(), 1, 10);
+        t_issue_pcx(2, 2000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_000_500));
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_000_100));
+        assert_eq!(
+            XSpot::price_levels_of(0, Side::Sell),
+            vec![1_000_100, 1_000_500]
+        );
+
+        assert_ok!(t_put_order_buy(1, 0, 2_000, 900_000));
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![900_000]);
+
+        // Jump from 1_000_100 to 1_000_500 directly.
+        assert_ok!(t_put_order_buy(1, 0, 2_000, 1_000_500));
+
+        assert_eq!(XSpot::order_info_of(2, 0), None);
+        assert_eq!(XSpot::order_info_of(2, 1), None);
+        assert_eq!(XSpot::order_info_of(1, 1), None);
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![]);
+        assert_eq!(XSpot::quotations_of(0, 1_000_500), vec![]);
+
+        assert_ok!(t_cancel_order(1, 0, 0));
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![]);
+    })
+}
+
+#[test]
+fn market_buy_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_issue_pcx(2, 2_000_000);
+        t_generic_issue(quote, 1, 1_500);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_100));
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_500));
+
+        // Spend 1500 quote currency at most, the price can not be higher than 1_000_500.
+        assert_ok!(t_put_market_order(1, 0, Side::Buy, 1_500, 1_000_500));
+
+        // The market order never rests in the order book.
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::order_info_of(2, 0), None);
+        assert_eq!(XSpot::order_info_of(2, 1).unwrap().already_filled, 499_750);
+        assert_eq!(XSpot::quotations_of(0, 1_000_500), vec![(2, 1)]);
+
+        // 1000 at 1_000_100 + 499 at 1_000_500, the dust is refunded.
+        assert_eq!(t_generic_free_balance(1, trading_pair.base()), 1_499_750);
+        assert_eq!(t_generic_free_balance(1, quote), 1);
+        assert_eq!(
+            XAssets::asset_balance_of(&1, &quote, AssetType::ReservedDexSpot),
+            0
+        );
+    })
+}
+
+#[test]
+fn market_sell_should_refund_the_unfilled() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_generic_issue(quote, 3, 1_000);
+        t_issue_pcx(4, 1_500_000);
+
+        assert_ok!(t_put_order_buy(3, 0, 1_000_000, 1_000_000));
+
+        assert_noop!(
+            t_put_market_order(4, 0, Side::Sell, 1_500_000, 890_000),
+            Error::<Test>::TooLowAskPrice
+        );
+
+        assert_ok!(t_put_market_order(4, 0, Side::Sell, 1_500_000, 999_000));
+
+        assert_eq!(XSpot::order_info_of(3, 0), None);
+        assert_eq!(XSpot::order_info_of(4, 0), None);
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![]);
+        assert_eq!(t_generic_free_balance(4, quote), 1_000);
+        assert_eq!(t_generic_free_balance(4, trading_pair.base()), 500_000);
+        assert_eq!(XSpot::native_reserves(&4), 0);
+
+        // Nothing to match, all the reserved are refunded.
+        assert_ok!(t_put_market_order(4, 0, Side::Sell, 500_000, 999_000));
+        assert_eq!(XSpot::order_info_of(4, 1), None);
+        assert_eq!(t_generic_free_balance(4, trading_pair.base()), 500_000);
+        assert_eq!(XSpot::native_reserves(&4), 0);
+    })
+}
+
+#[test]
+fn immediate_or_cancel_should_never_rest() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_generic_issue(quote, 1, 10);
+        t_issue_pcx(2, 2000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_000_100));
+
+        // Nothing to match.
+        assert_ok!(t_put_order_with_time_in_force(
+            1,
+            0,
+            Side::Buy,
+            2_000,
+            900_000,
+            TimeInForce::ImmediateOrCancel
+        ));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![]);
+        assert_eq!(t_generic_free_balance(1, quote), 10);
+
+        // Half of the order is filled, the rest is canceled.
+        assert_ok!(t_put_order_with_time_in_force(
+            1,
+            0,
+            Side::Buy,
+            2_000,
+            1_000_100,
+            TimeInForce::ImmediateOrCancel
+        ));
+        assert_eq!(XSpot::order_info_of(1, 1), None);
+        assert_eq!(XSpot::order_info_of(2, 0), None);
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![]);
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![]);
+        assert_eq!(t_generic_free_balance(1, trading_pair.base()), 1_000);
+        assert_eq!(t_generic_free_balance(1, quote), 9);
+    })
+}
+
+#[test]
+fn fill_or_kill_should_be_filled_completely_or_canceled() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_generic_issue(quote, 1, 10);
+        t_issue_pcx(2, 2000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_000_100));
+
+        assert_ok!(t_put_order_with_time_in_force(
+            1,
+            0,
+            Side::Buy,
+            2_000,
+            1_000_100,
+            TimeInForce::FillOrKill
+        ));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::order_info_of(2, 0).unwrap().already_filled, 0);
+        assert_eq!(t_generic_free_balance(1, quote), 10);
+
+        assert_ok!(t_put_order_with_time_in_force(
+            1,
+            0,
+            Side::Buy,
+            1_000,
+            1_000_100,
+            TimeInForce::FillOrKill
+        ));
+        assert_eq!(XSpot::order_info_of(1, 1), None);
+        assert_eq!(XSpot::order_info_of(2, 0), None);
+        assert_eq!(t_generic_free_balance(1, trading_pair.base()), 1_000);
+    })
+}
+
+#[test]
+fn post_only_should_not_cross_the_handicap() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+
+        t_generic_issue(trading_pair.quote(), 1, 10);
+        t_issue_pcx(2, 2000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_000_100));
+
+        assert_noop!(
+            t_put_order_with_time_in_force(
+                1,
+                0,
+                Side::Buy,
+                1_000,
+                1_000_100,
+                TimeInForce::PostOnly
+            ),
+            Error::<Test>::PostOnlyOrderWouldMatch
+        );
+        assert_noop!(
+            XSpot::put_order(
+                Origin::signed(1),
+                0,
+                OrderType::Market,
+                Side::Buy,
+                1,
+                1_000_100,
+                TimeInForce::PostOnly,
+                None,
+                None
+            ),
+            Error::<Test>::InvalidTimeInForce
+        );
+
+        assert_ok!(t_put_order_with_time_in_force(
+            1,
+            0,
+            Side::Buy,
+            1_000,
+            1_000_000,
+            TimeInForce::PostOnly
+        ));
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![(1, 0)]);
+    })
+}
+
+#[test]
+fn expired_orders_should_be_canceled() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+
+        t_issue_pcx(1, 1000);
+
+        assert_noop!(
+            XSpot::put_order(
+                Origin::signed(1),
+                0,
+                OrderType::Limit,
+                Side::Sell,
+                100,
+                1_100_000,
+                TimeInForce::GoodTillCancel,
+                Some(1),
+                None
+            ),
+            Error::<Test>::InvalidExpiry
+        );
+
+        assert_ok!(XSpot::put_order(
+            Origin::signed(1),
+            0,
+            OrderType::Limit,
+            Side::Sell,
+            100,
+            1_100_000,
+            TimeInForce::GoodTillCancel,
+            Some(3),
+            None
+        ));
+        assert_eq!(XSpot::expiring_orders_at(3), vec![(1, 0)]);
+        assert_eq!(XSpot::native_reserves(&1), 100);
+
+        System::set_block_number(2);
+        XSpot::on_initialize(2);
+        assert!(XSpot::order_info_of(1, 0).is_some());
+
+        System::set_block_number(3);
+        XSpot::on_initialize(3);
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::expiring_orders_at(3), vec![]);
+        assert_eq!(XSpot::quotations_of(0, 1_100_000), vec![]);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(t_generic_free_balance(1, trading_pair.base()), 1000);
+    })
+}
+
+#[test]
+fn expirations_should_be_postponed_when_exceeding_the_limit() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 1000);
+
+        for _ in 0..(MAX_EXPIRATIONS_PER_BLOCK + 1) {
+            assert_ok!(XSpot::put_order(
+                Origin::signed(1),
+                0,
+                OrderType::Limit,
+                Side::Sell,
+                1,
+                1_100_000,
+                TimeInForce::GoodTillCancel,
+                Some(2),
+                None
+            ));
+        }
+
+        System::set_block_number(2);
+        XSpot::on_initialize(2);
+        assert_eq!(
+            XSpot::expiring_orders_at(3),
+            vec![(1, MAX_EXPIRATIONS_PER_BLOCK as OrderId)]
+        );
+        assert_eq!(XSpot::native_reserves(&1), 1);
+
+        System::set_block_number(3);
+        XSpot::on_initialize(3);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(XSpot::quotations_of(0, 1_100_000), vec![]);
+    })
+}
+
+#[test]
+fn trading_fee_should_be_deducted_from_the_received() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let base = trading_pair.base();
+        let quote = trading_pair.quote();
+        let fee_receiver = 100;
+
+        assert_ok!(XSpot::set_trading_fee(
+            Origin::root(),
+            0,
+            Permill::from_percent(1),
+            Permill::from_percent(2)
+        ));
+        assert_ok!(XSpot::set_fee_destination(
+            Origin::root(),
+            Some(fee_receiver)
+        ));
+
+        t_issue_pcx(1, 1_000_000);
+        t_generic_issue(quote, 2, 1_000);
+        t_issue_pcx(fee_receiver, 1);
+
+        // Maker sells 1_000_000 base at 1_000_000 for 1_000 quote.
+        assert_ok!(t_put_order_sell(1, 0, 1_000_000, 1_000_000));
+        assert_ok!(t_put_order_buy(2, 0, 1_000_000, 1_000_000));
+
+        // The maker pays 1% of the quote received.
+        assert_eq!(t_generic_free_balance(1, quote), 990);
+        assert_eq!(t_generic_free_balance(fee_receiver, quote), 10);
+        // The taker pays 2% of the base received.
+        assert_eq!(t_generic_free_balance(2, base), 980_000);
+        assert_eq!(t_generic_free_balance(fee_receiver, base), 20_001);
+    })
+}
+
+#[test]
+fn trading_fee_should_be_waived_if_it_can_not_be_delivered() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let base = trading_pair.base();
+        let quote = trading_pair.quote();
+        // The fee receiver doesn't exist.
+        let fee_receiver = 100;
+
+        assert_ok!(XSpot::set_trading_fee(
+            Origin::root(),
+            0,
+            Permill::from_percent(1),
+            Permill::from_percent(2)
+        ));
+        assert_ok!(XSpot::set_fee_destination(
+            Origin::root(),
+            Some(fee_receiver)
+        ));
+
+        t_issue_pcx(1, 1_000_000);
+        t_generic_issue(quote, 2, 1_000);
+
+        assert_ok!(t_put_order_sell(1, 0, 1_000_000, 1_000_000));
+        assert_ok!(t_put_order_buy(2, 0, 1_000_000, 1_000_000));
+
+        // The native token can't be moved to the account that doesn't exist,
+        // the fee of the taker is waived.
+        assert_eq!(t_generic_free_balance(2, base), 1_000_000);
+        assert_eq!(t_generic_free_balance(fee_receiver, base), 0);
+        // The foreign asset is moved as usual.
+        assert_eq!(t_generic_free_balance(1, quote), 990);
+        assert_eq!(t_generic_free_balance(fee_receiver, quote), 10);
+
+        let executed = XSpot::trade_history_of(0, 0).unwrap();
+        assert_eq!(executed.maker_fee, 10);
+        assert_eq!(executed.taker_fee, 0);
+    })
+}
+
+#[test]
+fn self_trade_prevention_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_issue_pcx(1, 1_000);
+        t_generic_issue(quote, 1, 10);
+        t_issue_pcx(2, 1_000);
+
+        // CancelTaker by default, the maker order stays untouched.
+        assert_ok!(t_put_order_sell(1, 0, 1_000, 1_000_100));
+        assert_ok!(t_put_order_buy(1, 0, 1_000, 1_000_100));
+        assert_eq!(XSpot::order_info_of(1, 1), None);
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), vec![(1, 0)]);
+        assert_eq!(t_generic_free_balance(1, quote), 10);
+        assert_eq!(XSpot::native_reserves(&1), 1_000);
+
+        // CancelMaker, the taker goes on matching with the orders of others.
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_000_100));
+        assert_ok!(t_put_order_buy_with_stp(
+            1,
+            0,
+            1_000,
+            1_000_100,
+            Some(SelfTradePrevention::CancelMaker)
+        ));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::order_info_of(1, 2), None);
+        assert_eq!(XSpot::order_info_of(2, 0), None);
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), vec![]);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(t_generic_free_balance(1, trading_pair.base()), 2_000);
+
+        // CancelBoth as the default policy of the trading pair.
+        assert_ok!(XSpot::set_self_trade_prevention(
+            Origin::root(),
+            0,
+            SelfTradePrevention::CancelBoth
+        ));
+        assert_ok!(t_put_order_sell(1, 0, 1_000, 1_000_100));
+        assert_ok!(t_put_order_buy(1, 0, 1_000, 1_000_100));
+        assert_eq!(XSpot::order_info_of(1, 3), None);
+        assert_eq!(XSpot::order_info_of(1, 4), None);
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), vec![]);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(t_generic_free_balance(1, trading_pair.base()), 2_000);
+
+        // None, the order is matched with the own order like the others.
+        assert_ok!(t_put_order_sell(1, 0, 1_000, 1_000_100));
+        assert_ok!(t_put_order_buy_with_stp(
+            1,
+            0,
+            1_000,
+            1_000_100,
+            Some(SelfTradePrevention::None)
+        ));
+        assert_eq!(XSpot::order_info_of(1, 5), None);
+        assert_eq!(XSpot::order_info_of(1, 6), None);
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), vec![]);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(XSpot::trade_history_of(0, 1).unwrap().maker_order_id, 5);
+    })
+}
+
+fn t_limit_order_request(side: Side, amount: Balance, price: Price) -> OrderRequestOf<Test> {
+    OrderRequest {
+        pair_id: 0,
+        order_type: OrderType::Limit,
+        side,
+        amount,
+        price,
+        time_in_force: TimeInForce::GoodTillCancel,
+        expire_at: None,
+        self_trade_prevention: None,
+    }
+}
+
+#[test]
+fn batch_orders_should_be_put_and_canceled_atomically() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_issue_pcx(1, 1_000_000);
+        t_generic_issue(quote, 1, 1_000);
+
+        assert_noop!(
+            XSpot::put_orders(Origin::signed(1), vec![]),
+            Error::<Test>::InvalidBatchSize
+        );
+        assert_noop!(
+            XSpot::put_orders(
+                Origin::signed(1),
+                vec![
+                    t_limit_order_request(Side::Sell, 1_000_000, 1_100_000),
+                    t_limit_order_request(Side::Buy, 0, 1_000_000),
+                ]
+            ),
+            Error::<Test>::ZeroAmount
+        );
+
+        assert_ok!(XSpot::put_orders(
+            Origin::signed(1),
+            vec![
+                t_limit_order_request(Side::Sell, 1_000_000, 1_100_000),
+                t_limit_order_request(Side::Buy, 1_000_000, 1_000_000),
+            ]
+        ));
+        assert_eq!(XSpot::quotations_of(0, 1_100_000), vec![(1, 0)]);
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![(1, 1)]);
+        assert_eq!(XSpot::native_reserves(&1), 1_000_000);
+        assert_eq!(t_generic_free_balance(1, quote), 0);
+
+        assert_noop!(
+            XSpot::cancel_orders(Origin::signed(1), vec![(0, 0), (0, 2)]),
+            Error::<Test>::InvalidOrderId
+        );
+
+        assert_ok!(XSpot::cancel_orders(
+            Origin::signed(1),
+            vec![(0, 0), (0, 1)]
+        ));
+        assert_eq!(XSpot::quotations_of(0, 1_100_000), vec![]);
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![]);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(t_generic_free_balance(1, quote), 1_000);
+    })
+}
+
+#[test]
+fn replace_order_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_generic_issue(quote, 1, 3_000);
+
+        assert_ok!(t_put_order_buy(1, 0, 1_000_000, 1_000_000));
+        assert_eq!(t_generic_free_balance(1, quote), 2_000);
+
+        assert_noop!(
+            XSpot::replace_order(Origin::signed(1), 0, 0, 4_000_000, 1_000_000),
+            Error::<Test>::InsufficientBalance
+        );
+
+        assert_ok!(XSpot::replace_order(
+            Origin::signed(1),
+            0,
+            0,
+            2_000_000,
+            1_000_100
+        ));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::quotations_of(0, 1_000_000), vec![]);
+        assert_eq!(XSpot::quotations_of(0, 1_000_100), vec![(1, 1)]);
+        let order = XSpot::order_info_of(1, 1).unwrap();
+        assert_eq!(order.side(), Side::Buy);
+        assert_eq!(order.amount(), 2_000_000);
+        assert_eq!(
+            XAssets::asset_balance_of(&1, &quote, AssetType::ReservedDexSpot),
+            2_000
+        );
+        assert_eq!(t_generic_free_balance(1, quote), 1_000);
+    })
+}
+
+#[test]
+fn cancel_all_orders_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_issue_pcx(1, 1_000);
+        t_generic_issue(quote, 1, 10);
+        t_issue_pcx(2, 1_000);
+
+        assert_ok!(t_put_order_sell(1, 0, 500, 1_100_000));
+        assert_ok!(t_put_order_sell(1, 0, 500, 1_100_100));
+        assert_ok!(t_put_order_buy(1, 0, 1_000, 1_000_000));
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_100_000));
+
+        assert_ok!(XSpot::cancel_all_orders(Origin::signed(1), Some(0)));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::order_info_of(1, 1), None);
+        assert_eq!(XSpot::order_info_of(1, 2), None);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(t_generic_free_balance(1, quote), 10);
+        assert_eq!(XSpot::quotations_of(0, 1_100_000), vec![(2, 0)]);
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![1_100_000]);
+        assert_eq!(XSpot::price_levels_of(0, Side::Buy), vec![]);
+    })
+}
+
+#[test]
+fn cancel_all_orders_should_cancel_the_open_orders_in_batches() {
+    ExtBuilder::default().build_and_execute(|| {
+        let pcx = XSpot::trading_pair_of(0).unwrap().base();
+        // EOS/PCX
+        t_register_asset(EOS, b"EOS");
+        t_add_trading_pair(CurrencyPair::new(EOS, pcx), 9, 2, 2_000_000_000, true);
+
+        t_issue_pcx(1, 80_000);
+        t_generic_issue(EOS, 1, 80_000);
+        let put_orders = |pair_id, price| {
+            for _ in 0..80 {
+                assert_ok!(t_put_order_sell(1, pair_id, 1_000, price));
+            }
+        };
+        let open_orders = |pair_id| OpenOrdersOf::<Test>::iter_prefix((1, pair_id)).count();
+
+        put_orders(0, 1_100_000);
+        put_orders(2, 2_000_000_000);
+
+        // Only the orders on the given trading pair are touched.
+        assert_ok!(XSpot::cancel_all_orders(Origin::signed(1), Some(2)));
+        assert_eq!(open_orders(0), 80);
+        assert_eq!(open_orders(2), 0);
+        assert_eq!(XSpot::price_levels_of(2, Side::Sell), vec![]);
+        assert_eq!(t_generic_free_balance(1, EOS), 80_000);
+
+        // At most `MAX_ORDERS_PER_BATCH` orders are canceled in one call.
+        put_orders(2, 2_000_000_000);
+        assert_ok!(XSpot::cancel_all_orders(Origin::signed(1), None));
+        assert_eq!(open_orders(0) + open_orders(2), 160 - MAX_ORDERS_PER_BATCH);
+        assert_ok!(XSpot::cancel_all_orders(Origin::signed(1), None));
+        assert_eq!(open_orders(0) + open_orders(2), 0);
+        assert_eq!(OrderInfoOf::<Test>::iter_prefix_values(1).count(), 0);
+
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(t_generic_free_balance(1, EOS), 80_000);
+        assert_eq!(XSpot::integrity_check(), vec![]);
+    })
+}
+
+#[test]
+fn force_cancel_all_orders_should_refund_the_untradable_pair() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 1_000);
+        t_issue_pcx(2, 1_000);
+
+        assert_ok!(t_put_order_sell(1, 0, 1_000, 1_100_000));
+        assert_ok!(t_put_order_sell(2, 0, 1_000, 1_100_100));
+
+        let tick_decimals = XSpot::trading_pair_of(0).unwrap().tick_decimals;
+        assert_ok!(XSpot::update_trading_pair(
+            Origin::root(),
+            0,
+            tick_decimals,
+            false
+        ));
+
+        assert_noop!(
+            XSpot::cancel_all_orders(Origin::signed(1), Some(0)),
+            Error::<Test>::TradingPairUntradable
+        );
+        assert_ok!(XSpot::cancel_all_orders(Origin::signed(1), None));
+        assert!(XSpot::order_info_of(1, 0).is_some());
+
+        assert_noop!(
+            XSpot::force_cancel_all_orders(Origin::root(), None, None),
+            Error::<Test>::InvalidCancelTarget
+        );
+        assert_ok!(XSpot::force_cancel_all_orders(
+            Origin::root(),
+            None,
+            Some(0)
+        ));
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::order_info_of(2, 0), None);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(XSpot::native_reserves(&2), 0);
+        assert_eq!(XSpot::price_levels_of(0, Side::Sell), vec![]);
+    })
+}
+
+#[test]
+fn conditional_orders_should_be_activated_when_triggered() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let base = trading_pair.base();
+        let quote = trading_pair.quote();
+
+        t_generic_issue(quote, 1, 1_000);
+        t_issue_pcx(2, 2_000_000);
+        t_generic_issue(quote, 3, 1_000);
+        t_issue_pcx(4, 1_000_000);
+
+        // Buy stop at 1_000_200.
+        assert_ok!(XSpot::put_conditional_order(
+            Origin::signed(1),
+            0,
+            ConditionalOrderKind::StopLimit,
+            Side::Buy,
+            1_000_000,
+            1_000_300,
+            1_000_200
+        ));
+        assert_eq!(t_generic_free_balance(1, quote), 0);
+        assert_eq!(
+            XSpot::trigger_prices_of(0, TriggerDirection::Rise),
+            vec![1_000_200]
+        );
+
+        // Sell take-profit at 1_000_500.
+        assert_ok!(XSpot::put_conditional_order(
+            Origin::signed(4),
+            0,
+            ConditionalOrderKind::TakeProfit,
+            Side::Sell,
+            1_000_000,
+            1_000_500,
+            1_000_500
+        ));
+        assert_eq!(XSpot::native_reserves(&4), 1_000_000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_200));
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_300));
+
+        // The latest price rises to 1_000_200.
+        assert_ok!(t_put_order_buy(3, 0, 1_000_000, 1_000_200));
+        assert_eq!(XSpot::triggering_range_of(0), Some((1_000_200, 1_000_200)));
+
+        XSpot::collect_triggered_orders();
+        assert_eq!(XSpot::triggering_range_of(0), None);
+        assert_eq!(XSpot::triggered_orders(), vec![(1, 0)]);
+        assert_eq!(
+            XSpot::trigger_prices_of(0, TriggerDirection::Rise),
+            vec![1_000_500]
+        );
+
+        System::set_block_number(2);
+        XSpot::on_initialize(2);
+        assert_eq!(XSpot::triggered_orders(), vec![]);
+        assert_eq!(XSpot::triggered_queue_head(), 1);
+        assert_eq!(XSpot::conditional_order_info_of(1, 0), None);
+        // The activated order is filled at 1_000_300.
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(t_generic_free_balance(1, base), 1_000_000);
+
+        assert_noop!(
+            XSpot::cancel_conditional_order(Origin::signed(4), 1),
+            Error::<Test>::InvalidConditionalOrderId
+        );
+        assert_ok!(XSpot::cancel_conditional_order(Origin::signed(4), 0));
+        assert_eq!(XSpot::native_reserves(&4), 0);
+        assert_eq!(XSpot::trigger_prices_of(0, TriggerDirection::Rise), vec![]);
+    })
+}
+
+#[test]
+fn crossed_trigger_price_should_be_rejected() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 1_000_000);
+
+        // The latest price of genesis is 100_000.
+        assert_noop!(
+            XSpot::put_conditional_order(
+                Origin::signed(1),
+                0,
+                ConditionalOrderKind::StopLimit,
+                Side::Sell,
+                1_000_000,
+                1_000_000,
+                1_000_000
+            ),
+            Error::<Test>::InvalidTriggerPrice
+        );
+    })
+}
+
+#[test]
+fn trigger_price_crossed_since_last_collection_should_be_rejected() {
+    ExtBuilder::default().build_and_execute(|| {
+        let quote = XSpot::trading_pair_of(0).unwrap().quote();
+
+        t_issue_pcx(1, 3_000_000);
+        t_generic_issue(quote, 2, 3_000);
+
+        // The price rises to 1_000_300 and then falls to 1_000_100.
+        assert_ok!(t_put_order_sell(1, 0, 1_000_000, 1_000_300));
+        assert_ok!(t_put_order_buy(2, 0, 1_000_000, 1_000_300));
+        assert_ok!(t_put_order_buy(2, 0, 1_000_000, 1_000_100));
+        assert_ok!(t_put_order_sell(1, 0, 1_000_000, 1_000_100));
+        assert_eq!(XSpot::triggering_range_of(0), Some((1_000_100, 1_000_300)));
+
+        let put_take_profit = || {
+            XSpot::put_conditional_order(
+                Origin::signed(1),
+                0,
+                ConditionalOrderKind::TakeProfit,
+                Side::Sell,
+                1_000_000,
+                1_000_200,
+                1_000_200,
+            )
+        };
+        assert_noop!(put_take_profit(), Error::<Test>::InvalidTriggerPrice);
+
+        XSpot::collect_triggered_orders();
+        assert_eq!(XSpot::triggering_range_of(0), None);
+        assert_ok!(put_take_profit());
+    })
+}
+
+#[test]
+fn conditional_orders_per_trigger_price_should_be_capped() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 1_000_000);
+
+        // The latest price of genesis is 100_000.
+        let put_stop_limit = || {
+            XSpot::put_conditional_order(
+                Origin::signed(1),
+                0,
+                ConditionalOrderKind::StopLimit,
+                Side::Sell,
+                1_000,
+                90_000,
+                90_000,
+            )
+        };
+        for _ in 0..MAX_CONDITIONAL_ORDERS_PER_PRICE {
+            assert_ok!(put_stop_limit());
+        }
+        assert_noop!(put_stop_limit(), Error::<Test>::TooManyConditionalOrders);
+    })
+}
+
+#[test]
+fn order_size_limit_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 10_000_000);
+
+        assert_noop!(
+            XSpot::set_order_size_limit(Origin::signed(1), 0, 1_000_000, 100_000),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(XSpot::set_order_size_limit(
+            Origin::root(),
+            0,
+            1_000_000,
+            100_000
+        ));
+        assert_eq!(
+            XSpot::order_size_limit_of(0),
+            OrderSizeLimit::new(1_000_000, 100_000)
+        );
+        assert_noop!(
+            XSpot::set_order_size_limit(Origin::root(), 0, 1_050_000, 100_000),
+            Error::<Test>::InvalidLotSize
+        );
+
+        assert_noop!(
+            t_put_order_sell(1, 0, 900_000, 1_000_100),
+            Error::<Test>::AmountTooSmall
+        );
+        assert_noop!(
+            t_put_order_sell(1, 0, 1_050_000, 1_000_100),
+            Error::<Test>::InvalidLotSize
+        );
+        assert_ok!(t_put_order_sell(1, 0, 1_100_000, 1_000_100));
+
+        // The amount of market buy is measured in the quote currency.
+        t_generic_issue(XSpot::trading_pair_of(0).unwrap().quote(), 2, 1_000);
+        assert_ok!(t_put_market_order(2, 0, Side::Buy, 1_000, 1_000_100));
+        // 1_000 quote affords 999_900 base at 1_000_100, which is rounded down to the lot.
+        assert_eq!(XSpot::order_info_of(1, 0).unwrap().already_filled, 900_000);
+
+        assert_noop!(
+            XSpot::put_conditional_order(
+                Origin::signed(1),
+                0,
+                ConditionalOrderKind::StopLimit,
+                Side::Sell,
+                1_050_000,
+                1_000_000,
+                1_000_000
+            ),
+            Error::<Test>::InvalidLotSize
+        );
+
+        let pairs = XSpot::trading_pairs();
+        assert_eq!(pairs[0].order_size_limit.min_amount, 1_000_000);
+        assert_eq!(pairs[0].order_size_limit.lot_size, 100_000);
+    })
+}
+
+#[test]
+fn matching_should_stop_at_the_maker_cap() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 10_000_000);
+        t_generic_issue(XSpot::trading_pair_of(0).unwrap().quote(), 2, 1_000);
+
+        let makers = MAX_MAKERS_PER_ORDER as OrderId;
+        for _ in 0..=makers {
+            assert_ok!(t_put_order_sell(1, 0, 1_000, 1_000_100));
+        }
+
+        let post_info = t_put_order_buy(2, 0, 1_000 * (makers as Balance + 1), 1_000_100).unwrap();
+        assert_eq!(
+            post_info.actual_weight,
+            Some(<() as WeightInfo>::put_order(MAX_MAKERS_PER_ORDER))
+        );
+
+        // The remainder of the taker order is canceled once the cap is reached.
+        assert!(XSpot::order_info_of(2, 0).is_none());
+        assert!(XSpot::order_info_of(1, makers - 1).is_none());
+        assert!(XSpot::order_info_of(1, makers).is_some());
+        assert_eq!(XSpot::handicap_of(0).highest_bid, None);
+
+        // Only the maker orders touched are charged.
+        let post_info = t_put_order_buy(2, 0, 1_000, 1_000_100).unwrap();
+        assert_eq!(
+            post_info.actual_weight,
+            Some(<() as WeightInfo>::put_order(1))
+        );
+    })
+}
+
+#[test]
+fn integrity_check_should_pass_after_matching_and_canceling() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        t_generic_issue(quote, 1, 2_000);
+        t_issue_pcx(2, 2_000_000);
+        t_generic_issue(quote, 3, 1_000);
+
+        assert_ok!(t_put_order_buy(1, 0, 1_000_000, 1_000_000));
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_200));
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_300));
+        assert_ok!(XSpot::put_conditional_order(
+            Origin::signed(1),
+            0,
+            ConditionalOrderKind::StopLimit,
+            Side::Buy,
+            500_000,
+            1_000_400,
+            1_000_400
+        ));
+        assert_eq!(XSpot::integrity_check(), vec![]);
+
+        // Partially fill the ask at 1_000_200.
+        assert_ok!(t_put_order_buy(3, 0, 500_000, 1_000_200));
+        assert_eq!(XSpot::order_info_of(2, 0).unwrap().already_filled, 500_000);
+        assert_eq!(XSpot::integrity_check(), vec![]);
+
+        assert_ok!(t_cancel_order(1, 0, 0));
+        assert_ok!(t_cancel_order(2, 0, 1));
+        assert_eq!(XSpot::integrity_check(), vec![]);
+    })
+}
+
+#[test]
+fn integrity_check_should_detect_the_drift() {
+    ExtBuilder::default().build_and_execute(|| {
+        let pcx = XSpot::trading_pair_of(0).unwrap().base();
+
+        t_issue_pcx(2, 2_000_000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_200));
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_300));
+        assert_eq!(XSpot::integrity_check(), vec![]);
+
+        HandicapOf::<Test>::mutate(0, |handicap| handicap.lowest_ask = Some(1_000_100));
+        OrderInfoOf::<Test>::remove(2, 1);
+
+        assert_eq!(
+            XSpot::integrity_check(),
+            vec![
+                IntegrityViolation::DanglingQuotation(0, 1_000_300, 2, 1),
+                IntegrityViolation::HandicapMismatch(
+                    0,
+                    Side::Sell,
+                    Some(1_000_200),
+                    Some(1_000_100)
+                ),
+                IntegrityViolation::ReservedMismatch(2, pcx, 1_000_000, 2_000_000),
+            ]
+        );
+    })
+}
+
+const LP_ASSET: AssetId = 7777;
+
+fn t_register_asset(asset_id: AssetId, token: &[u8]) {
+    assert_ok!(xpallet_assets_registrar::Module::<Test>::register(
+        Origin::root(),
+        asset_id,
+        xpallet_assets::AssetInfo::new::<Test>(
+            token.to_vec(),
+            token.to_vec(),
+            xpallet_assets::Chain::ChainX,
+            8,
+            b"".to_vec(),
+        )
+        .unwrap(),
+        true,
+        false
+    ));
+}
+
+fn t_create_liquidity_pool(pair_id: TradingPairId, lp_asset_id: AssetId) -> DispatchResult {
+    t_register_asset(lp_asset_id, b"PCX-BTC-LP");
+    XSpot::create_liquidity_pool(
+        Origin::root(),
+        pair_id,
+        lp_asset_id,
+        Permill::from_parts(3_000),
+    )
+}
+
+#[test]
+fn liquidity_pool_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let btc = XSpot::trading_pair_of(0).unwrap().quote();
+
+        t_issue_pcx(1, 2_000_000);
+        t_generic_issue(btc, 1, 200_000);
+        t_issue_pcx(2, 2_000_000);
+        t_generic_issue(btc, 2, 200_000);
+        t_issue_pcx(3, 100_000);
+
+        assert_ok!(t_create_liquidity_pool(0, LP_ASSET));
+
+        // The first liquidity provider decides the price.
+        assert_ok!(XSpot::add_liquidity(
+            Origin::signed(1),
+            0,
+            1_000_000,
+            100_000,
+            0
+        ));
+        assert_eq!(XAssets::usable_balance(&1, &LP_ASSET), 316_227);
+
+        // The quote currency is deposited proportionally.
+        assert_noop!(
+            XSpot::add_liquidity(Origin::signed(2), 0, 500_000, 49_999, 0),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_noop!(
+            XSpot::add_liquidity(Origin::signed(2), 0, 500_000, 60_000, 158_114),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(XSpot::add_liquidity(
+            Origin::signed(2),
+            0,
+            500_000,
+            60_000,
+            158_113
+        ));
+        assert_eq!(XAssets::usable_balance(&2, &LP_ASSET), 158_113);
+        assert_eq!(t_generic_free_balance(2, btc), 150_000);
+
+        // Sell 10_000 PCX, 0.3% of which is charged as the fee.
+        assert_noop!(
+            XSpot::swap(Origin::signed(3), 0, Side::Sell, 10_000, 991),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(XSpot::swap(Origin::signed(3), 0, Side::Sell, 10_000, 990));
+        assert_eq!(t_generic_free_balance(3, xp_protocol::PCX), 90_000);
+        assert_eq!(t_generic_free_balance(3, btc), 990);
+
+        let pool = XSpot::liquidity_pool_of(0).unwrap();
+        assert_eq!(pool.base_reserve, 1_510_000);
+        assert_eq!(pool.quote_reserve, 149_010);
+        assert_eq!(pool.total_shares, 474_340);
+        assert_eq!(XSpot::integrity_check(), vec![]);
+
+        assert_noop!(
+            XSpot::remove_liquidity(Origin::signed(2), 0, 474_341, 0, 0),
+            Error::<Test>::InsufficientLiquidity
+        );
+        assert_ok!(XSpot::remove_liquidity(
+            Origin::signed(2),
+            0,
+            158_113,
+            503_332,
+            49_669
+        ));
+        assert_eq!(XAssets::usable_balance(&2, &LP_ASSET), 0);
+        assert_eq!(t_generic_free_balance(2, xp_protocol::PCX), 2_003_332);
+        assert_eq!(t_generic_free_balance(2, btc), 199_669);
+
+        let pool = XSpot::liquidity_pool_of(0).unwrap();
+        assert_eq!(pool.base_reserve, 1_006_668);
+        assert_eq!(pool.quote_reserve, 99_341);
+        assert_eq!(pool.total_shares, 316_227);
+        assert_eq!(XSpot::integrity_check(), vec![]);
+    })
+}
+
+#[test]
+fn create_liquidity_pool_should_check_the_lp_asset() {
+    ExtBuilder::default().build_and_execute(|| {
+        let btc = XSpot::trading_pair_of(0).unwrap().quote();
+
+        assert_noop!(
+            XSpot::create_liquidity_pool(Origin::root(), 0, btc, Permill::zero()),
+            Error::<Test>::InvalidLpAsset
+        );
+        assert_noop!(
+            XSpot::create_liquidity_pool(Origin::root(), 0, LP_ASSET, Permill::zero()),
+            Error::<Test>::InvalidLpAsset
+        );
+        assert_noop!(
+            XSpot::swap(Origin::signed(1), 0, Side::Sell, 10_000, 0),
+            Error::<Test>::NonexistentLiquidityPool
+        );
+
+        assert_ok!(t_create_liquidity_pool(0, LP_ASSET));
+        assert_noop!(
+            XSpot::create_liquidity_pool(Origin::root(), 0, LP_ASSET, Permill::zero()),
+            Error::<Test>::LiquidityPoolAlreadyExists
+        );
+        // The LP asset can not be shared by the pools.
+        assert_noop!(
+            XSpot::create_liquidity_pool(Origin::root(), 1, LP_ASSET, Permill::zero()),
+            Error::<Test>::InvalidLpAsset
+        );
+    })
+}
+
+#[test]
+fn swap_exact_in_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let btc = XSpot::trading_pair_of(0).unwrap().quote();
+        let pcx = XSpot::trading_pair_of(0).unwrap().base();
+
+        // EOS/PCX
+        t_register_asset(EOS, b"EOS");
+        t_add_trading_pair(CurrencyPair::new(EOS, pcx), 9, 2, 2_000_000_000, true);
+
+        t_generic_issue(btc, 1, 500);
+        t_issue_pcx(2, 1_000_000);
+        t_generic_issue(EOS, 3, 1_000_000);
+
+        assert_ok!(t_put_order_sell(2, 0, 1_000_000, 1_000_000));
+        assert_ok!(t_put_order_sell(3, 2, 1_000_000, 2_000_000_000));
+
+        let path = vec![btc, pcx, EOS];
+
+        // 500 X-BTC -> 500_000 PCX -> 250_000 EOS
+        assert_noop!(
+            XSpot::swap_exact_in(Origin::signed(1), path.clone(), 500, 250_001),
+            Error::<Test>::SlippageExceeded
+        );
+        assert_ok!(XSpot::swap_exact_in(Origin::signed(1), path, 500, 250_000));
+
+        assert_eq!(t_generic_free_balance(1, btc), 0);
+        assert_eq!(t_generic_free_balance(1, pcx), 0);
+        assert_eq!(t_generic_free_balance(1, EOS), 250_000);
+        assert_eq!(t_generic_free_balance(2, btc), 500);
+        assert_eq!(t_generic_free_balance(3, pcx), 500_000);
+        assert_eq!(XSpot::order_info_of(2, 0).unwrap().already_filled, 500_000);
+        assert_eq!(XSpot::order_info_of(3, 0).unwrap().already_filled, 250_000);
+        assert_eq!(XSpot::integrity_check(), vec![]);
+    })
+}
+
+#[test]
+fn swap_exact_in_should_check_the_path() {
+    ExtBuilder::default().build_and_execute(|| {
+        let btc = XSpot::trading_pair_of(0).unwrap().quote();
+        let pcx = XSpot::trading_pair_of(0).unwrap().base();
+
+        t_issue_pcx(1, 1_000_000);
+
+        assert_noop!(
+            XSpot::swap_exact_in(Origin::signed(1), vec![pcx], 1_000, 0),
+            Error::<Test>::InvalidSwapPath
+        );
+        assert_noop!(
+            XSpot::swap_exact_in(Origin::signed(1), vec![pcx, pcx], 1_000, 0),
+            Error::<Test>::InvalidSwapPath
+        );
+        assert_noop!(
+            XSpot::swap_exact_in(Origin::signed(1), vec![pcx, EOS], 1_000, 0),
+            Error::<Test>::InvalidSwapPath
+        );
+        assert_noop!(
+            XSpot::swap_exact_in(Origin::signed(1), vec![pcx, btc, pcx, btc, pcx], 1_000, 0),
+            Error::<Test>::InvalidSwapPath
+        );
+
+        // There is no bid of PCX/X-BTC.
+        assert_noop!(
+            XSpot::swap_exact_in(Origin::signed(1), vec![pcx, btc], 1_000, 0),
+            Error::<Test>::InsufficientLiquidity
+        );
+    })
+}
+
+fn t_set_maker_mining_rule(pair_id: TradingPairId, power: u32, band: Permill) {
+    assert_ok!(XSpot::set_maker_mining_rule(
+        Origin::root(),
+        pair_id,
+        Some(MakerMiningRule { power, band })
+    ));
+}
+
+#[test]
+fn maker_mining_power_should_only_count_the_orders_in_band() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+
+        t_generic_issue(trading_pair.quote(), 1, 20);
+        t_generic_issue(trading_pair.quote(), 2, 10);
+
+        // No rule, no mining.
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_eq!(XSpot::order_mining_power_of(1, 0), 0);
+
+        // The bids not lower than 990_000 are in band once the orders are refreshed.
+        t_set_maker_mining_rule(0, 2, Permill::from_percent(1));
+        assert_eq!(XSpot::order_mining_power_of(1, 0), 0);
+        XSpot::refresh_queued_mining_power();
+        assert_eq!(XSpot::order_mining_power_of(1, 0), 2000);
+
+        assert_ok!(t_put_order_buy(1, 0, 10_000, 900_000));
+        assert_eq!(XSpot::order_mining_power_of(1, 1), 0);
+        assert_eq!(XSpot::maker_mining_ledgers(1).power, 2000);
+
+        // The best bid rises to 1_020_000, the order at 1_000_000 moves out of the band.
+        assert_ok!(t_put_order_buy(2, 0, 1000, 1_020_000));
+        assert_eq!(XSpot::order_mining_power_of(2, 0), 2000);
+        XSpot::refresh_queued_mining_power();
+        assert_eq!(XSpot::order_mining_power_of(1, 0), 0);
+        assert_eq!(XSpot::maker_mining_ledgers(1).power, 0);
+
+        // The best bid is partially filled.
+        t_issue_pcx(3, 1000);
+        assert_ok!(t_put_order_sell(3, 0, 400, 1_020_000));
+        assert_eq!(XSpot::order_mining_power_of(2, 0), 1200);
+
+        // The best bid is canceled, the order at 1_000_000 is back in band.
+        assert_ok!(t_cancel_order(2, 0, 0));
+        assert_eq!(XSpot::order_mining_power_of(2, 0), 0);
+        XSpot::refresh_queued_mining_power();
+        assert_eq!(XSpot::order_mining_power_of(1, 0), 2000);
+        assert_eq!(XSpot::total_maker_mining_ledger().power, 2000);
+
+        // Removing the rule stops the mining.
+        assert_ok!(XSpot::set_maker_mining_rule(Origin::root(), 0, None));
+        XSpot::refresh_queued_mining_power();
+        assert_eq!(XSpot::order_mining_power_of(1, 0), 0);
+        assert_eq!(XSpot::total_maker_mining_ledger().power, 0);
+    })
+}
+
+#[test]
+fn maker_mining_refresh_should_be_bounded_per_block() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+
+        t_generic_issue(trading_pair.quote(), 1, 10);
+        t_generic_issue(trading_pair.quote(), 2, 200);
+        t_set_maker_mining_rule(0, 1, Permill::from_percent(1));
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_020_000));
+        XSpot::refresh_queued_mining_power();
+
+        // Out of the band of the best bid 1_020_000.
+        let count = MAX_MINING_REFRESHES_PER_BLOCK as u64 + 1;
+        for _ in 0..count {
+            assert_ok!(t_put_order_buy(2, 0, 1000, 1_000_000));
+        }
+        assert_eq!(XSpot::maker_mining_ledgers(2).power, 0);
+
+        // The level at 1_000_000 moves into the band, only the rest is left to the next block.
+        assert_ok!(t_cancel_order(1, 0, 0));
+        XSpot::refresh_queued_mining_power();
+        assert_eq!(
+            XSpot::maker_mining_ledgers(2).power,
+            1000 * MAX_MINING_REFRESHES_PER_BLOCK as MiningPower
+        );
+        assert_eq!(XSpot::order_mining_power_of(2, count - 1), 0);
+        assert_eq!(
+            XSpot::mining_refresh_cursor(),
+            Some((1_000_000, (2, count - 1)))
+        );
+
+        XSpot::refresh_queued_mining_power();
+        assert_eq!(XSpot::order_mining_power_of(2, count - 1), 1000);
+        assert_eq!(
+            XSpot::total_maker_mining_ledger().power,
+            1000 * count as MiningPower
+        );
+        assert_eq!(XSpot::mining_refresh_cursor(), None);
+        assert_eq!(
+            XSpot::mining_refresh_queue_head(),
+            XSpot::mining_refresh_queue_tail()
+        );
+    })
+}
+
+#[test]
+fn claim_maker_reward_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+
+        System::set_block_number(1);
+        t_generic_issue(trading_pair.quote(), 1, 10);
+        t_generic_issue(trading_pair.quote(), 2, 10);
+        t_set_maker_mining_rule(0, 1, Permill::from_percent(1));
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        System::set_block_number(6);
+        assert_ok!(t_put_order_buy(2, 0, 3000, 1_000_000));
+
+        System::set_block_number(11);
+        <XSpot as xp_mining_staking::DexMining<Balance>>::reward(1_000);
+        assert_eq!(Balances::free_balance(XSpot::dex_reward_pot()), 1_000);
+
+        // Weight of 1: 1000 * 10, weight of 2: 3000 * 5.
+        assert_eq!(XSpot::maker_dividend_of(&1), 400);
+        assert_ok!(XSpot::claim_maker_reward(Origin::signed(1)));
+        assert_eq!(Balances::free_balance(1), 400);
+        assert_eq!(Balances::free_balance(XSpot::dex_reward_pot()), 600);
+
+        assert_noop!(
+            XSpot::claim_maker_reward(Origin::signed(1)),
+            Error::<Test>::ZeroMiningWeight
+        );
+        assert_noop!(
+            XSpot::claim_maker_reward(Origin::signed(3)),
+            Error::<Test>::ZeroMiningWeight
+        );
+
+        assert_ok!(XSpot::claim_maker_reward(Origin::signed(2)));
+        assert_eq!(Balances::free_balance(2), 600);
+        assert_eq!(Balances::free_balance(XSpot::dex_reward_pot()), 0);
+    })
+}
+
+#[test]
+fn delisting_should_cancel_and_refund_all_the_orders() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        System::set_block_number(1);
+        t_generic_issue(quote, 1, 10);
+        t_issue_pcx(2, 2000);
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_sell(2, 0, 500, 1_100_000));
+        assert_ok!(XSpot::put_conditional_order(
+            Origin::signed(2),
+            0,
+            ConditionalOrderKind::TakeProfit,
+            Side::Sell,
+            1000,
+            1_200_000,
+            1_200_000
+        ));
+        assert_eq!(t_generic_free_balance(1, quote), 9);
+        assert_eq!(XSpot::native_reserves(&2), 1500);
+
+        assert_noop!(
+            XSpot::schedule_delisting(Origin::root(), 0, 1),
+            Error::<Test>::InvalidDelistingBlock
+        );
+        assert_ok!(XSpot::schedule_delisting(Origin::root(), 0, 5));
+        assert_eq!(XSpot::delisting_at(0), Some(5));
+        assert_eq!(XSpot::delisting_pairs_at(5), vec![0]);
+
+        // No new orders are accepted, but the open orders can still be canceled.
+        assert_noop!(
+            t_put_order_buy(1, 0, 1000, 1_000_000),
+            Error::<Test>::TradingPairDelisting
+        );
+        assert_ok!(t_cancel_order(2, 0, 0));
+        assert_eq!(XSpot::native_reserves(&2), 1000);
+
+        System::set_block_number(5);
+        XSpot::on_initialize(5);
+
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::conditional_order_info_of(2, 0), None);
+        assert_eq!(t_generic_free_balance(1, quote), 10);
+        assert_eq!(XSpot::native_reserves(&2), 0);
+        assert_eq!(Balances::free_balance(2), 2000);
+
+        assert!(XSpot::price_levels_of(0, Side::Buy).is_empty());
+        assert_eq!(XSpot::handicap_of(0), Handicap::default());
+        assert!(!XSpot::trading_pair_of(0).unwrap().tradable);
+        assert_eq!(XSpot::delisting_at(0), None);
+        assert_eq!(XSpot::delisted_at(0), Some(5));
+
+        assert_noop!(
+            t_put_order_buy(1, 0, 1000, 1_000_000),
+            Error::<Test>::TradingPairUntradable
+        );
+        assert_noop!(
+            XSpot::update_trading_pair(Origin::root(), 0, 2, true),
+            Error::<Test>::TradingPairDelisted
+        );
+
+        // Re-list the trading pair with a fresh info.
+        System::set_block_number(10);
+        assert_ok!(XSpot::relist_trading_pair(Origin::root(), 0, 3, 2_000_000));
+        assert_noop!(
+            XSpot::relist_trading_pair(Origin::root(), 0, 3, 2_000_000),
+            Error::<Test>::TradingPairNotDelisted
+        );
+        let pair = XSpot::trading_pair_of(0).unwrap();
+        assert!(pair.tradable);
+        assert_eq!(pair.tick_decimals, 3);
+        let info = XSpot::trading_pair_info_of(0).unwrap();
+        assert_eq!(info.latest_price, 2_000_000);
+        assert_eq!(info.last_updated, 10);
+        assert_eq!(XSpot::delisted_at(0), None);
+
+        assert_ok!(t_put_order_buy(1, 0, 1000, 2_000_000));
+    })
+}
+
+#[test]
+fn delisting_should_be_postponed_when_exceeding_the_limit() {
+    ExtBuilder::default().build_and_execute(|| {
+        t_issue_pcx(1, 1000);
+
+        for _ in 0..(MAX_DELISTING_CANCELS_PER_BLOCK + 1) {
+            assert_ok!(t_put_order_sell(1, 0, 1, 1_100_000));
+        }
+        assert_ok!(XSpot::schedule_delisting(Origin::root(), 0, 2));
+
+        System::set_block_number(2);
+        XSpot::on_initialize(2);
+        assert_eq!(XSpot::native_reserves(&1), 1);
+        assert_eq!(XSpot::delisting_at(0), Some(3));
+        assert_eq!(XSpot::delisting_pairs_at(3), vec![0]);
+        assert_eq!(XSpot::delisted_at(0), None);
+
+        System::set_block_number(3);
+        XSpot::on_initialize(3);
+        assert_eq!(XSpot::native_reserves(&1), 0);
+        assert_eq!(XSpot::quotations_of(0, 1_100_000), vec![]);
+        assert_eq!(XSpot::delisted_at(0), Some(3));
+    })
+}
+
+#[test]
+fn delisting_should_refund_the_orders_of_the_deregistered_asset() {
+    ExtBuilder::default().build_and_execute(|| {
+        let trading_pair = XSpot::trading_pair_of(0).unwrap();
+        let quote = trading_pair.quote();
+
+        System::set_block_number(1);
+        t_generic_issue(quote, 1, 10);
+        assert_ok!(t_put_order_buy(1, 0, 1000, 1_000_000));
+        assert_ok!(t_put_order_buy(1, 0, 2000, 1_000_000));
+        assert_eq!(t_generic_free_balance(1, quote), 7);
+
+        assert_ok!(xpallet_assets_registrar::Module::<Test>::deregister(
+            Origin::root(),
+            quote
+        ));
+        assert!(!XSpot::trading_pair_of(0).unwrap().tradable);
+        assert_eq!(XSpot::delisting_at(0), Some(2));
+
+        System::set_block_number(2);
+        XSpot::on_initialize(2);
+
+        assert_eq!(XSpot::order_info_of(1, 0), None);
+        assert_eq!(XSpot::order_info_of(1, 1), None);
+        assert_eq!(t_generic_free_balance(1, quote), 10);
+        assert_eq!(
+            XAssets::asset_balance(1, quote).get(&AssetType::ReservedDexSpot),
+            None
+        );
+        assert!(XSpot::price_levels_of(0, Side::Buy).is_empty());
+        assert_eq!(XSpot::delisting_at(0), None);
+        assert_eq!(XSpot::delisting_pairs_at(3), vec![]);
+        assert_eq!(XSpot::delisted_at(0), Some(2));
+    })
+}
@@ -7,11 +7,16 @@ use codec::{Decode, Encode};
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 use sp_arithmetic::traits::BaseArithmetic;
-use sp_runtime::RuntimeDebug;
+use sp_runtime::{traits::Saturating, Permill, RuntimeDebug};
+use xp_mining_common::{BaseMiningWeight, WeightType};
+use xp_mining_staking::MiningPower;
 
 /// Type for counting the number of user orders.
 pub type OrderId = u64;
 
+/// Type for counting the number of user conditional orders.
+pub type ConditionalOrderId = u64;
+
 /// Type for counting the number of trading pairs.
 pub type TradingPairId = u32;
 
@@ -25,12 +30,13 @@ pub type Tick = u64;
 pub type PriceFluctuation = u32;
 
 /// Type of an order.
-///
-/// Currently only Limit Order is supported.
 #[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum OrderType {
+    /// Order that rests in the order book until it's filled or canceled.
     Limit,
+    /// Order that is filled against the order book immediately,
+    /// the unfilled remainder is refunded.
     Market,
 }
 
@@ -40,6 +46,55 @@ impl Default for OrderType {
     }
 }
 
+/// How long an order remains active before it's executed or canceled.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum TimeInForce {
+    /// Rest in the order book until it's filled or canceled.
+    GoodTillCancel,
+    /// Fill as much as possible immediately, cancel the unfilled remainder.
+    ImmediateOrCancel,
+    /// Fill completely and immediately, otherwise cancel the whole order.
+    FillOrKill,
+    /// Only rest in the order book as a maker, rejected if it would be matched immediately.
+    PostOnly,
+}
+
+impl Default for TimeInForce {
+    fn default() -> Self {
+        Self::GoodTillCancel
+    }
+}
+
+impl TimeInForce {
+    /// Returns true if the order must never rest in the order book.
+    pub fn is_immediate(&self) -> bool {
+        matches!(self, Self::ImmediateOrCancel | Self::FillOrKill)
+    }
+}
+
+/// What to do when a taker order would be matched with a maker order of the same submitter.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum SelfTradePrevention {
+    /// Cancel the unfilled remainder of the taker order.
+    CancelTaker,
+    /// Cancel the maker order and go on matching.
+    CancelMaker,
+    /// Cancel both the maker order and the unfilled remainder of the taker order.
+    CancelBoth,
+    /// Match with the orders of the same submitter like the others.
+    ///
+    /// The orders put before the self-trade prevention was introduced are migrated to this.
+    None,
+}
+
+impl Default for SelfTradePrevention {
+    fn default() -> Self {
+        Self::CancelTaker
+    }
+}
+
 /// Direction of an order.
 #[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -54,6 +109,48 @@ impl Default for Side {
     }
 }
 
+/// Kind of a conditional order.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum ConditionalOrderKind {
+    /// Triggered when the price moves against the trader, i.e., rises to the trigger
+    /// price for a buy and falls to the trigger price for a sell.
+    StopLimit,
+    /// Triggered when the price moves in favor of the trader, i.e., falls to the trigger
+    /// price for a buy and rises to the trigger price for a sell.
+    TakeProfit,
+}
+
+/// Direction in which the latest price has to move to trigger a conditional order.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum TriggerDirection {
+    /// Triggered when the latest price is no less than the trigger price.
+    Rise,
+    /// Triggered when the latest price is no more than the trigger price.
+    Fall,
+}
+
+impl TriggerDirection {
+    /// Returns the trigger direction of a conditional order.
+    pub fn of(kind: ConditionalOrderKind, side: Side) -> Self {
+        match (kind, side) {
+            (ConditionalOrderKind::StopLimit, Side::Buy)
+            | (ConditionalOrderKind::TakeProfit, Side::Sell) => Self::Rise,
+            (ConditionalOrderKind::StopLimit, Side::Sell)
+            | (ConditionalOrderKind::TakeProfit, Side::Buy) => Self::Fall,
+        }
+    }
+
+    /// Returns true if the `latest` price has crossed the `trigger` price.
+    pub fn is_triggered<Price: PartialOrd>(self, latest: Price, trigger: Price) -> bool {
+        match self {
+            Self::Rise => latest >= trigger,
+            Self::Fall => latest <= trigger,
+        }
+    }
+}
+
 /// Status of an order.
 #[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -83,34 +180,38 @@ impl Default for OrderStatus {
 /// -------------------
 ///   bid(buy price)
 /// ------------------- Highest Bid
+///
+/// The handicap is always derived from the non-empty price levels,
+/// `None` means there are no orders on that side.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct Handicap<Price> {
-    pub highest_bid: Price,
-    pub lowest_ask: Price,
+    pub highest_bid: Option<Price>,
+    pub lowest_ask: Option<Price>,
 }
 
-impl<Price: Copy + BaseArithmetic> Handicap<Price> {
-    pub fn new(highest_bid: Price, lowest_ask: Price) -> Self {
-        Self {
-            highest_bid,
-            lowest_ask,
-        }
-    }
-
-    /// Decreases the `highest_bid` by one tick.
-    pub fn tick_down_highest_bid(&mut self, tick_decimals: u32) -> Price {
-        let tick = 10_u64.pow(tick_decimals);
-        self.highest_bid = self.highest_bid.saturating_sub(tick.saturated_into());
-        self.highest_bid
-    }
+/// The storage version of Spot.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum Releases {
+    /// The handicap is a pair of prices which is ticked by the matching.
+    V1_0_0,
+    /// The handicap is derived from the best non-empty price levels.
+    ///
+    /// The orders carry the time in force, the self-trade prevention and the expiration.
+    ///
+    /// The quotations of a price level are linked order by order.
+    ///
+    /// The open orders are indexed by the account and the trading pair.
+    ///
+    /// NOTE: `set_handicap` is removed as well, which shifts the indices of
+    /// the calls after it.
+    V2_0_0,
+}
 
-    /// Increases the `lowest_ask` by one tick.
-    pub fn tick_up_lowest_ask(&mut self, tick_decimals: u32) -> Price {
-        let tick = 10_u64.pow(tick_decimals);
-        self.lowest_ask = self.lowest_ask.saturating_add(tick.saturated_into());
-        self.lowest_ask
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
     }
 }
 
@@ -217,6 +318,175 @@ impl TradingPairProfile {
     }
 }
 
+/// Fee rates charged on the executed orders of a trading pair.
+///
+/// The fee is deducted from the asset received by the maker or taker.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Default, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TradingFee {
+    /// Fee rate of the maker order.
+    pub maker: Permill,
+    /// Fee rate of the taker order.
+    pub taker: Permill,
+}
+
+impl TradingFee {
+    pub fn new(maker: Permill, taker: Permill) -> Self {
+        Self { maker, taker }
+    }
+}
+
+/// Restrictions on the amount of the orders of a trading pair, measured in the base currency.
+///
+/// Zero means no restriction.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OrderSizeLimit<Balance> {
+    /// The minimum amount of an order.
+    pub min_amount: Balance,
+    /// The amount of an order must be a multiple of the lot size.
+    pub lot_size: Balance,
+}
+
+impl<Balance> OrderSizeLimit<Balance> {
+    pub fn new(min_amount: Balance, lot_size: Balance) -> Self {
+        Self {
+            min_amount,
+            lot_size,
+        }
+    }
+}
+
+/// A constant-product liquidity pool of a trading pair.
+///
+/// The reserves are held by the pool account and locked in Spot, the liquidity
+/// providers own the pool in proportion to their LP shares, which are issued
+/// as the asset `lp_asset_id`.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct LiquidityPool<Balance> {
+    /// The asset of the LP shares.
+    pub lp_asset_id: AssetId,
+    /// The amount of the base currency in the pool.
+    pub base_reserve: Balance,
+    /// The amount of the quote currency in the pool.
+    pub quote_reserve: Balance,
+    /// The total issuance of the LP shares.
+    pub total_shares: Balance,
+    /// The swap fee rate charged from the input, which is left in the pool for the LPs.
+    pub fee: Permill,
+}
+
+impl<Balance: Copy + Zero> LiquidityPool<Balance> {
+    pub fn new(lp_asset_id: AssetId, fee: Permill) -> Self {
+        Self {
+            lp_asset_id,
+            base_reserve: Zero::zero(),
+            quote_reserve: Zero::zero(),
+            total_shares: Zero::zero(),
+            fee,
+        }
+    }
+
+    /// Returns the reserves of the input and output currency of a swap of `side`.
+    pub fn reserves_of(&self, side: Side) -> (Balance, Balance) {
+        match side {
+            Side::Buy => (self.quote_reserve, self.base_reserve),
+            Side::Sell => (self.base_reserve, self.quote_reserve),
+        }
+    }
+}
+
+/// The rule of the liquidity mining of a trading pair.
+///
+/// The orders resting within `band` of the best price of their side accrue
+/// the mining weight, each unit of the base currency counts as `power`.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct MakerMiningRule {
+    /// The mining power of each unit of the base currency.
+    pub power: u32,
+    /// The maximum deviation of the price from the best price of the same side.
+    pub band: Permill,
+}
+
+impl MakerMiningRule {
+    /// Returns true if the order of `side` at `price` is within the band of `best`.
+    pub fn is_in_band<Price: AtLeast32BitUnsigned + Copy>(
+        &self,
+        side: Side,
+        price: Price,
+        best: Price,
+    ) -> bool {
+        let deviation = self.band * best;
+        match side {
+            Side::Buy => price.saturating_add(deviation) >= best,
+            Side::Sell => price <= best.saturating_add(deviation),
+        }
+    }
+}
+
+/// Mining weight properties of a maker, or of all the makers as a whole.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct MakerMiningLedger<BlockNumber> {
+    /// The mining power of the orders within the band.
+    pub power: MiningPower,
+    /// Last calculated mining weight.
+    pub last_mining_weight: WeightType,
+    /// Block number at which point `last_mining_weight` just updated.
+    pub last_mining_weight_update: BlockNumber,
+}
+
+impl<BlockNumber: Copy> BaseMiningWeight<MiningPower, BlockNumber>
+    for MakerMiningLedger<BlockNumber>
+{
+    fn amount(&self) -> MiningPower {
+        self.power
+    }
+
+    fn set_amount(&mut self, new: MiningPower) {
+        self.power = new;
+    }
+
+    fn last_acum_weight(&self) -> WeightType {
+        self.last_mining_weight
+    }
+
+    fn set_last_acum_weight(&mut self, latest_mining_weight: WeightType) {
+        self.last_mining_weight = latest_mining_weight;
+    }
+
+    fn last_acum_weight_update(&self) -> BlockNumber {
+        self.last_mining_weight_update
+    }
+
+    fn set_last_acum_weight_update(&mut self, current_block: BlockNumber) {
+        self.last_mining_weight_update = current_block;
+    }
+}
+
+/// The first and the last order of the quotations at a price level.
+///
+/// The quotations are a doubly linked list in the time priority, the links
+/// of each order are stored in the `QuotationNode` of the order.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct QuotationQueue<AccountId> {
+    pub head: (AccountId, OrderId),
+    pub tail: (AccountId, OrderId),
+}
+
+/// The previous and the next order of an order in the quotations at its price level.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct QuotationNode<AccountId> {
+    pub prev: Option<(AccountId, OrderId)>,
+    pub next: Option<(AccountId, OrderId)>,
+}
+
 /// Immutable information of an order.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -236,8 +506,75 @@ pub struct OrderProperty<PairId, AccountId, Amount, Price, BlockNumber> {
     pub submitter: AccountId,
     /// The type of order.
     pub order_type: OrderType,
+    /// How long the order remains active.
+    pub time_in_force: TimeInForce,
+    /// How to prevent the order from matching with the orders of the same submitter.
+    pub self_trade_prevention: SelfTradePrevention,
     /// Block number at which the order is created.
     pub created_at: BlockNumber,
+    /// Block number at which the order will be canceled automatically if it's still open.
+    pub expire_at: Option<BlockNumber>,
+}
+
+/// Parameters of a new order, used to put the orders in batches.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct OrderRequest<PairId, Balance, Price, BlockNumber> {
+    /// The trading pair identifier.
+    pub pair_id: PairId,
+    /// The type of order.
+    pub order_type: OrderType,
+    /// The direction of order.
+    pub side: Side,
+    /// The amount of order.
+    pub amount: Balance,
+    /// The price of order.
+    pub price: Price,
+    /// How long the order remains active.
+    pub time_in_force: TimeInForce,
+    /// Block number at which the order will be canceled automatically if it's still open.
+    pub expire_at: Option<BlockNumber>,
+    /// The self-trade prevention policy, the default one of trading pair is used if `None`.
+    pub self_trade_prevention: Option<SelfTradePrevention>,
+}
+
+/// Details of a conditional order waiting for being triggered.
+///
+/// The asset of the limit order is reserved when the conditional order is put.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ConditionalOrder<PairId, AccountId, Balance, Price, BlockNumber> {
+    /// The conditional order identifier.
+    pub id: ConditionalOrderId,
+    /// The trading pair identifier.
+    pub pair_id: PairId,
+    /// The account that submitted the conditional order.
+    pub submitter: AccountId,
+    /// The kind of conditional order.
+    pub kind: ConditionalOrderKind,
+    /// The direction of the limit order.
+    pub side: Side,
+    /// The amount of the limit order, measured in the base currency.
+    pub amount: Balance,
+    /// The price of the limit order.
+    pub price: Price,
+    /// The price at which the limit order is put.
+    pub trigger_price: Price,
+    /// The reserved asset balance of the limit order.
+    pub reserved: Balance,
+    /// Block number at which the conditional order is created.
+    pub created_at: BlockNumber,
+}
+
+impl<PairId, AccountId, Balance, Price, BlockNumber>
+    ConditionalOrder<PairId, AccountId, Balance, Price, BlockNumber>
+{
+    /// Returns the trigger direction of the conditional order.
+    pub fn direction(&self) -> TriggerDirection {
+        TriggerDirection::of(self.kind, self.side)
+    }
 }
 
 /// Details of an order.
@@ -324,11 +661,26 @@ where
         self.props.order_type
     }
 
+    /// Returns the time in force of the order.
+    pub fn time_in_force(&self) -> TimeInForce {
+        self.props.time_in_force
+    }
+
+    /// Returns the self-trade prevention policy of the order.
+    pub fn self_trade_prevention(&self) -> SelfTradePrevention {
+        self.props.self_trade_prevention
+    }
+
     /// Returns the block number of the order created.
     pub fn created_at(&self) -> BlockNumber {
         self.props.created_at
     }
 
+    /// Returns the block number of the order expired.
+    pub fn expire_at(&self) -> Option<BlockNumber> {
+        self.props.expire_at
+    }
+
     /// The `remaining` field is measured by the quote currency.
     /// (self.amount - self.already_filled) is the remaining in the base currency,
     pub fn remaining_in_base(&self) -> Balance {
@@ -391,28 +743,90 @@ pub struct TradingPairInfo<Price, BlockNumber> {
     pub last_updated: BlockNumber,
 }
 
+/// The open/high/low/close prices and the volume of the executions within an interval.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct Kline<Price, Balance, BlockNumber> {
+    /// Block number at which the interval starts.
+    pub start_at: BlockNumber,
+    /// Price of the first execution within the interval.
+    pub open: Price,
+    /// The highest execution price within the interval.
+    pub high: Price,
+    /// The lowest execution price within the interval.
+    pub low: Price,
+    /// Price of the last execution within the interval.
+    pub close: Price,
+    /// The executed amount within the interval, measured by the base currency.
+    pub volume: Balance,
+}
+
+impl<Price: Copy + Ord, Balance: Saturating, BlockNumber> Kline<Price, Balance, BlockNumber> {
+    /// Creates a new kline starting with an execution.
+    pub fn new(start_at: BlockNumber, price: Price, volume: Balance) -> Self {
+        Self {
+            start_at,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        }
+    }
+
+    /// Aggregates a new execution into the kline.
+    pub fn update(&mut self, price: Price, volume: Balance) {
+        self.high = cmp::max(self.high, price);
+        self.low = cmp::min(self.low, price);
+        self.close = price;
+        self.volume = self.volume.saturating_add(volume);
+    }
+}
+
 /// Information about the executed orders.
+///
+/// The fee of maker or taker is measured by the asset it receives, i.e.,
+/// the base currency for the buyer and the quote currency for the seller.
 #[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct OrderExecutedInfo<AccountId, Balance, BlockNumber, Price> {
-    trading_history_idx: TradingHistoryIndex,
-    pair_id: TradingPairId,
-    price: Price,
-    maker: AccountId,
-    taker: AccountId,
-    maker_order_id: OrderId,
-    taker_order_id: OrderId,
-    turnover: Balance,
-    executed_at: BlockNumber,
+    /// The index of this execution in the trading history of pair.
+    pub trading_history_idx: TradingHistoryIndex,
+    /// The trading pair identifier.
+    pub pair_id: TradingPairId,
+    /// The price at which the orders are executed.
+    pub price: Price,
+    /// The submitter of maker order.
+    pub maker: AccountId,
+    /// The submitter of taker order.
+    pub taker: AccountId,
+    /// The identifier of maker order.
+    pub maker_order_id: OrderId,
+    /// The identifier of taker order.
+    pub taker_order_id: OrderId,
+    /// The executed amount, measured by the base currency.
+    pub turnover: Balance,
+    /// The fee paid by the maker.
+    pub maker_fee: Balance,
+    /// The fee paid by the taker.
+    pub taker_fee: Balance,
+    /// Block number at which the orders are executed.
+    pub executed_at: BlockNumber,
 }
 
 impl<AccountId: Clone, Balance: Copy + Ord + BaseArithmetic, BlockNumber: Copy, Price: Copy>
     OrderExecutedInfo<AccountId, Balance, BlockNumber, Price>
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         trading_history_idx: TradingHistoryIndex,
         pair_id: TradingPairId,
         price: Price,
         turnover: Balance,
+        maker_fee: Balance,
+        taker_fee: Balance,
         maker_order: &Order<TradingPairId, AccountId, Balance, Price, BlockNumber>,
         taker_order: &Order<TradingPairId, AccountId, Balance, Price, BlockNumber>,
         executed_at: BlockNumber,
@@ -422,6 +836,8 @@ impl<AccountId: Clone, Balance: Copy + Ord + BaseArithmetic, BlockNumber: Copy,
             pair_id,
             price,
             turnover,
+            maker_fee,
+            taker_fee,
             executed_at,
             maker: maker_order.submitter(),
             taker: taker_order.submitter(),
@@ -0,0 +1,48 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! Runtime API definition required by ChainX RPC extensions.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::too_many_arguments, clippy::unnecessary_mut_passed)]
+
+use sp_std::prelude::*;
+
+use sp_runtime::DispatchError;
+
+pub use xp_gateway_bitcoin::BtcTxType;
+
+pub use xpallet_gateway_bitcoin::{
+    BtcAddress, BtcDepositCache, BtcGatewayParams, BtcHeaderDetails, BtcHeaderIndex,
+    BtcTrusteeVote, BtcTxResult, BtcTxState, BtcWithdrawalProposalDetails, VoteResult, H256,
+};
+
+sp_api::decl_runtime_apis! {
+    pub trait XGatewayBitcoinApi<AccountId>
+    where
+        AccountId: codec::Codec,
+    {
+        /// Get the best block header index.
+        fn best_index() -> BtcHeaderIndex;
+
+        /// Get the confirmed block header index.
+        fn confirmed_index() -> Option<BtcHeaderIndex>;
+
+        /// Get the block header by hash, including the forked ones.
+        fn header(hash: H256) -> Option<BtcHeaderDetails>;
+
+        /// Get the block header at the height of the main chain.
+        fn header_at(height: u32) -> Option<BtcHeaderDetails>;
+
+        /// Get the handle result of the transaction.
+        fn tx_state(txid: H256) -> Option<BtcTxState>;
+
+        /// Get the unclaimed deposits of the bitcoin address.
+        fn pending_deposits(addr: BtcAddress) -> Result<Vec<BtcDepositCache>, DispatchError>;
+
+        /// Get the current withdrawal proposal with the vote of each trustee.
+        fn withdrawal_proposal() -> Option<BtcWithdrawalProposalDetails<AccountId>>;
+
+        /// Get the parameters of the bitcoin gateway.
+        fn params() -> BtcGatewayParams;
+    }
+}
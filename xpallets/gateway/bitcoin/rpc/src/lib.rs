@@ -0,0 +1,295 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! RPC interface for the bitcoin gateway module.
+//!
+//! The block hashes and the transaction ids are in the user-visible (reversed) byte order,
+//! the same as the bitcoin block explorers.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+use light_bitcoin::{primitives::hash_rev, serialization::serialize};
+
+use xp_rpc::{hex_decode_error_into_rpc_err, runtime_error_into_rpc_err, Result};
+
+use xpallet_gateway_bitcoin_rpc_runtime_api::{
+    BtcDepositCache, BtcGatewayParams, BtcHeaderDetails, BtcHeaderIndex, BtcTrusteeVote,
+    BtcTxState, BtcWithdrawalProposalDetails, VoteResult,
+    XGatewayBitcoinApi as XGatewayBitcoinRuntimeApi, H256,
+};
+
+/// XGatewayBitcoin RPC methods.
+#[rpc]
+pub trait XGatewayBitcoinApi<BlockHash, AccountId> {
+    /// Return the best block header index of the bitcoin light client.
+    #[rpc(name = "xgatewaybitcoin_bestIndex")]
+    fn best_index(&self, at: Option<BlockHash>) -> Result<RpcBtcHeaderIndex>;
+
+    /// Return the confirmed block header index of the bitcoin light client.
+    #[rpc(name = "xgatewaybitcoin_confirmedIndex")]
+    fn confirmed_index(&self, at: Option<BlockHash>) -> Result<Option<RpcBtcHeaderIndex>>;
+
+    /// Return the block header by hash, including the forked ones.
+    #[rpc(name = "xgatewaybitcoin_header")]
+    fn header(&self, hash: String, at: Option<BlockHash>) -> Result<Option<RpcBtcHeader>>;
+
+    /// Return the block header at the height of the main chain.
+    #[rpc(name = "xgatewaybitcoin_headerAt")]
+    fn header_at(&self, height: u32, at: Option<BlockHash>) -> Result<Option<RpcBtcHeader>>;
+
+    /// Return the handle result of the transaction, `None` if it has not been handled.
+    #[rpc(name = "xgatewaybitcoin_txState")]
+    fn tx_state(&self, txid: String, at: Option<BlockHash>) -> Result<Option<BtcTxState>>;
+
+    /// Return the unclaimed deposits of the bitcoin address.
+    #[rpc(name = "xgatewaybitcoin_pendingDeposits")]
+    fn pending_deposits(
+        &self,
+        addr: String,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<RpcBtcDepositCache>>;
+
+    /// Return the current withdrawal proposal with the vote of each trustee.
+    #[rpc(name = "xgatewaybitcoin_withdrawalProposal")]
+    fn withdrawal_proposal(
+        &self,
+        at: Option<BlockHash>,
+    ) -> Result<Option<RpcBtcWithdrawalProposal<AccountId>>>;
+
+    /// Return the parameters of the bitcoin gateway (e.g. min deposit and withdrawal fee).
+    #[rpc(name = "xgatewaybitcoin_params")]
+    fn params(&self, at: Option<BlockHash>) -> Result<BtcGatewayParams>;
+}
+
+/// A struct that implements the [`XGatewayBitcoinApi`].
+pub struct XGatewayBitcoin<C, B, AccountId> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<(B, AccountId)>,
+}
+
+impl<C, B, AccountId> XGatewayBitcoin<C, B, AccountId> {
+    /// Create new `XGatewayBitcoin` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block, AccountId> XGatewayBitcoinApi<<Block as BlockT>::Hash, AccountId>
+    for XGatewayBitcoin<C, Block, AccountId>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: XGatewayBitcoinRuntimeApi<Block, AccountId>,
+    AccountId: Codec + Send + Sync + 'static,
+{
+    fn best_index(&self, at: Option<<Block as BlockT>::Hash>) -> Result<RpcBtcHeaderIndex> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.best_index(&at)
+            .map(Into::into)
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn confirmed_index(
+        &self,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<RpcBtcHeaderIndex>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.confirmed_index(&at)
+            .map(|index| index.map(Into::into))
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn header(
+        &self,
+        hash: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<RpcBtcHeader>> {
+        let hash = parse_btc_hash(&hash)?;
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.header(&at, hash)
+            .map(|header| header.map(Into::into))
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn header_at(
+        &self,
+        height: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<RpcBtcHeader>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.header_at(&at, height)
+            .map(|header| header.map(Into::into))
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn tx_state(
+        &self,
+        txid: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<BtcTxState>> {
+        let txid = parse_btc_hash(&txid)?;
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.tx_state(&at, txid).map_err(runtime_error_into_rpc_err)
+    }
+
+    fn pending_deposits(
+        &self,
+        addr: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<RpcBtcDepositCache>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let deposits = api
+            .pending_deposits(&at, addr.into_bytes())
+            .map_err(runtime_error_into_rpc_err)?
+            .map_err(runtime_error_into_rpc_err)?;
+        Ok(deposits.into_iter().map(Into::into).collect())
+    }
+
+    fn withdrawal_proposal(
+        &self,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<RpcBtcWithdrawalProposal<AccountId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.withdrawal_proposal(&at)
+            .map(|proposal| proposal.map(Into::into))
+            .map_err(runtime_error_into_rpc_err)
+    }
+
+    fn params(&self, at: Option<<Block as BlockT>::Hash>) -> Result<BtcGatewayParams> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.params(&at).map_err(runtime_error_into_rpc_err)
+    }
+}
+
+/// Parses the hex string of the block hash or the transaction id in the reversed byte order.
+fn parse_btc_hash(hash: &str) -> Result<H256> {
+    let hash = if hash.starts_with("0x") {
+        &hash[2..]
+    } else {
+        hash
+    };
+    let bytes = hex::decode(hash).map_err(hex_decode_error_into_rpc_err)?;
+    if bytes.len() != 32 {
+        return Err(hex_decode_error_into_rpc_err(format!(
+            "Invalid hash length: {}",
+            bytes.len()
+        )));
+    }
+    Ok(hash_rev(H256::from_slice(&bytes)))
+}
+
+/// Encodes the block hash or the transaction id as the hex string in the reversed byte order.
+fn btc_hash_to_string(hash: H256) -> String {
+    hex::encode(hash_rev(hash).as_bytes())
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBtcHeaderIndex {
+    pub hash: String,
+    pub height: u32,
+}
+
+impl From<BtcHeaderIndex> for RpcBtcHeaderIndex {
+    fn from(index: BtcHeaderIndex) -> Self {
+        Self {
+            hash: btc_hash_to_string(index.hash),
+            height: index.height,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBtcHeader {
+    pub hash: String,
+    pub height: u32,
+    pub is_main_chain: bool,
+    pub version: u32,
+    pub previous_header_hash: String,
+    pub merkle_root_hash: String,
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl From<BtcHeaderDetails> for RpcBtcHeader {
+    fn from(details: BtcHeaderDetails) -> Self {
+        let header = details.info.header;
+        Self {
+            hash: btc_hash_to_string(details.hash),
+            height: details.info.height,
+            is_main_chain: details.is_main_chain,
+            version: header.version,
+            previous_header_hash: btc_hash_to_string(header.previous_header_hash),
+            merkle_root_hash: btc_hash_to_string(header.merkle_root_hash),
+            time: header.time,
+            bits: header.bits.into(),
+            nonce: header.nonce,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBtcDepositCache {
+    pub txid: String,
+    pub balance: u64,
+}
+
+impl From<BtcDepositCache> for RpcBtcDepositCache {
+    fn from(cache: BtcDepositCache) -> Self {
+        Self {
+            txid: btc_hash_to_string(cache.txid),
+            balance: cache.balance,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcBtcWithdrawalProposal<AccountId> {
+    pub sig_state: VoteResult,
+    pub withdrawal_id_list: Vec<u32>,
+    pub txid: String,
+    /// The raw transaction in hex, with the signatures collected so far.
+    pub tx: String,
+    pub input_amounts: Vec<u64>,
+    pub required_sigs: u32,
+    pub trustee_votes: Vec<(AccountId, BtcTrusteeVote)>,
+}
+
+impl<AccountId> From<BtcWithdrawalProposalDetails<AccountId>>
+    for RpcBtcWithdrawalProposal<AccountId>
+{
+    fn from(details: BtcWithdrawalProposalDetails<AccountId>) -> Self {
+        let proposal = details.proposal;
+        Self {
+            sig_state: proposal.sig_state,
+            withdrawal_id_list: proposal.withdrawal_id_list,
+            txid: btc_hash_to_string(proposal.tx.hash()),
+            tx: hex::encode(serialize(&proposal.tx)),
+            input_amounts: proposal.input_amounts,
+            required_sigs: details.required_sigs,
+            trustee_votes: details.trustee_votes,
+        }
+    }
+}
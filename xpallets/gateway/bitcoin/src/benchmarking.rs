@@ -179,6 +179,7 @@ benchmarks! {
             withdrawal_id_list: vec![0, 1],
             tx: tx.clone(),
             trustee_list: vec![],
+            input_amounts: vec![],
         };
         WithdrawalProposal::<T>::put(proposal);
 
@@ -215,7 +216,7 @@ benchmarks! {
 
         let tx = create_tx();
         let tx_raw: Vec<u8> = serialization::serialize(&tx).into();
-    }: _(RawOrigin::Signed(caller), vec![0, 1], tx_raw)
+    }: _(RawOrigin::Signed(caller), vec![0, 1], tx_raw, vec![])
     verify {
         assert!(WithdrawalProposal::<T>::get().is_some());
     }
@@ -231,6 +232,7 @@ benchmarks! {
             withdrawal_id_list: vec![0, 1],
             tx: tx,
             trustee_list: vec![ (alice, true) ],
+            input_amounts: vec![],
         };
         WithdrawalProposal::<T>::put(proposal);
 
@@ -292,6 +294,7 @@ benchmarks! {
             withdrawal_id_list: vec![0, 1],
             tx: tx,
             trustee_list: vec![],
+            input_amounts: vec![],
         };
         WithdrawalProposal::<T>::put(proposal);
     }: _(RawOrigin::Root)
@@ -305,7 +308,7 @@ benchmarks! {
         Verifier::put(BtcTxVerifier::Test);
         let tx = prepare_withdrawal::<T>();
         let raw = serialization::serialize(&tx);
-    }: _(RawOrigin::Root, raw.into())
+    }: _(RawOrigin::Root, raw.into(), vec![])
     verify {
         assert_eq!(WithdrawalProposal::<T>::get().unwrap().tx, tx);
     }
@@ -321,6 +324,12 @@ benchmarks! {
     }: _(RawOrigin::Root,  2000000)
     verify {
     }
+
+    set_trustee_addr_type {
+    }: _(RawOrigin::Root, BtcTrusteeAddrType::P2WSH)
+    verify {
+        assert_eq!(Module::<T>::trustee_addr_type(), BtcTrusteeAddrType::P2WSH);
+    }
 }
 
 #[cfg(test)]
@@ -342,6 +351,7 @@ mod tests {
             assert_ok!(test_benchmark_force_replace_proposal_tx::<Test>());
             assert_ok!(test_benchmark_set_btc_withdrawal_fee::<Test>());
             assert_ok!(test_benchmark_set_btc_deposit_limit::<Test>());
+            assert_ok!(test_benchmark_set_trustee_addr_type::<Test>());
         });
     }
 }
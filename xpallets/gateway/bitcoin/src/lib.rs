@@ -5,6 +5,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod header;
+mod migration;
+mod rpc;
 pub mod trustee;
 mod tx;
 mod types;
@@ -25,7 +27,7 @@ use frame_support::{
     dispatch::{DispatchError, DispatchResult, DispatchResultWithPostInfo},
     ensure,
     traits::{EnsureOrigin, UnixTime},
-    weights::Pays,
+    weights::{Pays, Weight},
 };
 use frame_system::{ensure_root, ensure_signed};
 use orml_utilities::with_transaction_result;
@@ -44,6 +46,7 @@ use light_bitcoin::{
 };
 
 use chainx_primitives::{AssetId, ReferralId};
+use xp_gateway_bitcoin::{Destination, WitnessAddress};
 use xp_gateway_common::AccountExtractor;
 use xp_logging::{debug, error, info};
 use xpallet_assets::{BalanceOf, Chain, ChainT, WithdrawalLimit};
@@ -53,15 +56,17 @@ use xpallet_gateway_common::{
 };
 use xpallet_support::try_addr;
 
-pub use self::types::{BtcAddress, BtcParams, BtcTxVerifier, BtcWithdrawalProposal};
+pub use self::rpc::*;
+pub use self::types::{
+    BtcAddress, BtcDepositCache, BtcHeaderIndex, BtcHeaderInfo, BtcParams, BtcRelayedTxInfo,
+    BtcTrusteeAddrType, BtcTxResult, BtcTxState, BtcTxVerifier, BtcWithdrawalProposal, Releases,
+    VoteResult,
+};
 pub use self::weights::WeightInfo;
 use self::{
     trustee::{get_current_trustee_address_pair, get_last_trustee_address_pair},
     tx::remove_pending_deposit,
-    types::{
-        BtcDepositCache, BtcHeaderIndex, BtcHeaderInfo, BtcRelayedTx, BtcRelayedTxInfo,
-        BtcTxResult, BtcTxState,
-    },
+    types::BtcRelayedTx,
 };
 
 // syntactic sugar for native log.
@@ -163,6 +168,8 @@ decl_error! {
         NoWithdrawalRecord,
         /// already vote for this withdrawal proposal
         DuplicateVote,
+        /// the input amounts do not match the inputs of the withdrawal tx
+        InvalidInputAmounts,
     }
 }
 
@@ -232,8 +239,16 @@ decl_storage! {
         pub BtcMinDeposit get(fn btc_min_deposit): u64 = 1 * 100000;
         /// max withdraw account count in bitcoin withdrawal transaction
         pub MaxWithdrawalCount get(fn max_withdrawal_count) config(): u32;
+        /// the type of the multisig address generated for the next trustee session
+        pub TrusteeAddrType get(fn trustee_addr_type): BtcTrusteeAddrType;
 
         Verifier get(fn verifier) config(): BtcTxVerifier;
+
+        /// Storage version of the pallet.
+        ///
+        /// New networks start with the last version.
+        pub StorageVersion get(fn storage_version)
+            build(|_: &GenesisConfig<T>| Releases::V2_0_0): Releases;
     }
     add_extra_genesis {
         config(genesis_hash): H256;
@@ -268,6 +283,10 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            migration::on_runtime_upgrade::<T>()
+        }
+
         /// if use `BtcHeader` struct would export in metadata, cause complex in front-end
         #[weight = <T as Trait>::WeightInfo::push_header()]
         pub fn push_header(origin, header: Vec<u8>) -> DispatchResultWithPostInfo {
@@ -307,16 +326,28 @@ decl_module! {
         /// Trustee create a proposal for a withdrawal list. `tx` is the proposal withdrawal transaction.
         /// The `tx` would have a sign for current creator or do not have sign. if creator do not sign
         /// for this transaction, he could do `sign_withdraw_tx` later.
+        ///
+        /// `input_amounts` are the values of the outputs spent by the inputs of `tx`, which are
+        /// required when the trustee address is P2WSH or `tx` spends any P2WSH output, pass an
+        /// empty list when `tx` only spends the P2SH outputs of a P2SH trustee address.
+        ///
+        /// NOTE: `input_amounts` is appended since `Releases::V2_0_0`, the old calls without it
+        /// can't be decoded any more.
         #[weight = <T as Trait>::WeightInfo::create_withdraw_tx()]
-        pub fn create_withdraw_tx(origin, withdrawal_id_list: Vec<u32>, tx: Vec<u8>) -> DispatchResult {
+        pub fn create_withdraw_tx(
+            origin,
+            withdrawal_id_list: Vec<u32>,
+            tx: Vec<u8>,
+            input_amounts: Vec<u64>
+        ) -> DispatchResult {
             let from = ensure_signed(origin)?;
             // committer must be in the trustee list
             Self::ensure_trustee(&from)?;
 
             let tx = Self::deserialize_tx(tx.as_slice())?;
-            native!(debug, "[create_withdraw_tx] from:{:?}, withdrawal list:{:?}, tx:{:?}", from, withdrawal_id_list, tx);
+            native!(debug, "[create_withdraw_tx] from:{:?}, withdrawal list:{:?}, tx:{:?}, input amounts:{:?}", from, withdrawal_id_list, tx, input_amounts);
 
-            Self::apply_create_withdraw(from, tx, withdrawal_id_list)?;
+            Self::apply_create_withdraw(from, tx, withdrawal_id_list, input_amounts)?;
             Ok(())
         }
 
@@ -386,12 +417,15 @@ decl_module! {
         /// trustees finish signing, the inputs are in double spend due other case. Thus could create
         /// a new valid transaction which outputs same to current proposal to replace current proposal
         /// transaction.)
+        ///
+        /// `input_amounts` are the same as the ones of `create_withdraw_tx`, which is appended
+        /// since `Releases::V2_0_0` as well.
         #[weight = <T as Trait>::WeightInfo::force_replace_proposal_tx()]
-        pub fn force_replace_proposal_tx(origin, tx: Vec<u8>) -> DispatchResult {
+        pub fn force_replace_proposal_tx(origin, tx: Vec<u8>, input_amounts: Vec<u64>) -> DispatchResult {
             T::TrusteeOrigin::try_origin(origin).map(|_| ()).or_else(ensure_root)?;
             let tx = Self::deserialize_tx(tx.as_slice())?;
-            native!(debug, "[force_replace_proposal_tx] new_tx:{:?}", tx);
-            Self::force_replace_withdraw_tx(tx)
+            native!(debug, "[force_replace_proposal_tx] new_tx:{:?}, input amounts:{:?}", tx, input_amounts);
+            Self::force_replace_withdraw_tx(tx, input_amounts)
         }
 
         /// Set bitcoin withdrawal fee
@@ -409,6 +443,18 @@ decl_module! {
             BtcMinDeposit::put(value);
             Ok(())
         }
+
+        /// Set the type of the multisig address for the next trustee session, the address of
+        /// the current trustee session is not affected.
+        ///
+        /// The withdrawals can still spend the outputs of the previous address type, since the
+        /// sigs of each input are checked in the way of the output it spends.
+        #[weight = <T as Trait>::WeightInfo::set_trustee_addr_type()]
+        pub fn set_trustee_addr_type(origin, addr_type: BtcTrusteeAddrType) -> DispatchResult {
+            T::TrusteeOrigin::try_origin(origin).map(|_| ()).or_else(ensure_root)?;
+            TrusteeAddrType::put(addr_type);
+            Ok(())
+        }
     }
 }
 
@@ -420,7 +466,7 @@ impl<T: Trait> ChainT<BalanceOf<T>> for Module<T> {
     }
 
     fn check_addr(addr: &[u8], _: &[u8]) -> DispatchResult {
-        // this addr is base58 addr
+        // this addr is base58 addr or bech32/bech32m addr
         let address = Self::verify_btc_address(addr).map_err(|err| {
             error!(
                 "[verify_btc_address] Verify failed, error:{:?}, source addr:{:?}",
@@ -430,6 +476,18 @@ impl<T: Trait> ChainT<BalanceOf<T>> for Module<T> {
             err
         })?;
 
+        // the withdrawal to the address of another network could never be processed
+        let network = Self::network_id();
+        if address.network() != network {
+            error!(
+                "[check_addr] Address network mismatch, expect:{:?}, got:{:?}, source addr:{:?}",
+                network,
+                address.network(),
+                try_addr(addr)
+            );
+            return Err(Error::<T>::InvalidAddress.into());
+        }
+
         match get_current_trustee_address_pair::<T>() {
             Ok((hot_addr, cold_addr)) => {
                 // do not allow withdraw from trustee address
@@ -461,7 +519,17 @@ impl<T: Trait> ChainT<BalanceOf<T>> for Module<T> {
 }
 
 impl<T: Trait> Module<T> {
-    pub fn verify_btc_address(data: &[u8]) -> Result<Address, DispatchError> {
+    /// Verifies the bitcoin address, which is either a bech32/bech32m native SegWit
+    /// address (P2WPKH/P2WSH/P2TR) or a base58 legacy address (P2PKH/P2SH).
+    pub fn verify_btc_address(data: &[u8]) -> Result<Destination, DispatchError> {
+        if let Some(addr) = WitnessAddress::from_bech32(data) {
+            return Ok(addr.into());
+        }
+        Self::verify_btc_legacy_address(data).map(Into::into)
+    }
+
+    /// Verifies the base58 legacy bitcoin address (P2PKH/P2SH).
+    pub fn verify_btc_legacy_address(data: &[u8]) -> Result<Address, DispatchError> {
         let r = bs58::decode(data)
             .into_vec()
             .map_err(|_| Error::<T>::InvalidBase58)?;
@@ -470,6 +538,9 @@ impl<T: Trait> Module<T> {
     }
 
     /// Helper function for deserializing the slice of raw tx.
+    ///
+    /// Both the legacy and the SegWit serialization (BIP-144) are accepted, the witness
+    /// data is kept in the inputs but excluded from the txid.
     #[inline]
     fn deserialize_tx(input: &[u8]) -> Result<Transaction, Error<T>> {
         deserialize(Reader::new(input)).map_err(|_| Error::<T>::DeserializeErr)
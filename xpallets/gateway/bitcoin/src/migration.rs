@@ -0,0 +1,56 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of the storage migrations of Bitcoin Gateway.
+
+use super::*;
+use codec::{Decode, Encode};
+use xp_logging::warn;
+
+/// Applies the migrations from the current storage version.
+pub(crate) fn on_runtime_upgrade<T: Trait>() -> Weight {
+    let mut weight = T::DbWeight::get().reads(1);
+
+    if Module::<T>::storage_version() == Releases::V1_0_0 {
+        weight = weight.saturating_add(migrate_withdrawal_proposal::<T>());
+        StorageVersion::put(Releases::V2_0_0);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+    }
+
+    weight
+}
+
+/// `BtcWithdrawalProposal` of `Releases::V1_0_0`.
+#[derive(Encode, Decode)]
+struct OldBtcWithdrawalProposal<AccountId> {
+    sig_state: VoteResult,
+    withdrawal_id_list: Vec<u32>,
+    tx: Transaction,
+    trustee_list: Vec<(AccountId, bool)>,
+}
+
+/// Appends the empty `input_amounts` to the pending `WithdrawalProposal`.
+///
+/// The proposals before are always spending the P2SH trustee address, of which the
+/// signature doesn't commit to the input amounts.
+fn migrate_withdrawal_proposal<T: Trait>() -> Weight {
+    let translated =
+        WithdrawalProposal::<T>::translate::<OldBtcWithdrawalProposal<T::AccountId>, _>(|old| {
+            old.map(|old| {
+                BtcWithdrawalProposal::new(
+                    old.sig_state,
+                    old.withdrawal_id_list,
+                    old.tx,
+                    old.trustee_list,
+                    Vec::new(),
+                )
+            })
+        });
+    match translated {
+        Ok(proposal) => info!(
+            "[migrate_withdrawal_proposal] withdrawal proposal:{:?}",
+            proposal
+        ),
+        Err(_) => warn!("[migrate_withdrawal_proposal] failed to decode the old proposal"),
+    }
+    T::DbWeight::get().reads_writes(1, 1)
+}
@@ -0,0 +1,133 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+use xpallet_gateway_common::utils::two_thirds_unsafe;
+
+use super::*;
+use crate::{trustee::current_trustee_session, tx::addr2vecu8};
+
+/// The block header with its hash and whether it's in the main chain.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct BtcHeaderDetails {
+    pub hash: H256,
+    pub info: BtcHeaderInfo,
+    pub is_main_chain: bool,
+}
+
+/// The vote of a trustee on the withdrawal proposal.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum BtcTrusteeVote {
+    /// The trustee has signed the withdrawal transaction.
+    Signed,
+    /// The trustee has rejected the withdrawal proposal.
+    Rejected,
+    /// The trustee has not voted yet.
+    NotVoted,
+}
+
+/// The withdrawal proposal with the vote state of all the current trustees.
+#[derive(PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct BtcWithdrawalProposalDetails<AccountId> {
+    pub proposal: BtcWithdrawalProposal<AccountId>,
+    /// The number of signatures required to finish the proposal.
+    pub required_sigs: u32,
+    /// The votes of the current trustees and the voters who are no longer trustees.
+    pub trustee_votes: Vec<(AccountId, BtcTrusteeVote)>,
+}
+
+/// The parameters of the bitcoin gateway.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct BtcGatewayParams {
+    pub network: BtcNetwork,
+    pub params: BtcParams,
+    pub confirmation_number: u32,
+    pub min_deposit: u64,
+    pub withdrawal_fee: u64,
+    pub max_withdrawal_count: u32,
+    pub trustee_addr_type: BtcTrusteeAddrType,
+}
+
+impl<T: Trait> Module<T> {
+    /// Returns the details of the block header `hash`, including the forked ones.
+    pub fn btc_header_details(hash: H256) -> Option<BtcHeaderDetails> {
+        Self::headers(&hash).map(|info| BtcHeaderDetails {
+            hash,
+            info,
+            is_main_chain: Self::main_chain(&hash),
+        })
+    }
+
+    /// Returns the details of the block header at `height` in the main chain.
+    pub fn btc_main_chain_header_details(height: u32) -> Option<BtcHeaderDetails> {
+        Self::block_hash_for(height)
+            .into_iter()
+            .find(|hash| Self::main_chain(hash))
+            .and_then(Self::btc_header_details)
+    }
+
+    /// Returns the unclaimed deposits of the bitcoin address, the address is
+    /// normalized before the lookup, e.g., the uppercase bech32 address.
+    pub fn btc_pending_deposits(addr: BtcAddress) -> Result<Vec<BtcDepositCache>, DispatchError> {
+        let addr = Self::verify_btc_address(&addr)?;
+        Ok(Self::pending_deposits(addr2vecu8(&addr)))
+    }
+
+    /// Returns the current withdrawal proposal with the vote of each trustee.
+    pub fn btc_withdrawal_proposal_details() -> Option<BtcWithdrawalProposalDetails<T::AccountId>> {
+        let proposal = Self::withdrawal_proposal()?;
+        let trustees = current_trustee_session::<T>()
+            .map(|session_info| session_info.trustee_list)
+            .unwrap_or_default();
+
+        let vote_of = |who: &T::AccountId| {
+            proposal
+                .trustee_list
+                .iter()
+                .find(|(voter, _)| voter == who)
+                .map_or(BtcTrusteeVote::NotVoted, |(_, signed)| {
+                    if *signed {
+                        BtcTrusteeVote::Signed
+                    } else {
+                        BtcTrusteeVote::Rejected
+                    }
+                })
+        };
+        let mut trustee_votes = trustees
+            .iter()
+            .map(|who| (who.clone(), vote_of(who)))
+            .collect::<Vec<_>>();
+        // the trustee session may have changed since the proposal was created.
+        for (voter, _) in proposal.trustee_list.iter() {
+            if !trustees.contains(voter) {
+                trustee_votes.push((voter.clone(), vote_of(voter)));
+            }
+        }
+
+        Some(BtcWithdrawalProposalDetails {
+            required_sigs: two_thirds_unsafe(trustees.len() as u32),
+            trustee_votes,
+            proposal,
+        })
+    }
+
+    /// Returns the parameters of the bitcoin gateway.
+    pub fn btc_gateway_params() -> BtcGatewayParams {
+        BtcGatewayParams {
+            network: Self::network_id(),
+            params: Self::params_info(),
+            confirmation_number: Self::confirmation_number(),
+            min_deposit: Self::btc_min_deposit(),
+            withdrawal_fee: Self::btc_withdrawal_fee(),
+            max_withdrawal_count: Self::max_withdrawal_count(),
+            trustee_addr_type: Self::trustee_addr_type(),
+        }
+    }
+}
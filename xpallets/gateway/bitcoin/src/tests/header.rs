@@ -65,6 +65,42 @@ fn test_insert_headers() {
         })
 }
 
+#[test]
+fn test_header_details() {
+    let (base_height, c1, forked) = generate_blocks_478557_478563();
+    ExtBuilder::default()
+        .build_mock(
+            (c1.get(1).unwrap().clone(), base_height + 1),
+            Network::Mainnet,
+        )
+        .execute_with(|| {
+            assert_ok!(XGatewayBitcoin::apply_push_header(
+                c1.get(2).unwrap().clone()
+            ));
+            assert_ok!(XGatewayBitcoin::apply_push_header(
+                forked.get(2).unwrap().clone()
+            ));
+            assert_ok!(XGatewayBitcoin::apply_push_header(
+                forked.get(3).unwrap().clone()
+            ));
+
+            // the forked header is still available by hash
+            let details = XGatewayBitcoin::btc_header_details(c1.get(2).unwrap().hash()).unwrap();
+            assert_eq!(details.info.header, c1.get(2).unwrap().clone());
+            assert_eq!(details.info.height, base_height + 2);
+            assert!(!details.is_main_chain);
+
+            // only the header of the main chain is returned by height
+            let details = XGatewayBitcoin::btc_main_chain_header_details(base_height + 2).unwrap();
+            assert_eq!(details.hash, forked.get(2).unwrap().hash());
+            assert!(details.is_main_chain);
+            assert_eq!(
+                XGatewayBitcoin::btc_main_chain_header_details(base_height + 4),
+                None
+            );
+        })
+}
+
 fn should_in_mainchain(headers: &[BlockHeader], expect: bool) {
     for header in headers.iter() {
         assert_eq!(XGatewayBitcoin::main_chain(&header.hash()), expect);
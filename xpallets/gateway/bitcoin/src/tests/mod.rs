@@ -11,12 +11,42 @@ use xp_gateway_common::AccountExtractor;
 use light_bitcoin::script::Script;
 
 use crate::mock::{Test, XGatewayBitcoin};
-use crate::Trait;
+use crate::{h256_rev, Trait};
 
 #[test]
 pub fn test_verify_btc_address() {
     let address = b"mqVznxoxdeSNYgDCg6ZVE5pc6476BY6zHK".to_vec();
     assert!(XGatewayBitcoin::verify_btc_address(&address).is_ok());
+
+    // native SegWit address
+    let address = b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_vec();
+    assert!(XGatewayBitcoin::verify_btc_address(&address).is_ok());
+    assert!(XGatewayBitcoin::verify_btc_legacy_address(&address).is_err());
+    let address = b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7".to_vec();
+    assert!(XGatewayBitcoin::verify_btc_address(&address).is_ok());
+    // p2tr
+    let address = b"bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0".to_vec();
+    assert!(XGatewayBitcoin::verify_btc_address(&address).is_ok());
+    // witness version 1 with the bech32 checksum
+    let address =
+        b"bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx".to_vec();
+    assert!(XGatewayBitcoin::verify_btc_address(&address).is_err());
+    // invalid checksum
+    let address = b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k8".to_vec();
+    assert!(XGatewayBitcoin::verify_btc_address(&address).is_err());
+}
+
+#[test]
+fn test_deserialize_witness_tx() {
+    // txid: e41061d3ad1d6a46c69be30475e23446cccf1a05e4dc9eaf6bc33443e51b0f2f (witness)
+    let raw = hex::decode("020000000001011529f2fbaca4cc374e12409cc3db0a8fe2509894f8b79f1f67d648f488d7a1f50100000017160014b1ef3d9fd4a68b53e75c56845076bfb4b4ae3974ffffffff03307500000000000017a914cb94110435d0635223eebe25ed2aaabc03781c4587bfe400000000000017a9141df425d522de50d46c32f979d73b823887446fd0870000000000000000016a02483045022100d591090fd8f0d62145d967fad754533fcdb5e7180c8644d16d071c3c5dfcb3a802200ee6cea9eb146d7e24b4142c36baa19e9c4c70095ef9b3ccc736247ecf0b8ed3012102632394028f212c1bc88f01dd14b4f8bc81c16ef464c830021030062a8f7788ae00000000").unwrap();
+    let tx = XGatewayBitcoin::deserialize_tx(&raw).unwrap();
+    assert!(tx.has_witness());
+    // the txid excludes the witness data
+    assert_eq!(
+        tx.hash(),
+        h256_rev("e41061d3ad1d6a46c69be30475e23446cccf1a05e4dc9eaf6bc33443e51b0f2f")
+    );
 }
 
 #[test]
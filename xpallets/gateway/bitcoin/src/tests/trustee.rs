@@ -1,28 +1,44 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
-use frame_support::{assert_noop, assert_ok, storage::StorageValue};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::{unhashed, StorageDoubleMap, StorageValue},
+    traits::OnRuntimeUpgrade,
+};
 use frame_system::RawOrigin;
 use hex_literal::hex;
 
 use light_bitcoin::{
     chain::Transaction,
     crypto::dhash160,
+    crypto::sha256,
     keys::{Address, Network, Public, Type},
-    script::{Builder, Opcode},
+    primitives::Bytes,
+    script::{Builder, Opcode, Script, SignatureVersion},
     serialization::{self, Reader},
 };
 
-use xpallet_gateway_common::traits::TrusteeForChain;
+use xp_gateway_bitcoin::{WitnessAddress, WitnessProgram};
+use xpallet_assets::{Chain, ChainT};
+use xpallet_gateway_common::{
+    traits::TrusteeForChain, types::GenericTrusteeSessionInfo, TrusteeSessionInfoOf,
+};
 
 use crate::mock::{
-    alice, bob, AccountId, ExtBuilder, Test, XGatewayBitcoin, XGatewayBitcoinErr, XGatewayCommon,
-    XGatewayRecords, X_BTC,
+    alice, bob, trustees, AccountId, ExtBuilder, Test, XGatewayBitcoin, XGatewayBitcoinErr,
+    XGatewayCommon, XGatewayRecords, X_BTC,
 };
 use crate::{
-    trustee::create_multi_address,
-    tx::validator::parse_and_check_signed_tx_impl,
-    types::{BtcTxVerifier, BtcWithdrawalProposal, VoteResult},
-    Verifier, WithdrawalProposal,
+    trustee::{create_multi_address, current_trustee_session},
+    tx::{
+        secp256k1_verifier::verify_sig,
+        validator::{
+            check_input_amounts, parse_and_check_signed_tx, parse_and_check_signed_tx_impl,
+        },
+    },
+    types::{BtcTrusteeAddrType, BtcTxVerifier, BtcWithdrawalProposal, VoteResult},
+    Releases, StorageVersion, Verifier, WithdrawalProposal,
 };
 
 #[test]
@@ -98,8 +114,10 @@ fn test_create_multi_address() {
     cold_keys.push(Public::from_slice(&pubkey8_bytes).unwrap());
 
     ExtBuilder::default().build_and_execute(|| {
-        let hot_info = create_multi_address::<Test>(&hot_keys, 3).unwrap();
-        let cold_info = create_multi_address::<Test>(&cold_keys, 3).unwrap();
+        let hot_info =
+            create_multi_address::<Test>(&hot_keys, 3, BtcTrusteeAddrType::P2SH).unwrap();
+        let cold_info =
+            create_multi_address::<Test>(&cold_keys, 3, BtcTrusteeAddrType::P2SH).unwrap();
         let real_hot_addr = b"39eBWF3miGWb4CPiHw4MfsSwHcjtGq2pYL".to_vec();
         let real_cold_addr = b"3AWmpzJ1kSF1cktFTDEb3qmLcdN8YydxA7".to_vec();
         assert_eq!(hot_info.addr, real_hot_addr);
@@ -120,12 +138,237 @@ fn test_create_multi_address() {
     });
 }
 
+#[test]
+fn test_create_witness_multi_address() {
+    let hot_keys = [
+        hex!("03f72c448a0e59f48d4adef86cba7b278214cece8e56ef32ba1d179e0a8129bdba"),
+        hex!("0306117a360e5dbe10e1938a047949c25a86c0b0e08a0a7c1e611b97de6b2917dd"),
+        hex!("0311252930af8ba766b9c7a6580d8dc4bbf9b0befd17a8ef7fabac275bba77ae40"),
+        hex!("0227e54b65612152485a812b8856e92f41f64788858466cc4d8df674939a5538c3"),
+    ]
+    .iter()
+    .map(|key| Public::from_slice(key).unwrap())
+    .collect::<Vec<_>>();
+
+    ExtBuilder::default().build_and_execute(|| {
+        let legacy_info =
+            create_multi_address::<Test>(&hot_keys, 3, BtcTrusteeAddrType::P2SH).unwrap();
+        let witness_info =
+            create_multi_address::<Test>(&hot_keys, 3, BtcTrusteeAddrType::P2WSH).unwrap();
+        // the same multisig script with different address
+        assert_eq!(witness_info.redeem_script, legacy_info.redeem_script);
+        assert_ne!(witness_info.addr, legacy_info.addr);
+
+        let addr = WitnessAddress::from_bech32(&witness_info.addr).unwrap();
+        assert_eq!(addr.network, Network::Mainnet);
+        assert_eq!(
+            addr.program,
+            WitnessProgram::ScriptHash(sha256(&witness_info.redeem_script))
+        );
+        assert_eq!(
+            XGatewayBitcoin::verify_btc_address(&witness_info.addr),
+            Ok(addr.into())
+        );
+    });
+}
+
+#[test]
+fn test_verify_witness_signed_tx() {
+    ExtBuilder::default().build_and_execute(|| {
+        // test would ignore sign check and always return true
+        Verifier::put(BtcTxVerifier::Test);
+
+        let script: Script = "522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253ae".parse().unwrap();
+        let script_bytes = script.to_bytes();
+        let sig: Bytes = vec![0x30; 71].into();
+
+        // https://btc.com/62c389f1974b8a44737d76f92da0f5cd7f6f48d065e7af6ba368298361141270.rawhex
+        let mut tx = "0100000001052ceda6cf9c93012a994f4ffa2a29c9e31ecf96f472b175eb8e602bfa2b2c5100000000fdfd000047304402200e4d732c456f4722d376252be16554edb27fc93c55db97859e16682bc62b014502202b9c4b01ad55daa1f76e6a564b7762cd0a81240c947806ab3f3b056f2e77c1da01483045022100c7cd680992de60da8c33fc3ef7f5ead85b204660822d9fbda2d85f9fadba732a022021fdc49b20a6007ea971a385732a4065d1d7c792ac9dc391034fb78aa9f5034b014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff03e0349500000000001976a91413256ff2dee6e80c275ddb877abc1ffe453a731488ace00f9700000000001976a914ea6e8dd56703ace584eb9dff0224629f8486672988acc88a02000000000017a914cb94110435d0635223eebe25ed2aaabc03781c458700000000".parse::<Transaction>().unwrap();
+        tx.inputs[0].script_sig = Bytes::default();
+
+        // no witness, i.e., no sig
+        assert_eq!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script.clone(), &[10000]),
+            Ok(0)
+        );
+
+        let dummy = Bytes::default();
+        tx.inputs[0].script_witness =
+            vec![dummy.clone(), sig.clone(), sig.clone(), script_bytes.clone()];
+        assert_eq!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script.clone(), &[10000]),
+            Ok(2)
+        );
+        // the input amounts must match the inputs
+        assert_noop!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script.clone(), &[]),
+            XGatewayBitcoinErr::InvalidInputAmounts
+        );
+
+        // the witness script must be the trustee multisig script
+        tx.inputs[0].script_witness = vec![dummy.clone(), sig.clone(), sig.clone(), sig.clone()];
+        assert_noop!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script.clone(), &[10000]),
+            XGatewayBitcoinErr::BadSignature
+        );
+
+        // the script sig of the native SegWit input must be empty
+        tx.inputs[0].script_witness = vec![dummy, sig.clone(), script_bytes];
+        tx.inputs[0].script_sig = sig;
+        assert_noop!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script, &[10000]),
+            XGatewayBitcoinErr::BadSignature
+        );
+    });
+}
+
+#[test]
+fn test_verify_bip143_sig() {
+    ExtBuilder::default().build_and_execute(|| {
+        assert_eq!(XGatewayBitcoin::verifier(), BtcTxVerifier::Recover);
+
+        // the native P2WPKH example of BIP143
+        // https://github.com/bitcoin/bips/blob/master/bip-0143.mediawiki#native-p2wpkh
+        let tx = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000".parse::<Transaction>().unwrap();
+        let script_code: Bytes = hex!("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac").to_vec().into();
+        let pubkey: Bytes = hex!("025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357").to_vec().into();
+        let sig: Bytes = hex!("304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01").to_vec().into();
+        let amount = 600_000_000;
+
+        assert_ok!(verify_sig::<Test>(&sig, &pubkey, &tx, &script_code, 1, amount, SignatureVersion::WitnessV0));
+        // the amount of the spent output is committed by the sig
+        assert!(verify_sig::<Test>(&sig, &pubkey, &tx, &script_code, 1, amount + 1, SignatureVersion::WitnessV0).is_err());
+        // the legacy sighash is not the same
+        assert!(verify_sig::<Test>(&sig, &pubkey, &tx, &script_code, 1, amount, SignatureVersion::Base).is_err());
+    });
+}
+
+#[test]
+fn test_verify_witness_signed_tx_with_recover() {
+    ExtBuilder::default().build_and_execute(|| {
+        assert_eq!(XGatewayBitcoin::verifier(), BtcTxVerifier::Recover);
+
+        // a 2-of-3 P2WSH multisig spending 1 BTC, signed by the 1st and the 3rd keys,
+        // the private keys are sha256("trustee0"), sha256("trustee1") and sha256("trustee2").
+        // the sigs are generated with an independent BIP143 implementation, which reproduces
+        // the sighash of the BIP143 native P2WPKH example above.
+        let script: Script = "522103bf4b5edcaaddc406c05cafd587bc032b590e4702eb0ca2a58cf6182298993d1521036834235f721bd841e3edf096607dc19067c00ea5ecb23527f0b3b70f0ddd140b2102ab14dc072ada032d1eede6f3319009d767e5b5600ec6230d674e574f313e8d4753ae".parse().unwrap();
+        let tx = "010000000001017b09769b57c82e523ddaf7f5077d19b8047333ad18042c19401e9f96f9a37b240000000000ffffffff01f0b9f505000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac040047304402202c796657f5110e319c1e5febb0c1ad19ec244d787d18153e4c6c199446fba25502202f05739721db94a666b35690284703dc2d9a11bf55f9836b6907be76deba5f3801483045022100dd3ecd5f307b6cd72d2be49d9f06ff2da6b1bc38afdd573a4fae38f2ac7260eb0220556c4e3f70d8082e5a1e71778b9b16ca589d7ba46b2fd3e51439e31bb7b88b740169522103bf4b5edcaaddc406c05cafd587bc032b590e4702eb0ca2a58cf6182298993d1521036834235f721bd841e3edf096607dc19067c00ea5ecb23527f0b3b70f0ddd140b2102ab14dc072ada032d1eede6f3319009d767e5b5600ec6230d674e574f313e8d4753ae00000000".parse::<Transaction>().unwrap();
+        let amount = 100_000_000;
+
+        assert_eq!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script.clone(), &[amount]),
+            Ok(2)
+        );
+        // a wrong input amount would make the sigs invalid
+        assert_noop!(
+            parse_and_check_signed_tx_impl::<Test>(&tx, script, &[amount + 1]),
+            XGatewayBitcoinErr::VerifySignFailed
+        );
+    });
+}
+
+#[test]
+fn test_verify_signed_tx_spending_both_addresses() {
+    ExtBuilder::default().build_and_execute(|| {
+        // test would ignore sign check and always return true
+        Verifier::put(BtcTxVerifier::Test);
+
+        // switch the hot address of the current session from P2SH to P2WSH with the same
+        // trustees, the UTXOs of the previous P2SH address are still spendable.
+        let hot_keys = trustees()
+            .into_iter()
+            .map(|(_, _, hot_key, _)| Public::from_slice(&hot_key).unwrap())
+            .collect::<Vec<_>>();
+        let mut session = current_trustee_session::<Test>().unwrap();
+        session.hot_address =
+            create_multi_address::<Test>(&hot_keys, 2, BtcTrusteeAddrType::P2WSH).unwrap();
+        let script_bytes: Bytes = session.hot_address.redeem_script.clone().into();
+        let number = XGatewayCommon::trustee_session_info_len(Chain::Bitcoin) - 1;
+        TrusteeSessionInfoOf::<Test>::insert(
+            Chain::Bitcoin,
+            number,
+            GenericTrusteeSessionInfo::from(session),
+        );
+
+        // https://btc.com/62c389f1974b8a44737d76f92da0f5cd7f6f48d065e7af6ba368298361141270.rawhex
+        let mut tx = "0100000001052ceda6cf9c93012a994f4ffa2a29c9e31ecf96f472b175eb8e602bfa2b2c5100000000fdfd000047304402200e4d732c456f4722d376252be16554edb27fc93c55db97859e16682bc62b014502202b9c4b01ad55daa1f76e6a564b7762cd0a81240c947806ab3f3b056f2e77c1da01483045022100c7cd680992de60da8c33fc3ef7f5ead85b204660822d9fbda2d85f9fadba732a022021fdc49b20a6007ea971a385732a4065d1d7c792ac9dc391034fb78aa9f5034b014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff03e0349500000000001976a91413256ff2dee6e80c275ddb877abc1ffe453a731488ace00f9700000000001976a914ea6e8dd56703ace584eb9dff0224629f8486672988acc88a02000000000017a914cb94110435d0635223eebe25ed2aaabc03781c458700000000".parse::<Transaction>().unwrap();
+        // the outputs of tx: 9778400 + 9900000 + 166600
+        let total_output = 19845000;
+
+        // the 1st input spends the P2SH address with the sigs in the script sig,
+        // the 2nd input spends the P2WSH address with the sigs in the witness.
+        let mut witness_input = tx.inputs[0].clone();
+        witness_input.previous_output.index += 1;
+        witness_input.script_sig = Bytes::default();
+        let sig: Bytes = vec![0x30; 71].into();
+        witness_input.script_witness = vec![Bytes::default(), sig.clone(), sig, script_bytes];
+        tx.inputs.push(witness_input);
+
+        let input_amounts = [total_output - 10000, 10000];
+        assert_eq!(
+            parse_and_check_signed_tx::<Test>(&tx, &input_amounts, 1),
+            Ok(2)
+        );
+        // the amounts are required by the witness sigs
+        assert_noop!(
+            parse_and_check_signed_tx::<Test>(&tx, &[], 1),
+            XGatewayBitcoinErr::InvalidInputAmounts
+        );
+        // all inputs must have the same sigs count
+        tx.inputs[1].script_witness.remove(1);
+        assert_noop!(
+            parse_and_check_signed_tx::<Test>(&tx, &input_amounts, 1),
+            XGatewayBitcoinErr::InvalidSignCount
+        );
+    });
+}
+
+#[test]
+fn test_check_input_amounts() {
+    // https://btc.com/62c389f1974b8a44737d76f92da0f5cd7f6f48d065e7af6ba368298361141270.rawhex
+    let tx = "0100000001052ceda6cf9c93012a994f4ffa2a29c9e31ecf96f472b175eb8e602bfa2b2c5100000000fdfd000047304402200e4d732c456f4722d376252be16554edb27fc93c55db97859e16682bc62b014502202b9c4b01ad55daa1f76e6a564b7762cd0a81240c947806ab3f3b056f2e77c1da01483045022100c7cd680992de60da8c33fc3ef7f5ead85b204660822d9fbda2d85f9fadba732a022021fdc49b20a6007ea971a385732a4065d1d7c792ac9dc391034fb78aa9f5034b014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff03e0349500000000001976a91413256ff2dee6e80c275ddb877abc1ffe453a731488ace00f9700000000001976a914ea6e8dd56703ace584eb9dff0224629f8486672988acc88a02000000000017a914cb94110435d0635223eebe25ed2aaabc03781c458700000000".parse::<Transaction>().unwrap();
+    // the outputs of tx: 9778400 + 9900000 + 166600
+    let total_output = 19845000;
+    let err = Err(XGatewayBitcoinErr::InvalidInputAmounts.into());
+
+    // the fee of 2 withdrawals is at most 2 * 10000
+    assert_eq!(
+        check_input_amounts::<Test>(&tx, &[total_output], 2, 10000),
+        Ok(())
+    );
+    assert_eq!(
+        check_input_amounts::<Test>(&tx, &[total_output + 20000], 2, 10000),
+        Ok(())
+    );
+    // the input amounts can not imply a larger fee
+    assert_eq!(
+        check_input_amounts::<Test>(&tx, &[total_output + 20001], 2, 10000),
+        err
+    );
+    assert_eq!(
+        check_input_amounts::<Test>(&tx, &[u64::max_value()], 2, 10000),
+        err
+    );
+    // the input amounts must cover the outputs
+    assert_eq!(
+        check_input_amounts::<Test>(&tx, &[total_output - 1], 2, 10000),
+        err
+    );
+    // the input amounts must match the inputs
+    assert_eq!(check_input_amounts::<Test>(&tx, &[], 2, 10000), err);
+    assert_eq!(
+        check_input_amounts::<Test>(&tx, &[total_output, 0], 2, 10000),
+        err
+    );
+}
+
 #[test]
 fn test_verify_signed() {
     let full_sig_tx = "010000000317840b38d466580696e9cb065c7a7aa55cb58cd5eb2526a10c3a30cc06d4b50a05000000fdfd0000483045022100dabbf878df8cacb23c08a8b5414cd64392a3f84777db4c01d8eec1e06d2e03fb0220502bd6e3960b68452699a40debfd92ac02e45d1526a2b570f5b28abdb496706401473044022047c58c3ad586d93f4b4caf65230a21e0ff70475b66affb8d4f92e916e6f6f664022029231b30472a949648dd99585ccbb169ccc2c007ad5387f580d41affdc8b37b6014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff853c87b1ecb4e881f323fec5314cb8623ca15de1341694e8352f99c434e7046a02000000fdfe0000483045022100b1b2233f70434f4079c1a8be1be5843b4dfe1edea30a3533aa94781af9984b2e02201ef78527ced51c7b122568666b9499d9cd2d4c3e704f5a54ebe433489c91b20101483045022100bde660b2f6f3c6fa512794377564289cbfcbeab6ecba1fe3b0b1531ebaa7d00a02207ea5435312280e0b502de715a6cbff7de866ba508a5fe8a644b88540ed471aee014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff442214a2d5a31195d6849005699892f60d48d89bca15bdb4ad6349c083e9936202000000fdfd000047304402205960c277575a7d2bb719211fe9cee0dd398c5a64d3a258fb0f877ae176dd11af02206cc0be53b1d5ea59477f9d2103ce06b61608561ac466c72235e86b26fe45734d01483045022100dcbd79d6f2d9504e2ea1578b7fdc9f98dadc018708acb4b87bd8b154312edfaa022043197a5b72219dc9603a81146a65c724a09022229ada2e3101a002dbd834b591014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff0340ebd201000000001976a9148e2fbed4fc7481a9a51f2bfe204301a122473f2f88ac406fdf25000000001976a914ede61104eddc07594f0c0cf43fecb9675353d16288ac91a3f6070000000017a914cb94110435d0635223eebe25ed2aaabc03781c458700000000".parse().unwrap();
     let script = "522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253ae".parse().unwrap();
     ExtBuilder::default().build_and_execute(|| {
-        let result = parse_and_check_signed_tx_impl::<Test>(&full_sig_tx, script);
+        let result = parse_and_check_signed_tx_impl::<Test>(&full_sig_tx, script, &[]);
         assert_eq!(result, Ok(2))
     });
 }
@@ -157,12 +400,38 @@ fn test_verify_tx_sign() {
             let bytes = hex::decode(tx_hex).unwrap();
             let tx: Transaction = serialization::deserialize(Reader::new(&bytes)).unwrap();
             let script = script_hex.parse().unwrap();
-            let got = parse_and_check_signed_tx_impl::<Test>(&tx, script);
+            let got = parse_and_check_signed_tx_impl::<Test>(&tx, script, &[]);
             assert_eq!(got, Ok(expect));
         }
     });
 }
 
+#[test]
+fn check_addr_should_reject_the_other_network() {
+    ExtBuilder::default().build_and_execute(|| {
+        // the mock runs on the mainnet
+        assert_ok!(XGatewayBitcoin::check_addr(
+            b"12kEgqNShFw7BN27QCMQZCynQpSuV4x1Ax",
+            b""
+        ));
+        assert_ok!(XGatewayBitcoin::check_addr(
+            b"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            b""
+        ));
+        assert_noop!(
+            XGatewayBitcoin::check_addr(b"mqVznxoxdeSNYgDCg6ZVE5pc6476BY6zHK", b""),
+            XGatewayBitcoinErr::InvalidAddress
+        );
+        assert_noop!(
+            XGatewayBitcoin::check_addr(
+                b"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                b""
+            ),
+            XGatewayBitcoinErr::InvalidAddress
+        );
+    });
+}
+
 #[test]
 fn force_replace_withdraw() {
     ExtBuilder::default().build_and_execute(|| {
@@ -205,6 +474,7 @@ fn force_replace_withdraw() {
             withdrawal_id_list: vec![0, 1],
             tx: old_withdraw.clone(),
             trustee_list: vec![(alice, true), (bob, true)],
+            input_amounts: vec![],
         };
         WithdrawalProposal::<Test>::put(proposal);
 
@@ -213,7 +483,43 @@ fn force_replace_withdraw() {
         new_withdraw.inputs = tmp.inputs; // replace inputs
 
         let raw = serialization::serialize(&new_withdraw);
-        assert_ok!(XGatewayBitcoin::force_replace_proposal_tx(RawOrigin::Root.into(), raw.into()));
+        assert_ok!(XGatewayBitcoin::force_replace_proposal_tx(
+            RawOrigin::Root.into(),
+            raw.into(),
+            vec![]
+        ));
         assert_eq!(XGatewayBitcoin::withdrawal_proposal().unwrap().tx, new_withdraw);
     });
 }
+
+#[test]
+fn withdrawal_proposal_should_be_migrated_on_runtime_upgrade() {
+    ExtBuilder::default().build_and_execute(|| {
+        let alice = alice();
+        // https://btc.com/62c389f1974b8a44737d76f92da0f5cd7f6f48d065e7af6ba368298361141270.rawhex
+        let tx = "0100000001052ceda6cf9c93012a994f4ffa2a29c9e31ecf96f472b175eb8e602bfa2b2c5100000000fdfd000047304402200e4d732c456f4722d376252be16554edb27fc93c55db97859e16682bc62b014502202b9c4b01ad55daa1f76e6a564b7762cd0a81240c947806ab3f3b056f2e77c1da01483045022100c7cd680992de60da8c33fc3ef7f5ead85b204660822d9fbda2d85f9fadba732a022021fdc49b20a6007ea971a385732a4065d1d7c792ac9dc391034fb78aa9f5034b014c69522102df92e88c4380778c9c48268460a124a8f4e7da883f80477deaa644ced486efc6210244d81efeb4171b1a8a433b87dd202117f94e44c909c49e42e77b69b5a6ce7d0d2103a36339f413da869df12b1ab0def91749413a0dee87f0bfa85ba7196e6cdad10253aeffffffff03e0349500000000001976a91413256ff2dee6e80c275ddb877abc1ffe453a731488ace00f9700000000001976a914ea6e8dd56703ace584eb9dff0224629f8486672988acc88a02000000000017a914cb94110435d0635223eebe25ed2aaabc03781c458700000000".parse::<Transaction>().unwrap();
+        // The proposal without `input_amounts`.
+        unhashed::put_raw(
+            &WithdrawalProposal::<Test>::hashed_key(),
+            &(VoteResult::Unfinish, vec![0u32, 1], tx.clone(), vec![(alice.clone(), true)]).encode(),
+        );
+        StorageVersion::put(Releases::V1_0_0);
+        assert_eq!(XGatewayBitcoin::withdrawal_proposal(), None);
+
+        XGatewayBitcoin::on_runtime_upgrade();
+
+        let expected = BtcWithdrawalProposal::<AccountId> {
+            sig_state: VoteResult::Unfinish,
+            withdrawal_id_list: vec![0, 1],
+            tx,
+            trustee_list: vec![(alice, true)],
+            input_amounts: vec![],
+        };
+        assert_eq!(XGatewayBitcoin::withdrawal_proposal(), Some(expected.clone()));
+        assert_eq!(XGatewayBitcoin::storage_version(), Releases::V2_0_0);
+
+        // Migrating again is a no-op.
+        XGatewayBitcoin::on_runtime_upgrade();
+        assert_eq!(XGatewayBitcoin::withdrawal_proposal(), Some(expected));
+    });
+}
@@ -103,8 +103,8 @@ fn mock_detect_transaction_type<T: Trait>(
         Network::Mainnet,
         0,
         (
-            DEPOSIT_HOT_ADDR.parse::<Address>().unwrap(),
-            DEPOSIT_COLD_ADDR.parse::<Address>().unwrap(),
+            DEPOSIT_HOT_ADDR.parse::<Address>().unwrap().into(),
+            DEPOSIT_COLD_ADDR.parse::<Address>().unwrap().into(),
         ),
         None,
     );
@@ -219,8 +219,8 @@ fn mock_process_tx<T: Trait>(tx: Transaction, prev_tx: Option<Transaction>) -> B
     let network = Network::Mainnet;
     let min_deposit = 0;
     let current_trustee_pair = (
-        DEPOSIT_HOT_ADDR.parse::<Address>().unwrap(),
-        DEPOSIT_COLD_ADDR.parse::<Address>().unwrap(),
+        DEPOSIT_HOT_ADDR.parse::<Address>().unwrap().into(),
+        DEPOSIT_COLD_ADDR.parse::<Address>().unwrap().into(),
     );
     let previous_trustee_pair = None;
     process_tx::<T>(
@@ -310,6 +310,7 @@ fn test_process_tx() {
             withdrawal_id_list: vec![],
             tx: withdraw.clone(),
             trustee_list: vec![],
+            input_amounts: vec![],
         });
 
         let r = mock_process_tx::<Test>(withdraw.clone(), None);
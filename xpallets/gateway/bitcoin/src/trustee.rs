@@ -10,13 +10,13 @@ use sp_std::{convert::TryFrom, prelude::*};
 
 use light_bitcoin::{
     chain::Transaction,
-    crypto::dhash160,
+    crypto::{dhash160, sha256},
     keys::{Address, Public, Type},
     primitives::Bytes,
     script::{Builder, Opcode, Script},
 };
 
-use xp_gateway_bitcoin::extract_output_addr;
+use xp_gateway_bitcoin::{extract_output_addr, Destination, WitnessAddress, WitnessProgram};
 use xp_logging::{debug, error, info};
 use xpallet_assets::Chain;
 use xpallet_gateway_common::{
@@ -28,7 +28,7 @@ use xpallet_gateway_common::{
 
 use crate::{
     tx::{addr2vecu8, ensure_identical, validator::parse_and_check_signed_tx},
-    types::{BtcWithdrawalProposal, VoteResult},
+    types::{BtcTrusteeAddrType, BtcWithdrawalProposal, VoteResult},
     Error, Event, Module, Trait, WithdrawalProposal,
 };
 
@@ -44,7 +44,7 @@ fn current_trustee_addr_pair<T: Trait>(
         .map(|session_info| (session_info.hot_address, session_info.cold_address))
 }
 
-pub fn get_hot_trustee_address<T: Trait>() -> Result<Address, DispatchError> {
+pub fn get_hot_trustee_address<T: Trait>() -> Result<Destination, DispatchError> {
     current_trustee_addr_pair::<T>()
         .and_then(|(addr_info, _)| Module::<T>::verify_btc_address(&addr_info.addr))
 }
@@ -54,7 +54,8 @@ pub fn get_hot_trustee_redeem_script<T: Trait>() -> Result<Script, DispatchError
 }
 
 #[inline]
-pub fn get_current_trustee_address_pair<T: Trait>() -> Result<(Address, Address), DispatchError> {
+pub fn get_current_trustee_address_pair<T: Trait>(
+) -> Result<(Destination, Destination), DispatchError> {
     current_trustee_addr_pair::<T>().map(|(hot_info, cold_info)| {
         (
             Module::<T>::verify_btc_address(&hot_info.addr)
@@ -66,7 +67,8 @@ pub fn get_current_trustee_address_pair<T: Trait>() -> Result<(Address, Address)
 }
 
 #[inline]
-pub fn get_last_trustee_address_pair<T: Trait>() -> Result<(Address, Address), DispatchError> {
+pub fn get_last_trustee_address_pair<T: Trait>() -> Result<(Destination, Destination), DispatchError>
+{
     T::TrusteeSessionProvider::last_trustee_session().map(|session_info| {
         (
             Module::<T>::verify_btc_address(&session_info.hot_address.addr)
@@ -184,9 +186,10 @@ impl<T: Trait> TrusteeForChain<T::AccountId, BtcTrusteeType, BtcTrusteeAddrInfo>
         );
 
         let sig_num = two_thirds_unsafe(trustees.len() as u32);
+        let addr_type = Module::<T>::trustee_addr_type();
 
         let hot_trustee_addr_info: BtcTrusteeAddrInfo =
-            create_multi_address::<T>(&hot_keys, sig_num).ok_or_else(|| {
+            create_multi_address::<T>(&hot_keys, sig_num, addr_type).ok_or_else(|| {
                 error!(
                     "[generate_trustee_session_info] Create hot_addr error, hot_keys:{:?}",
                     hot_keys
@@ -195,7 +198,7 @@ impl<T: Trait> TrusteeForChain<T::AccountId, BtcTrusteeType, BtcTrusteeAddrInfo>
             })?;
 
         let cold_trustee_addr_info: BtcTrusteeAddrInfo =
-            create_multi_address::<T>(&cold_keys, sig_num).ok_or_else(|| {
+            create_multi_address::<T>(&cold_keys, sig_num, addr_type).ok_or_else(|| {
                 error!(
                     "[generate_trustee_session_info] Create cold_addr error, cold_keys:{:?}",
                     cold_keys
@@ -238,6 +241,7 @@ impl<T: Trait> Module<T> {
         who: T::AccountId,
         tx: Transaction,
         withdrawal_id_list: Vec<u32>,
+        input_amounts: Vec<u64>,
     ) -> DispatchResult {
         let withdraw_amount = Self::max_withdrawal_count();
         if withdrawal_id_list.len() > withdraw_amount as usize {
@@ -259,7 +263,8 @@ impl<T: Trait> Module<T> {
         );
 
         // check sig
-        let sigs_count = parse_and_check_signed_tx::<T>(&tx)?;
+        let sigs_count =
+            parse_and_check_signed_tx::<T>(&tx, &input_amounts, withdrawal_id_list.len())?;
         let apply_sig = if sigs_count == 0 {
             false
         } else if sigs_count == 1 {
@@ -282,6 +287,7 @@ impl<T: Trait> Module<T> {
             withdrawal_id_list.clone(),
             tx,
             Vec::new(),
+            input_amounts,
         );
 
         info!("[apply_create_withdraw] Pass the legality check of withdrawal");
@@ -324,7 +330,11 @@ impl<T: Trait> Module<T> {
 
                 // sign
                 // check first and get signatures from commit transaction
-                let sigs_count = parse_and_check_signed_tx::<T>(&tx)?;
+                let sigs_count = parse_and_check_signed_tx::<T>(
+                    &tx,
+                    &proposal.input_amounts,
+                    proposal.withdrawal_id_list.len(),
+                )?;
                 if sigs_count == 0 {
                     error!("[apply_sig_withdraw] Tx sig should not be zero, zero is the source tx without any sig, tx{:?}", tx);
                     return Err(Error::<T>::InvalidSignCount.into());
@@ -409,7 +419,7 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    pub fn force_replace_withdraw_tx(tx: Transaction) -> DispatchResult {
+    pub fn force_replace_withdraw_tx(tx: Transaction, input_amounts: Vec<u64>) -> DispatchResult {
         let mut proposal: BtcWithdrawalProposal<T::AccountId> =
             Self::withdrawal_proposal().ok_or(Error::<T>::NoProposal)?;
 
@@ -424,7 +434,8 @@ impl<T: Trait> Module<T> {
 
         // sign
         // check first and get signatures from commit transaction
-        let sigs_count = parse_and_check_signed_tx::<T>(&tx)?;
+        let sigs_count =
+            parse_and_check_signed_tx::<T>(&tx, &input_amounts, proposal.withdrawal_id_list.len())?;
         ensure!(
             proposal.trustee_list.len() as u32 == sigs_count,
             Error::<T>::InvalidSignCount
@@ -432,6 +443,7 @@ impl<T: Trait> Module<T> {
 
         // replace old transaction
         proposal.tx = tx;
+        proposal.input_amounts = input_amounts;
 
         WithdrawalProposal::<T>::put(proposal);
         Ok(())
@@ -453,6 +465,7 @@ pub fn get_sig_num<T: Trait>() -> (u32, u32) {
 pub(crate) fn create_multi_address<T: Trait>(
     pubkeys: &[Public],
     sig_num: u32,
+    addr_type: BtcTrusteeAddrType,
 ) -> Option<BtcTrusteeAddrInfo> {
     let sum = pubkeys.len() as u32;
     if sig_num > sum {
@@ -481,10 +494,20 @@ pub(crate) fn create_multi_address<T: Trait>(
         .push_opcode(Opcode::OP_CHECKMULTISIG)
         .into_script();
 
-    let addr = Address {
-        kind: Type::P2SH,
-        network: Module::<T>::network_id(),
-        hash: dhash160(&redeem_script),
+    let network = Module::<T>::network_id();
+    let addr: Destination = match addr_type {
+        BtcTrusteeAddrType::P2SH => Address {
+            kind: Type::P2SH,
+            network,
+            hash: dhash160(&redeem_script),
+        }
+        .into(),
+        // the redeem script is used as the witness script
+        BtcTrusteeAddrType::P2WSH => WitnessAddress {
+            network,
+            program: WitnessProgram::ScriptHash(sha256(&redeem_script)),
+        }
+        .into(),
     };
     let script_bytes: Bytes = redeem_script.into();
     Some(BtcTrusteeAddrInfo {
@@ -532,25 +555,25 @@ fn check_withdraw_tx_impl<T: Trait>(
     withdrawal_id_list: &[u32],
 ) -> DispatchResult {
     // withdrawal addr list for account withdrawal application
-    let mut appl_withdrawal_list: Vec<(Address, u64)> = Vec::new();
+    let mut appl_withdrawal_list: Vec<(Destination, u64)> = Vec::new();
     for withdraw_index in withdrawal_id_list.iter() {
         let record = xpallet_gateway_records::Module::<T>::pending_withdrawals(withdraw_index)
             .ok_or(Error::<T>::NoWithdrawalRecord)?;
         // record.addr() is base58
-        // verify btc address would conveRelayedTx a base58 addr to Address
-        let addr: Address = Module::<T>::verify_btc_address(&record.addr())?;
+        // verify btc address would convert a base58 or bech32 addr to Destination
+        let addr: Destination = Module::<T>::verify_btc_address(&record.addr())?;
 
         appl_withdrawal_list.push((addr, record.balance().saturated_into::<u64>()));
     }
     // not allow deposit directly to cold address, only hot address allow
-    let hot_trustee_address: Address = get_hot_trustee_address::<T>()?;
+    let hot_trustee_address: Destination = get_hot_trustee_address::<T>()?;
     // withdrawal addr list for tx outputs
     let btc_withdrawal_fee = Module::<T>::btc_withdrawal_fee();
     let btc_network = Module::<T>::network_id();
     let mut tx_withdraw_list = Vec::new();
     for output in &tx.outputs {
         let addr = extract_output_addr(&output, btc_network).ok_or("not found addr in this out")?;
-        if addr.hash != hot_trustee_address.hash {
+        if !addr.has_same_hash(&hot_trustee_address) {
             // expect change to trustee_addr output
             tx_withdraw_list.push((addr, output.value + btc_withdrawal_fee));
         }
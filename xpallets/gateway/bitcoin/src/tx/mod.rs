@@ -1,6 +1,6 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
-mod secp256k1_verifier;
+pub(crate) mod secp256k1_verifier;
 pub mod validator;
 
 use frame_support::{debug::native, dispatch::DispatchResult, StorageMap, StorageValue};
@@ -9,12 +9,12 @@ use sp_std::prelude::*;
 
 use light_bitcoin::{
     chain::Transaction,
-    keys::{Address, DisplayLayout, Network},
+    keys::{DisplayLayout, Network},
     primitives::{hash_rev, H256},
 };
 
 use chainx_primitives::AssetId;
-use xp_gateway_bitcoin::{BtcDepositInfo, BtcTxMetaType, BtcTxTypeDetector};
+use xp_gateway_bitcoin::{BtcDepositInfo, BtcTxMetaType, BtcTxTypeDetector, Destination};
 use xp_gateway_common::AccountExtractor;
 use xp_logging::{debug, error, info, warn};
 use xpallet_assets::ChainT;
@@ -33,8 +33,8 @@ pub fn process_tx<T: Trait>(
     prev_tx: Option<Transaction>,
     network: Network,
     min_deposit: u64,
-    current_trustee_pair: (Address, Address),
-    last_trustee_pair: Option<(Address, Address)>,
+    current_trustee_pair: (Destination, Destination),
+    last_trustee_pair: Option<(Destination, Destination)>,
 ) -> BtcTxState {
     let btc_tx_detector = BtcTxTypeDetector::new(
         network,
@@ -168,7 +168,7 @@ pub fn remove_pending_deposit<T: Trait>(input_address: &BtcAddress, who: &T::Acc
     }
 }
 
-fn insert_pending_deposit<T: Trait>(input_address: &Address, txid: H256, balance: u64) {
+fn insert_pending_deposit<T: Trait>(input_address: &Destination, txid: H256, balance: u64) {
     let addr_bytes = addr2vecu8(input_address);
 
     let cache = BtcDepositCache { txid, balance };
@@ -288,7 +288,12 @@ pub fn ensure_identical<T: Trait>(tx1: &Transaction, tx2: &Transaction) -> Dispa
     Err(Error::<T>::MismatchedTx.into())
 }
 
+/// Encodes the address as base58 for the legacy address or bech32/bech32m for the
+/// native SegWit address.
 #[inline]
-pub fn addr2vecu8(addr: &Address) -> Vec<u8> {
-    bs58::encode(&*addr.layout()).into_vec()
+pub fn addr2vecu8(addr: &Destination) -> Vec<u8> {
+    match addr {
+        Destination::Legacy(addr) => bs58::encode(&*addr.layout()).into_vec(),
+        Destination::Witness(addr) => addr.to_bech32(),
+    }
 }
@@ -2,7 +2,7 @@
 
 use frame_support::dispatch::DispatchResult;
 
-use light_bitcoin::{chain::Transaction, primitives::Bytes};
+use light_bitcoin::{chain::Transaction, primitives::Bytes, script::SignatureVersion};
 
 use crate::types::BtcTxVerifier;
 use crate::{Error, Module, Trait};
@@ -16,6 +16,8 @@ mod runtime_interface {
         _tx: &Transaction,
         _script_pubkey: &Bytes,
         _index: usize,
+        _input_amount: u64,
+        _version: SignatureVersion,
     ) -> DispatchResult {
         Err(Error::<T>::VerifySignFailed)?
     }
@@ -27,14 +29,28 @@ pub fn verify_sig<T: Trait>(
     tx: &Transaction,
     script_pubkey: &Bytes,
     index: usize,
+    input_amount: u64,
+    version: SignatureVersion,
 ) -> DispatchResult {
     match Module::<T>::verifier() {
-        BtcTxVerifier::Recover => {
-            recover_verifier::verify_sig_impl::<T>(sig, pubkey, tx, script_pubkey, index)
-        }
-        BtcTxVerifier::RuntimeInterface => {
-            runtime_interface::verify_sig_impl::<T>(sig, pubkey, tx, script_pubkey, index)
-        }
+        BtcTxVerifier::Recover => recover_verifier::verify_sig_impl::<T>(
+            sig,
+            pubkey,
+            tx,
+            script_pubkey,
+            index,
+            input_amount,
+            version,
+        ),
+        BtcTxVerifier::RuntimeInterface => runtime_interface::verify_sig_impl::<T>(
+            sig,
+            pubkey,
+            tx,
+            script_pubkey,
+            index,
+            input_amount,
+            version,
+        ),
         #[cfg(any(feature = "runtime-benchmarks", test))]
         BtcTxVerifier::Test => Ok(()),
     }
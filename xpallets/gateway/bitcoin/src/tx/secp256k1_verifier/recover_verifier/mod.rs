@@ -57,12 +57,14 @@ pub fn verify_sig_impl<T: Trait>(
     tx: &Transaction,
     script_pubkey: &Bytes,
     index: usize,
+    input_amount: u64,
+    version: SignatureVersion,
 ) -> DispatchResult {
     let tx_signer: TransactionInputSigner = tx.clone().into();
-    // TODO WARNNING!!! when support WitnessV0, the `input_amount` must set value
+    // NOTE: the `input_amount` is only committed by the signature of `WitnessV0` (BIP143).
     let checker = TransactionSignatureChecker::<T> {
         input_index: index,
-        input_amount: 0,
+        input_amount,
         signer: tx_signer,
         _marker: Default::default(),
     };
@@ -71,13 +73,7 @@ pub fn verify_sig_impl<T: Trait>(
     let pubkey = Public::try_from(pubkey.as_slice()).map_err(|_| Error::<T>::InvalidPublicKey)?;
 
     let script_code: Script = script_pubkey.clone().into();
-    checker.check_signature(
-        &signature,
-        &pubkey,
-        &script_code,
-        sighashtype,
-        SignatureVersion::Base,
-    )
+    checker.check_signature(&signature, &pubkey, &script_code, sighashtype, version)
 }
 
 pub struct TransactionSignatureChecker<T: Trait> {
@@ -6,11 +6,20 @@ use frame_support::{
 };
 use sp_std::prelude::Vec;
 
-use light_bitcoin::{chain::Transaction, primitives::H256, script::Script};
+use light_bitcoin::{
+    chain::Transaction,
+    primitives::{Bytes, H256},
+    script::{Script, SignatureVersion},
+};
 
+use xp_gateway_bitcoin::Destination;
 use xp_logging::{debug, error};
 
-use crate::{trustee::get_hot_trustee_redeem_script, types::BtcRelayedTx, Error, Trait};
+use crate::{
+    trustee::{get_hot_trustee_address, get_hot_trustee_redeem_script},
+    types::BtcRelayedTx,
+    Error, Trait,
+};
 
 pub fn validate_transaction<T: Trait>(
     tx: &BtcRelayedTx,
@@ -59,16 +68,70 @@ pub fn validate_transaction<T: Trait>(
 }
 
 /// Check signed transactions
-pub fn parse_and_check_signed_tx<T: Trait>(tx: &Transaction) -> Result<u32, DispatchError> {
+///
+/// The signatures of each input are checked in the way of the address it spends, rather than
+/// the current trustee address type: the same trustee keys share the redeem script between the
+/// P2SH and P2WSH addresses, so a withdrawal around the switch of `TrusteeAddrType` may spend
+/// the UTXOs of both.
+pub fn parse_and_check_signed_tx<T: Trait>(
+    tx: &Transaction,
+    input_amounts: &[u64],
+    withdrawal_count: usize,
+) -> Result<u32, DispatchError> {
     let redeem_script = get_hot_trustee_redeem_script::<T>()?;
-    parse_and_check_signed_tx_impl::<T>(tx, redeem_script)
+    let is_witness = matches!(get_hot_trustee_address::<T>()?, Destination::Witness(_));
+    if is_witness || !input_amounts.is_empty() {
+        let btc_withdrawal_fee = crate::Module::<T>::btc_withdrawal_fee();
+        check_input_amounts::<T>(tx, input_amounts, withdrawal_count, btc_withdrawal_fee)?;
+    }
+    parse_and_check_signed_tx_impl::<T>(tx, redeem_script, input_amounts)
+}
+
+/// Check the `input_amounts` supplied by the proposer against the withdrawal tx.
+///
+/// The amounts are not known on chain, but they must cover the outputs of `tx`, and
+/// the miner fee they imply can not exceed the withdrawal fee paid by the applications.
+pub fn check_input_amounts<T: Trait>(
+    tx: &Transaction,
+    input_amounts: &[u64],
+    withdrawal_count: usize,
+    btc_withdrawal_fee: u64,
+) -> DispatchResult {
+    ensure!(
+        input_amounts.len() == tx.inputs.len(),
+        Error::<T>::InvalidInputAmounts
+    );
+    let total_input = input_amounts
+        .iter()
+        .try_fold(0u64, |acc, amount| acc.checked_add(*amount))
+        .ok_or(Error::<T>::InvalidInputAmounts)?;
+    let total_output = tx
+        .outputs
+        .iter()
+        .try_fold(0u64, |acc, output| acc.checked_add(output.value))
+        .ok_or(Error::<T>::InvalidInputAmounts)?;
+    let max_fee = btc_withdrawal_fee.saturating_mul(withdrawal_count as u64);
+    match total_input.checked_sub(total_output) {
+        Some(fee) if fee <= max_fee => Ok(()),
+        _ => {
+            error!(
+                "[check_input_amounts] Input amounts not match to the withdrawal tx, total input:{}, total output:{}, max fee:{}",
+                total_input, total_output, max_fee
+            );
+            Err(Error::<T>::InvalidInputAmounts.into())
+        }
+    }
 }
 
 /// for test convenient
-#[inline]
+///
+/// The inputs spending the P2SH multisig carry the signatures in the script sig, and the
+/// inputs spending the P2WSH multisig carry them in the witness, which commit to the
+/// `input_amounts` (BIP143).
 pub fn parse_and_check_signed_tx_impl<T: Trait>(
     tx: &Transaction,
     script: Script,
+    input_amounts: &[u64],
 ) -> Result<u32, DispatchError> {
     let (pubkeys, _, _) = script
         .parse_redeem_script()
@@ -77,9 +140,44 @@ pub fn parse_and_check_signed_tx_impl<T: Trait>(
 
     let mut input_signs = Vec::new();
     // any input check meet error would return
-    for i in 0..tx.inputs.len() {
+    for (i, input) in tx.inputs.iter().enumerate() {
+        if !input.script_witness.is_empty() {
+            // the script sig of the native SegWit input must be empty
+            ensure!(input.script_sig.is_empty(), Error::<T>::BadSignature);
+            let input_amount = *input_amounts
+                .get(i)
+                .ok_or(Error::<T>::InvalidInputAmounts)?;
+            // the witness of multisig: `<empty> <sig>... <witness script>`
+            let sigs = match input.script_witness.split_last() {
+                Some((witness_script, [dummy, sigs @ ..]))
+                    if dummy.is_empty() && *witness_script == bytes_redeem_script =>
+                {
+                    sigs
+                }
+                _ => {
+                    error!(
+                        "[parse_and_check_signed_tx] Invalid witness, tx:{:?}, input:{:?}",
+                        tx, i
+                    );
+                    return Err(Error::<T>::BadSignature.into());
+                }
+            };
+
+            verify_input_sigs::<T>(
+                tx,
+                i,
+                sigs,
+                &pubkeys,
+                &bytes_redeem_script,
+                input_amount,
+                SignatureVersion::WitnessV0,
+            )?;
+            input_signs.push(sigs.len());
+            continue;
+        }
+
         // parse sigs from transaction inputs
-        let script: Script = tx.inputs[i].script_sig.clone().into();
+        let script: Script = input.script_sig.clone().into();
         if script.len() < 2 {
             // if script length less than 2, it must has no sig in input, use 0 to represent it
             input_signs.push(0);
@@ -89,21 +187,56 @@ pub fn parse_and_check_signed_tx_impl<T: Trait>(
             .extract_multi_scriptsig()
             .map_err(|_| Error::<T>::BadSignature)?;
 
-        for sig in sigs.iter() {
-            let verify = pubkeys.iter().any(|pubkey| {
-                super::secp256k1_verifier::verify_sig::<T>(sig, pubkey, tx, &bytes_redeem_script, i)
-                    .is_ok()
-            });
-            if !verify {
-                error!(
-                    "[parse_and_check_signed_tx] Verify sig failed, tx:{:?}, input:{:?}, bytes_redeem_script:{:?}",
-                    tx, i, bytes_redeem_script
-                );
-                return Err(Error::<T>::VerifySignFailed.into());
-            }
-        }
+        verify_input_sigs::<T>(
+            tx,
+            i,
+            &sigs,
+            &pubkeys,
+            &bytes_redeem_script,
+            0,
+            SignatureVersion::Base,
+        )?;
         input_signs.push(sigs.len());
     }
+    ensure_same_sign_count::<T>(&input_signs)
+}
+
+/// Verify that every sig of the input `index` is signed by one of `pubkeys`.
+fn verify_input_sigs<T: Trait>(
+    tx: &Transaction,
+    index: usize,
+    sigs: &[Bytes],
+    pubkeys: &[Bytes],
+    bytes_redeem_script: &Bytes,
+    input_amount: u64,
+    version: SignatureVersion,
+) -> DispatchResult {
+    for sig in sigs.iter() {
+        let verify = pubkeys.iter().any(|pubkey| {
+            super::secp256k1_verifier::verify_sig::<T>(
+                sig,
+                pubkey,
+                tx,
+                bytes_redeem_script,
+                index,
+                input_amount,
+                version,
+            )
+            .is_ok()
+        });
+        if !verify {
+            error!(
+                "[parse_and_check_signed_tx] Verify sig failed, tx:{:?}, input:{:?}, bytes_redeem_script:{:?}",
+                tx, index, bytes_redeem_script
+            );
+            return Err(Error::<T>::VerifySignFailed.into());
+        }
+    }
+    Ok(())
+}
+
+/// Return the sigs count if all inputs have the same sigs count.
+fn ensure_same_sign_count<T: Trait>(input_signs: &[usize]) -> Result<u32, DispatchError> {
     // the list length must more than one, due to must have inputs; qed
     ensure!(!input_signs.is_empty(), Error::<T>::InvalidSignCount);
 
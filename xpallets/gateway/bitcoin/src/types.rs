@@ -9,16 +9,17 @@ use sp_std::prelude::*;
 
 use light_bitcoin::{
     chain::{BlockHeader as BtcHeader, Transaction as BtcTransaction},
-    keys::Address,
     merkle::PartialMerkleTree,
     primitives::{Compact, H256},
 };
 
 use chainx_primitives::ReferralId;
-use xp_gateway_bitcoin::BtcTxType;
+use xp_gateway_bitcoin::{BtcTxType, Destination};
 
-/// BtcAddress is an bitcoin address encoded in base58
-/// like: "1Nekoo5VTe7yQQ8WFqrva2UbdyRMVYCP1t" or "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"
+/// BtcAddress is an bitcoin address encoded in base58, bech32 or bech32m
+/// like: "1Nekoo5VTe7yQQ8WFqrva2UbdyRMVYCP1t", "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+/// "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+/// or "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0"
 /// not layout state or public or else.
 pub type BtcAddress = Vec<u8>;
 
@@ -59,12 +60,15 @@ pub struct BtcHeaderIndex {
 }
 
 #[derive(PartialEq, Clone, Copy, Eq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct BtcTxState {
     pub tx_type: BtcTxType,
     pub result: BtcTxResult,
 }
 
 #[derive(PartialEq, Clone, Copy, Eq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum BtcTxResult {
     Success,
     Failure,
@@ -74,7 +78,7 @@ pub enum AccountInfo<AccountId> {
     /// A value of type `L`.
     Account((AccountId, Option<ReferralId>)),
     /// A value of type `R`.
-    Address(Address),
+    Address(Destination),
 }
 
 #[derive(PartialEq, Clone, Encode, Decode, Default, RuntimeDebug)]
@@ -89,6 +93,9 @@ pub struct BtcWithdrawalProposal<AccountId> {
     pub withdrawal_id_list: Vec<u32>,
     pub tx: BtcTransaction,
     pub trustee_list: Vec<(AccountId, bool)>,
+    /// The values of the previous outputs spent by the inputs of `tx`, which are
+    /// required by the signature (BIP143) of the P2WSH trustee address only.
+    pub input_amounts: Vec<u64>,
 }
 
 impl<AccountId> BtcWithdrawalProposal<AccountId> {
@@ -97,12 +104,14 @@ impl<AccountId> BtcWithdrawalProposal<AccountId> {
         withdrawal_id_list: Vec<u32>,
         tx: BtcTransaction,
         trustee_list: Vec<(AccountId, bool)>,
+        input_amounts: Vec<u64>,
     ) -> Self {
         Self {
             sig_state,
             withdrawal_id_list,
             tx,
             trustee_list,
+            input_amounts,
         }
     }
 }
@@ -174,6 +183,22 @@ impl BtcParams {
     }
 }
 
+/// The type of the multisig address generated for the trustees.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum BtcTrusteeAddrType {
+    /// Legacy multisig address, pay to script hash.
+    P2SH,
+    /// Native SegWit multisig address, pay to witness script hash.
+    P2WSH,
+}
+
+impl Default for BtcTrusteeAddrType {
+    fn default() -> Self {
+        BtcTrusteeAddrType::P2SH
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum BtcTxVerifier {
@@ -189,3 +214,20 @@ impl Default for BtcTxVerifier {
         BtcTxVerifier::Recover
     }
 }
+
+/// The storage version of Bitcoin Gateway.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum Releases {
+    /// `BtcWithdrawalProposal` has no `input_amounts`.
+    V1_0_0,
+    /// `BtcWithdrawalProposal` has the `input_amounts` appended, which are required by the
+    /// P2WSH trustee address, `create_withdraw_tx` and `force_replace_proposal_tx` take the
+    /// `input_amounts` as the last argument since then.
+    V2_0_0,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
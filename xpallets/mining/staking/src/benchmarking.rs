@@ -184,6 +184,17 @@ benchmarks! {
     verify {
         assert_eq!(SessionsPerEra::get(), c);
     }
+
+    set_mining_distribution_ratio {
+        let c = MiningDistribution {
+            asset: 1,
+            staking: 1,
+            dex: 1,
+        };
+    }: _(RawOrigin::Root, c)
+    verify {
+        assert_eq!(MiningDistributionRatio::get(), c);
+    }
 }
 
 #[cfg(test)]
@@ -207,6 +218,7 @@ mod tests {
             assert_ok!(test_benchmark_set_minimum_validator_count::<Test>());
             assert_ok!(test_benchmark_set_bonding_duration::<Test>());
             assert_ok!(test_benchmark_set_validator_bonding_duration::<Test>());
+            assert_ok!(test_benchmark_set_mining_distribution_ratio::<Test>());
         });
     }
 }
@@ -15,6 +15,7 @@
 mod constants;
 mod election;
 mod impls;
+mod migration;
 mod reward;
 mod rpc;
 mod slashing;
@@ -34,6 +35,7 @@ use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, ensure,
     storage::IterableStorageMap,
     traits::{Currency, ExistenceRequirement, Get, LockableCurrency, WithdrawReasons},
+    weights::Weight,
 };
 use frame_system::{ensure_root, ensure_signed};
 use sp_runtime::{
@@ -46,7 +48,7 @@ use chainx_primitives::ReferralId;
 use xp_logging::debug;
 pub use xp_mining_common::RewardPotAccountFor;
 use xp_mining_common::{Claim, ComputeMiningWeight, Delta, ZeroMiningWeightError};
-use xp_mining_staking::{AssetMining, SessionIndex, UnbondedIndex};
+use xp_mining_staking::{AssetMining, DexMining, SessionIndex, UnbondedIndex};
 use xpallet_support::traits::TreasuryAccount;
 
 use self::constants::*;
@@ -74,6 +76,9 @@ pub trait Trait: frame_system::Trait {
     /// Asset mining integration.
     type AssetMining: AssetMining<BalanceOf<Self>>;
 
+    /// DEX mining integration.
+    type DexMining: DexMining<BalanceOf<Self>>;
+
     /// Generate the reward pot account for a validator.
     type DetermineRewardPotAccount: RewardPotAccountFor<Self::AccountId, Self::AccountId>;
 
@@ -147,7 +152,7 @@ decl_storage! {
         /// (Treasury, Staking)
         pub GlobalDistributionRatio get(fn global_distribution_ratio): GlobalDistribution;
 
-        /// (Staker, Asset Miners)
+        /// (Asset Miners, Staker, DEX Miners)
         pub MiningDistributionRatio get(fn mining_distribution_ratio): MiningDistribution;
 
         /// The map from (wannabe) validator key to the profile of that validator.
@@ -204,24 +209,32 @@ decl_storage! {
         ///
         /// Immortals will be intialized from the genesis validators.
         Immortals get(fn immortals): Option<Vec<T::AccountId>>;
+
+        /// Storage version of the pallet.
+        ///
+        /// New networks start with the last version.
+        pub StorageVersion get(fn storage_version)
+            build(|_: &GenesisConfig<T>| Releases::V2_0_0): Releases;
     }
 
     add_extra_genesis {
         config(validators): Vec<(T::AccountId, ReferralId, BalanceOf<T>)>;
         config(glob_dist_ratio): (u32, u32);
-        config(mining_ratio): (u32, u32);
+        config(mining_ratio): (u32, u32, u32);
         config(candidate_requirement): (BalanceOf<T>, BalanceOf<T>);
         build(|config: &GenesisConfig<T>| {
             assert!(config.glob_dist_ratio.0 + config.glob_dist_ratio.1 > 0);
-            assert!(config.mining_ratio.0 + config.mining_ratio.1 > 0);
             GlobalDistributionRatio::put(GlobalDistribution {
                 treasury: config.glob_dist_ratio.0,
                 mining: config.glob_dist_ratio.1,
             });
-            MiningDistributionRatio::put(MiningDistribution {
+            let mining_distribution = MiningDistribution {
                 asset: config.mining_ratio.0,
                 staking: config.mining_ratio.1,
-            });
+                dex: config.mining_ratio.2,
+            };
+            assert!(mining_distribution.is_valid());
+            MiningDistributionRatio::put(mining_distribution);
             ValidatorCandidateRequirement::<T>::put(BondRequirement {
                 self_bonded: config.candidate_requirement.0,
                 total: config.candidate_requirement.1,
@@ -273,6 +286,8 @@ decl_event!(
         ForceChilled(SessionIndex, Vec<AccountId>),
         /// Unlock the unbonded withdrawal by force. [account]
         ForceAllWithdrawn(AccountId),
+        /// The shares of the mining reward have been updated. [mining_distribution]
+        MiningDistributionRatioUpdated(MiningDistribution),
     }
 );
 
@@ -319,6 +334,8 @@ decl_error! {
         XssCheckFailed,
         /// Failed to allocate the dividend.
         AllocateDividendFailed,
+        /// The shares of the mining distribution are either all zero or overflowing.
+        InvalidDistributionRatio,
     }
 }
 
@@ -340,6 +357,10 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            migration::on_runtime_upgrade::<T>()
+        }
+
         /// Nominate the `target` with `value` of the origin account's balance locked.
         #[weight = T::WeightInfo::bond()]
         pub fn bond(origin, target: <T::Lookup as StaticLookup>::Source, #[compact] value: BalanceOf<T>) {
@@ -566,6 +587,15 @@ decl_module! {
                 Self::set_lock(&who, new_lock);
             }
         }
+
+        /// Set the shares of the mining reward for (Asset Miners, Staker, DEX Miners).
+        #[weight = T::WeightInfo::set_mining_distribution_ratio()]
+        fn set_mining_distribution_ratio(origin, new: MiningDistribution) {
+            ensure_root(origin)?;
+            ensure!(new.is_valid(), Error::<T>::InvalidDistributionRatio);
+            MiningDistributionRatio::put(new);
+            Self::deposit_event(Event::<T>::MiningDistributionRatioUpdated(new));
+        }
     }
 }
 
@@ -0,0 +1,41 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+//! This module takes care of the storage migrations of Staking.
+
+use super::*;
+use xp_logging::{info, warn};
+
+/// Applies the migrations from the current storage version.
+pub(crate) fn on_runtime_upgrade<T: Trait>() -> Weight {
+    let mut weight = T::DbWeight::get().reads(1);
+
+    if Module::<T>::storage_version() == Releases::V1_0_0 {
+        weight = weight.saturating_add(migrate_mining_distribution_ratio::<T>());
+        StorageVersion::put(Releases::V2_0_0);
+        weight = weight.saturating_add(T::DbWeight::get().writes(1));
+    }
+
+    weight
+}
+
+/// Appends the DEX share to `MiningDistributionRatio`.
+///
+/// The ratio used to be (Asset Miners, Staker), which can't be decoded as the
+/// three shares any more. DEX Mining gets no share until it's set by root.
+fn migrate_mining_distribution_ratio<T: Trait>() -> Weight {
+    let translated = MiningDistributionRatio::translate::<(u32, u32), _>(|old| {
+        old.map(|(asset, staking)| MiningDistribution {
+            asset,
+            staking,
+            dex: 0,
+        })
+    });
+    match translated {
+        Ok(ratio) => info!(
+            "[migrate_mining_distribution_ratio] mining_distribution_ratio:{:?}",
+            ratio
+        ),
+        Err(_) => warn!("[migrate_mining_distribution_ratio] failed to decode the old ratio"),
+    }
+    T::DbWeight::get().reads_writes(1, 1)
+}
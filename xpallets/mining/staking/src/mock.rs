@@ -210,6 +210,7 @@ impl Trait for Test {
     type Currency = Balances;
     type Event = MetaEvent;
     type AssetMining = ();
+    type DexMining = ();
     type MigrationSessionOffset = MigrationSessionOffset;
     type SessionDuration = SessionDuration;
     type MinimumReferralId = MinimumReferralId;
@@ -276,7 +277,7 @@ impl ExtBuilder {
             sessions_per_era: 3,
             vesting_account: VESTING_ACCOUNT,
             glob_dist_ratio: (12, 88),
-            mining_ratio: (10, 90),
+            mining_ratio: (10, 90, 0),
             ..Default::default()
         }
         .assimilate_storage(&mut storage);
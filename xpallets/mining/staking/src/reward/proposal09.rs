@@ -107,10 +107,15 @@ impl<T: Trait> Module<T> {
     ) -> Vec<(T::AccountId, BalanceOf<T>)> {
         let mining_distribution = Self::mining_distribution_ratio();
         let staking_reward = mining_distribution.calc_staking_reward::<T>(total);
-        let max_asset_mining_reward = total - staking_reward;
+        let dex_reward = mining_distribution.calc_dex_reward::<T>(total);
+        let max_asset_mining_reward = total - staking_reward - dex_reward;
 
         let validator_rewards = Self::distribute_to_active_validators(staking_reward);
 
+        if !dex_reward.is_zero() {
+            T::DexMining::reward(dex_reward);
+        }
+
         let real_asset_mining_reward = if let Some(treasury_extra) =
             mining_distribution.has_treasury_extra::<T>(max_asset_mining_reward)
         {
@@ -152,6 +157,7 @@ impl<T: Trait> Module<T> {
         // -> Mining
         //      |-> XBTC(Asset Mining)
         //      |-> PCX(Staking)
+        //      |-> XSpot(DEX Mining)
         if !mining_reward.is_zero() {
             return Self::distribute_mining_rewards(mining_reward, &treasury_account);
         }
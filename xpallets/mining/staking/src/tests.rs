@@ -2,7 +2,11 @@
 
 use super::*;
 use crate::mock::*;
-use frame_support::{assert_err, assert_ok, traits::OnInitialize};
+use codec::Encode;
+use frame_support::{
+    assert_err, assert_ok,
+    traits::{OnInitialize, OnRuntimeUpgrade},
+};
 
 fn t_issue_pcx(to: AccountId, value: Balance) {
     XStaking::mint(&to, value);
@@ -886,3 +890,73 @@ fn migration_session_offset_should_work() {
         }
     });
 }
+
+#[test]
+fn set_mining_distribution_ratio_should_work() {
+    ExtBuilder::default().build_and_execute(|| {
+        let zero = MiningDistribution {
+            asset: 0,
+            staking: 0,
+            dex: 0,
+        };
+        assert_err!(
+            XStaking::set_mining_distribution_ratio(Origin::root(), zero),
+            Error::<Test>::InvalidDistributionRatio
+        );
+        let overflowing = MiningDistribution {
+            asset: u32::max_value(),
+            staking: 1,
+            dex: 0,
+        };
+        assert_err!(
+            XStaking::set_mining_distribution_ratio(Origin::root(), overflowing),
+            Error::<Test>::InvalidDistributionRatio
+        );
+
+        let new = MiningDistribution {
+            asset: 10,
+            staking: 80,
+            dex: 10,
+        };
+        assert_ok!(XStaking::set_mining_distribution_ratio(Origin::root(), new));
+        assert_eq!(XStaking::mining_distribution_ratio(), new);
+        assert!(System::events().iter().any(|record| record.event
+            == MetaEvent::staking(Event::<Test>::MiningDistributionRatioUpdated(new))));
+        assert_eq!(
+            new.calc_dex_reward::<Test>(1000),
+            new.calc_staking_reward::<Test>(1000) / 8
+        );
+    });
+}
+
+#[test]
+fn mining_distribution_ratio_should_be_migrated_on_runtime_upgrade() {
+    ExtBuilder::default().build_and_execute(|| {
+        // The ratio of (Asset Miners, Staker) before the DEX share was added.
+        frame_support::storage::unhashed::put_raw(
+            &MiningDistributionRatio::hashed_key(),
+            &(10u32, 90u32).encode(),
+        );
+        StorageVersion::put(Releases::V1_0_0);
+        assert_eq!(
+            XStaking::mining_distribution_ratio(),
+            MiningDistribution::default()
+        );
+
+        XStaking::on_runtime_upgrade();
+
+        assert_eq!(
+            XStaking::mining_distribution_ratio(),
+            MiningDistribution {
+                asset: 10,
+                staking: 90,
+                dex: 0,
+            }
+        );
+        assert_eq!(XStaking::storage_version(), Releases::V2_0_0);
+
+        // Migrating again is a no-op.
+        XStaking::on_runtime_upgrade();
+        assert_eq!(XStaking::mining_distribution_ratio().staking, 90);
+    });
+}
@@ -165,13 +165,31 @@ impl GlobalDistribution {
 pub struct MiningDistribution {
     pub asset: u32,
     pub staking: u32,
+    pub dex: u32,
 }
 
 impl MiningDistribution {
+    fn total_shares(&self) -> u32 {
+        self.asset + self.staking + self.dex
+    }
+
+    /// Returns true if the rewards can be split by these shares, i.e., the
+    /// total shares are positive and don't overflow.
+    pub fn is_valid(&self) -> bool {
+        self.asset
+            .checked_add(self.staking)
+            .and_then(|shares| shares.checked_add(self.dex))
+            .map_or(false, |total| total > 0)
+    }
+
     /// Returns the reward for Staking given the total reward according to the Staking proportion.
     pub fn calc_staking_reward<T: Trait>(&self, reward: BalanceOf<T>) -> BalanceOf<T> {
-        reward.saturating_mul(self.staking.saturated_into())
-            / (self.asset + self.staking).saturated_into()
+        reward.saturating_mul(self.staking.saturated_into()) / self.total_shares().saturated_into()
+    }
+
+    /// Returns the reward for DEX Mining given the total reward according to the DEX proportion.
+    pub fn calc_dex_reward<T: Trait>(&self, reward: BalanceOf<T>) -> BalanceOf<T> {
+        reward.saturating_mul(self.dex.saturated_into()) / self.total_shares().saturated_into()
     }
 
     /// Return a tuple (m1, m2) for comparing whether asset_mining_power are reaching the upper limit.
@@ -222,6 +240,21 @@ impl MiningDistribution {
     }
 }
 
+/// The storage version of Staking.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum Releases {
+    /// `MiningDistribution` is (Asset Miners, Staker).
+    V1_0_0,
+    /// `MiningDistribution` has the share of DEX Miners appended.
+    V2_0_0,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
+
 /// Result of performing a slash operation.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
 pub enum SlashOutcome<Balance> {
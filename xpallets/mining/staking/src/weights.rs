@@ -19,6 +19,10 @@
 // --output=./xpallets/mining/staking/src/weights.rs
 // --template=./scripts/xpallet-weight-template.hbs
 
+// NOTE: The weight of set_mining_distribution_ratio has not been produced by the command
+// above yet, it takes the benchmarked weight of the other setters of a single value and
+// must be regenerated from the case in `benchmarking.rs`.
+
 #![allow(unused_parens)]
 #![allow(unused_imports)]
 
@@ -44,6 +48,7 @@ pub trait WeightInfo {
     fn set_validator_bonding_duration() -> Weight;
     fn set_minimum_penalty() -> Weight;
     fn set_sessions_per_era() -> Weight;
+    fn set_mining_distribution_ratio() -> Weight;
 }
 
 /// Weights for xpallet_mining_staking using the Substrate node and recommended hardware.
@@ -107,6 +112,9 @@ impl<T: frame_system::Trait> WeightInfo for SubstrateWeight<T> {
     fn set_sessions_per_era() -> Weight {
         (4_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
     }
+    fn set_mining_distribution_ratio() -> Weight {
+        (4_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
 }
 
 // For backwards compatibility and tests
@@ -169,4 +177,7 @@ impl WeightInfo for () {
     fn set_sessions_per_era() -> Weight {
         (4_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
     }
+    fn set_mining_distribution_ratio() -> Weight {
+        (4_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
 }